@@ -7,5 +7,9 @@ until pg_isready -h postgres -U postgres -d mimic; do
   sleep 2
 done
 
+# schema.sql 可重复执行：新库由 postgres 的 initdb 建表，旧库在这里补列
+echo "Applying schema upgrades..."
+psql "$DATABASE_URL" -v ON_ERROR_STOP=1 -q -f /app/schema.sql
+
 echo "PostgreSQL is ready, starting mimic-backend..."
 exec /app/mimic-backend
@@ -13,6 +13,10 @@ CREATE TABLE IF NOT EXISTS themes (
     ai_prompt_style TEXT NOT NULL,
     spawn_rate INT DEFAULT 5,
     max_imposters INT DEFAULT 5,
+    -- AI 鱼出场策略，见 services/spawn_policy.rs
+    spawn_policy JSONB NOT NULL DEFAULT '{"type": "fixed_ratio"}',
+    -- AI 作者名的语种与主题自带名字，见 services/author_names.rs
+    author_corpus JSONB NOT NULL DEFAULT '{"locales": ["zh"]}',
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -29,6 +33,10 @@ CREATE TABLE IF NOT EXISTS rooms (
     turbidity FLOAT DEFAULT 0.0,
     voting_started_at TIMESTAMPTZ,
     voting_ends_at TIMESTAMPTZ,
+    ended_at TIMESTAMPTZ,
+    -- 房间相位、计数最近一次变化时的版本号，由下方触发器从全局序列取号；
+    -- 房间当前版本号还要算上其鱼的 state_seq，见 ws/room_sync.rs
+    state_seq BIGINT NOT NULL DEFAULT 0,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -50,11 +58,18 @@ CREATE TABLE IF NOT EXISTS drawings (
     scale FLOAT DEFAULT 1.0,
     flip_x BOOLEAN DEFAULT FALSE,
     vote_count INT DEFAULT 0,
+    -- 跨轮累计得票，不随每轮投票清零（撤票时扣回）
+    votes_received INT DEFAULT 0,
     is_eliminated BOOLEAN DEFAULT FALSE,
     eliminated_at TIMESTAMPTZ,
     report_count INT DEFAULT 0,
     is_hidden BOOLEAN DEFAULT FALSE,
     session_id VARCHAR(100),
+    -- 来自预置鱼池时的预置鱼 id
+    preset_fish_id SMALLINT,
+    -- 最近一次变化（新增、票数、淘汰、隐藏）时取的版本号
+    state_seq BIGINT NOT NULL DEFAULT 0,
+    created_seq BIGINT NOT NULL DEFAULT 0,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -90,7 +105,9 @@ CREATE TABLE IF NOT EXISTS comments (
 -- AI 生成任务表
 CREATE TABLE IF NOT EXISTS ai_tasks (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-    room_id UUID REFERENCES rooms(id) NOT NULL,
+    -- 旧任务按房间生成；新任务进入主题共享池，只记 theme_id
+    room_id UUID REFERENCES rooms(id),
+    theme_id UUID REFERENCES themes(id),
     drawing_id UUID REFERENCES drawings(id),
     status VARCHAR(20) DEFAULT 'pending',
     n8n_execution_id VARCHAR(100),
@@ -101,6 +118,14 @@ CREATE TABLE IF NOT EXISTS ai_tasks (
     generated_description VARCHAR(60),
     error_message TEXT,
     retry_count INT DEFAULT 0,
+    -- 触发 n8n 的原始请求，重试时原样重发
+    request_payload JSONB,
+    -- 回调必须原样带回的随机串
+    callback_nonce VARCHAR(64),
+    -- 拟态任务参考的人类画作
+    style_reference_ids UUID[],
+    dispatched_at TIMESTAMPTZ,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
     created_at TIMESTAMPTZ DEFAULT NOW(),
     completed_at TIMESTAMPTZ
 );
@@ -112,6 +137,9 @@ CREATE TABLE IF NOT EXISTS human_fish (
     metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
     weight INT NOT NULL DEFAULT 1,
     is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    content_hash VARCHAR(64),
+    source_drawing_id UUID REFERENCES drawings(id),
+    auto_calibrate BOOLEAN NOT NULL DEFAULT TRUE,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -123,10 +151,34 @@ CREATE TABLE IF NOT EXISTS ai_fish (
     metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
     weight INT NOT NULL DEFAULT 1,
     is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    content_hash VARCHAR(64),
+    source_drawing_id UUID REFERENCES drawings(id),
+    auto_calibrate BOOLEAN NOT NULL DEFAULT TRUE,
     created_at TIMESTAMPTZ DEFAULT NOW(),
     updated_at TIMESTAMPTZ DEFAULT NOW()
 );
 
+-- 单人模式关卡定义（theme_id 为空表示通用关卡）
+CREATE TABLE IF NOT EXISTS single_player_levels (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    level INT NOT NULL,
+    theme_id UUID REFERENCES themes(id),
+    total_fish INT NOT NULL,
+    ai_count INT NOT NULL,
+    seconds INT NOT NULL DEFAULT 60,
+    max_mistakes INT NOT NULL DEFAULT 3,
+    target_total INT NOT NULL DEFAULT 3,
+    difficulty_level INT NOT NULL DEFAULT 1,
+    time_bonus_seconds INT NOT NULL DEFAULT 0,
+    mistake_penalty_seconds INT NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sp_levels_level_theme
+    ON single_player_levels(level, COALESCE(theme_id, '00000000-0000-0000-0000-000000000000'::uuid));
+
 CREATE TABLE IF NOT EXISTS single_player_runs (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     session_id VARCHAR(100) NOT NULL,
@@ -139,7 +191,20 @@ CREATE TABLE IF NOT EXISTS single_player_runs (
     targets_found INT NOT NULL DEFAULT 0,
     started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
     ends_at TIMESTAMPTZ NOT NULL,
-    submitted_at TIMESTAMPTZ
+    submitted_at TIMESTAMPTZ,
+    level_id UUID,
+    time_bonus_seconds INT NOT NULL DEFAULT 0,
+    mistake_penalty_seconds INT NOT NULL DEFAULT 0,
+    user_id UUID,
+    finished_at TIMESTAMPTZ,
+    score INT,
+    challenge_date DATE,
+    suspicious_catches INT NOT NULL DEFAULT 0,
+    flagged BOOLEAN NOT NULL DEFAULT FALSE,
+    mode VARCHAR(20) NOT NULL DEFAULT 'classic',
+    wave INT NOT NULL DEFAULT 1,
+    difficulty_level INT NOT NULL DEFAULT 1,
+    owner_token VARCHAR(64)
 );
 
 CREATE TABLE IF NOT EXISTS single_player_run_fish (
@@ -148,6 +213,7 @@ CREATE TABLE IF NOT EXISTS single_player_run_fish (
     fish_kind VARCHAR(10) NOT NULL,
     fish_id UUID NOT NULL,
     order_index INT NOT NULL,
+    wave INT NOT NULL DEFAULT 1,
     is_caught BOOLEAN NOT NULL DEFAULT FALSE,
     caught_at TIMESTAMPTZ
 );
@@ -157,9 +223,49 @@ CREATE TABLE IF NOT EXISTS single_player_catches (
     run_id UUID REFERENCES single_player_runs(id) NOT NULL,
     run_fish_id UUID REFERENCES single_player_run_fish(id) NOT NULL,
     correct BOOLEAN NOT NULL,
+    flag_reason VARCHAR(50),
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- 每日挑战：当天所有玩家共享同一组鱼与顺序
+CREATE TABLE IF NOT EXISTS daily_challenges (
+    challenge_date DATE PRIMARY KEY,
+    level_id UUID REFERENCES single_player_levels(id) NOT NULL,
+    seed BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS daily_challenge_fish (
+    challenge_date DATE REFERENCES daily_challenges(challenge_date) NOT NULL,
+    order_index INT NOT NULL,
+    fish_kind VARCHAR(10) NOT NULL,
+    fish_id UUID NOT NULL,
+    PRIMARY KEY (challenge_date, order_index)
+);
+
+CREATE TABLE IF NOT EXISTS daily_challenge_streaks (
+    user_id UUID PRIMARY KEY,
+    current_streak INT NOT NULL DEFAULT 0,
+    best_streak INT NOT NULL DEFAULT 0,
+    last_date DATE NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 单人模式鱼难度校准结果（后台任务按抓取数据周期性刷新）
+CREATE TABLE IF NOT EXISTS fish_calibration_stats (
+    fish_kind VARCHAR(10) NOT NULL,
+    fish_id UUID NOT NULL,
+    exposures INT NOT NULL DEFAULT 0,
+    catches INT NOT NULL DEFAULT 0,
+    fool_rate FLOAT NOT NULL DEFAULT 0.0,
+    previous_level INT,
+    calibrated_level INT,
+    previous_weight INT,
+    calibrated_weight INT,
+    calibrated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (fish_kind, fish_id)
+);
+
 -- 账号体系（预留网站 + 小程序）
 CREATE TABLE IF NOT EXISTS users (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
@@ -194,20 +300,150 @@ CREATE TABLE IF NOT EXISTS auth_sessions (
 CREATE INDEX IF NOT EXISTS idx_auth_sessions_user ON auth_sessions(user_id);
 CREATE INDEX IF NOT EXISTS idx_auth_sessions_expires ON auth_sessions(expires_at);
 
+-- 已有数据库升级：上面的 CREATE TABLE IF NOT EXISTS 不会给旧表补列，
+-- 这里的语句可重复执行，容器启动时会对已有数据库重新执行本文件
+ALTER TABLE themes ADD COLUMN IF NOT EXISTS spawn_policy JSONB NOT NULL DEFAULT '{"type": "fixed_ratio"}';
+ALTER TABLE themes ADD COLUMN IF NOT EXISTS author_corpus JSONB NOT NULL DEFAULT '{"locales": ["zh"]}';
+ALTER TABLE rooms ADD COLUMN IF NOT EXISTS ended_at TIMESTAMPTZ;
+ALTER TABLE drawings ADD COLUMN IF NOT EXISTS votes_received INT DEFAULT 0;
+ALTER TABLE drawings ADD COLUMN IF NOT EXISTS preset_fish_id SMALLINT;
+ALTER TABLE ai_tasks ALTER COLUMN room_id DROP NOT NULL;
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS theme_id UUID REFERENCES themes(id);
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS request_payload JSONB;
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS callback_nonce VARCHAR(64);
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS style_reference_ids UUID[];
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS dispatched_at TIMESTAMPTZ;
+ALTER TABLE ai_tasks ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE human_fish ADD COLUMN IF NOT EXISTS content_hash VARCHAR(64);
+ALTER TABLE human_fish ADD COLUMN IF NOT EXISTS source_drawing_id UUID REFERENCES drawings(id);
+ALTER TABLE human_fish ADD COLUMN IF NOT EXISTS auto_calibrate BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE ai_fish ADD COLUMN IF NOT EXISTS content_hash VARCHAR(64);
+ALTER TABLE ai_fish ADD COLUMN IF NOT EXISTS source_drawing_id UUID REFERENCES drawings(id);
+ALTER TABLE ai_fish ADD COLUMN IF NOT EXISTS auto_calibrate BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS level_id UUID;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS time_bonus_seconds INT NOT NULL DEFAULT 0;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS mistake_penalty_seconds INT NOT NULL DEFAULT 0;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS user_id UUID;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS finished_at TIMESTAMPTZ;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS score INT;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS challenge_date DATE;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS suspicious_catches INT NOT NULL DEFAULT 0;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS flagged BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE single_player_catches ADD COLUMN IF NOT EXISTS flag_reason VARCHAR(50);
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS mode VARCHAR(20) NOT NULL DEFAULT 'classic';
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS wave INT NOT NULL DEFAULT 1;
+ALTER TABLE single_player_run_fish ADD COLUMN IF NOT EXISTS wave INT NOT NULL DEFAULT 1;
+-- 无尽模式曾把波次难度写在 level 里，其它模式取关卡配置的难度
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS difficulty_level INT;
+UPDATE single_player_runs r
+SET difficulty_level = CASE
+    WHEN r.mode = 'endless' THEN r.level
+    ELSE COALESCE((SELECT l.difficulty_level FROM single_player_levels l WHERE l.id = r.level_id), r.level)
+END
+WHERE r.difficulty_level IS NULL;
+ALTER TABLE single_player_runs ALTER COLUMN difficulty_level SET DEFAULT 1;
+ALTER TABLE single_player_runs ALTER COLUMN difficulty_level SET NOT NULL;
+ALTER TABLE single_player_runs DROP COLUMN IF EXISTS client_ip;
+ALTER TABLE single_player_runs ADD COLUMN IF NOT EXISTS owner_token VARCHAR(64);
+-- 状态版本号：旧数据按创建顺序补上序列号，序列从已有的最大版本号之后继续
+ALTER TABLE rooms ADD COLUMN IF NOT EXISTS state_seq BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE drawings ADD COLUMN IF NOT EXISTS state_seq BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE drawings ADD COLUMN IF NOT EXISTS created_seq BIGINT NOT NULL DEFAULT 0;
+CREATE SEQUENCE IF NOT EXISTS room_state_seq;
+DO $$
+BEGIN
+    PERFORM setval('room_state_seq', GREATEST(
+        (SELECT last_value FROM room_state_seq),
+        (SELECT COALESCE(MAX(state_seq), 0) FROM rooms),
+        (SELECT COALESCE(MAX(state_seq), 0) FROM drawings),
+        1
+    ));
+END;
+$$;
+UPDATE drawings d SET state_seq = s.seq, created_seq = s.seq
+FROM (
+    SELECT id, nextval('room_state_seq') AS seq
+    FROM (SELECT id FROM drawings WHERE state_seq = 0 ORDER BY created_at) pending
+) s
+WHERE d.id = s.id;
+UPDATE rooms SET state_seq = nextval('room_state_seq') WHERE state_seq = 0;
+
+-- 房间状态版本号：鱼的新增、票数、淘汰、隐藏变化时从全局序列取号记到该鱼上，
+-- 不回写 rooms 行，投票不会在房间行锁上排队；房间当前版本号是房间行与其鱼的最大值
+CREATE OR REPLACE FUNCTION bump_drawing_state_seq() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.state_seq := nextval('room_state_seq');
+    IF TG_OP = 'INSERT' THEN
+        NEW.created_seq := NEW.state_seq;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS drawings_state_seq_insert ON drawings;
+CREATE TRIGGER drawings_state_seq_insert
+    BEFORE INSERT ON drawings
+    FOR EACH ROW EXECUTE FUNCTION bump_drawing_state_seq();
+
+DROP TRIGGER IF EXISTS drawings_state_seq_update ON drawings;
+CREATE TRIGGER drawings_state_seq_update
+    BEFORE UPDATE ON drawings
+    FOR EACH ROW
+    WHEN (OLD.vote_count IS DISTINCT FROM NEW.vote_count
+        OR OLD.is_eliminated IS DISTINCT FROM NEW.is_eliminated
+        OR OLD.is_hidden IS DISTINCT FROM NEW.is_hidden)
+    EXECUTE FUNCTION bump_drawing_state_seq();
+
+-- 房间相位与计数变化时同样取新版本号
+CREATE OR REPLACE FUNCTION bump_room_state_seq() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.state_seq := nextval('room_state_seq');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS rooms_state_seq ON rooms;
+CREATE TRIGGER rooms_state_seq
+    BEFORE UPDATE ON rooms
+    FOR EACH ROW
+    WHEN (OLD.status IS DISTINCT FROM NEW.status
+        OR OLD.total_items IS DISTINCT FROM NEW.total_items
+        OR OLD.ai_count IS DISTINCT FROM NEW.ai_count
+        OR OLD.turbidity IS DISTINCT FROM NEW.turbidity
+        OR OLD.voting_started_at IS DISTINCT FROM NEW.voting_started_at
+        OR OLD.voting_ends_at IS DISTINCT FROM NEW.voting_ends_at)
+    EXECUTE FUNCTION bump_room_state_seq();
+
 -- 索引
 CREATE INDEX IF NOT EXISTS idx_rooms_theme ON rooms(theme_id);
 CREATE INDEX IF NOT EXISTS idx_rooms_status ON rooms(status);
 CREATE INDEX IF NOT EXISTS idx_drawings_room ON drawings(room_id);
+CREATE INDEX IF NOT EXISTS idx_drawings_ai_created ON drawings(created_at) WHERE is_ai = TRUE;
 CREATE INDEX IF NOT EXISTS idx_drawings_room_active ON drawings(room_id) 
     WHERE is_eliminated = FALSE AND is_hidden = FALSE;
+CREATE INDEX IF NOT EXISTS idx_drawings_room_seq ON drawings(room_id, state_seq);
 CREATE INDEX IF NOT EXISTS idx_votes_drawing ON votes(drawing_id);
 CREATE INDEX IF NOT EXISTS idx_ai_tasks_room ON ai_tasks(room_id);
 CREATE INDEX IF NOT EXISTS idx_ai_tasks_status ON ai_tasks(status);
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_theme ON ai_tasks(theme_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_due ON ai_tasks(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_drawing ON ai_tasks(drawing_id);
 CREATE INDEX IF NOT EXISTS idx_human_fish_active_level ON human_fish(is_active, difficulty_level);
 CREATE INDEX IF NOT EXISTS idx_ai_fish_active_level ON ai_fish(is_active, difficulty_level);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_human_fish_content_hash ON human_fish(content_hash);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ai_fish_content_hash ON ai_fish(content_hash);
 CREATE INDEX IF NOT EXISTS idx_sp_runs_session ON single_player_runs(session_id);
+CREATE INDEX IF NOT EXISTS idx_sp_runs_active_ends ON single_player_runs(ends_at)
+    WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS idx_sp_runs_user ON single_player_runs(user_id, started_at DESC);
+DROP INDEX IF EXISTS idx_sp_runs_active_ip;
+CREATE INDEX IF NOT EXISTS idx_sp_runs_active_owner ON single_player_runs(owner_token)
+    WHERE status = 'active' AND user_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sp_runs_daily_user ON single_player_runs(user_id, challenge_date)
+    WHERE challenge_date IS NOT NULL;
 CREATE INDEX IF NOT EXISTS idx_sp_run_fish_run ON single_player_run_fish(run_id);
 CREATE INDEX IF NOT EXISTS idx_sp_catches_run ON single_player_catches(run_id);
+CREATE INDEX IF NOT EXISTS idx_sp_run_fish_fish ON single_player_run_fish(fish_kind, fish_id);
 
 -- 初始主题数据
 INSERT INTO themes (theme_id, theme_name, background_url, particle_effect, palette, ai_keywords, ai_prompt_style, spawn_rate, max_imposters)
@@ -224,6 +460,13 @@ VALUES
      5, 5)
 ON CONFLICT (theme_id) DO NOTHING;
 
+INSERT INTO single_player_levels (id, level, theme_id, total_fish, ai_count, seconds, max_mistakes, target_total, difficulty_level)
+VALUES
+    ('00000000-0000-0000-0000-00000000a001', 1, NULL, 10, 3, 60, 3, 3, 1),
+    ('00000000-0000-0000-0000-00000000a002', 2, NULL, 20, 5, 60, 3, 3, 2),
+    ('00000000-0000-0000-0000-00000000a003', 3, NULL, 30, 7, 60, 3, 3, 3)
+ON CONFLICT (id) DO NOTHING;
+
 INSERT INTO human_fish (id, image_data, difficulty_level, metadata, weight, is_active)
 VALUES
     ('00000000-0000-0000-0000-000000000101', 'data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mP8/x8AAwMB/6XK7xkAAAAASUVORK5CYII=', 1, '{"authorName":"小明","createDate":"2024-05-20","description":"一只蓝色的鱼"}'::jsonb, 3, TRUE),
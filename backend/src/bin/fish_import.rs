@@ -0,0 +1,185 @@
+//! 单人模式鱼库批量导入工具
+//!
+//! 用法:
+//!   ADMIN_API_TOKEN=... fish_import <human|ai> <dir> [--level N] [--weight N] [--inactive]
+//!
+//! 读取目录下的 png/jpg/webp 图片，同名 `.json` 文件（如 `a.png` + `a.json`）作为 metadata，
+//! 分批调用 `POST /api/admin/fish/:kind/import`。重复图片由服务端按内容哈希跳过。
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use reqwest::StatusCode;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+const BATCH_SIZE: usize = 20;
+
+struct Args {
+    kind: String,
+    dir: PathBuf,
+    level: i32,
+    weight: i32,
+    is_active: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut positional = Vec::new();
+    let mut level = 1;
+    let mut weight = 1;
+    let mut is_active = true;
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--level" => {
+                level = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--level requires a value"))?
+                    .parse()
+                    .context("--level must be a number")?;
+            }
+            "--weight" => {
+                weight = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--weight requires a value"))?
+                    .parse()
+                    .context("--weight must be a number")?;
+            }
+            "--inactive" => is_active = false,
+            _ => positional.push(arg),
+        }
+    }
+
+    let [kind, dir] = <[String; 2]>::try_from(positional).map_err(|_| {
+        anyhow!("usage: fish_import <human|ai> <dir> [--level N] [--weight N] [--inactive]")
+    })?;
+    if kind != "human" && kind != "ai" {
+        bail!("kind must be `human` or `ai`, got `{}`", kind);
+    }
+
+    Ok(Args {
+        kind,
+        dir: PathBuf::from(dir),
+        level,
+        weight,
+        is_active,
+    })
+}
+
+fn mime_for(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn load_item(path: &Path, args: &Args) -> Result<serde_json::Value> {
+    let mime = mime_for(path).ok_or_else(|| anyhow!("unsupported file {}", path.display()))?;
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let image_data = format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    );
+
+    let sidecar = path.with_extension("json");
+    let metadata = if sidecar.exists() {
+        let raw = std::fs::read(&sidecar).with_context(|| format!("read {}", sidecar.display()))?;
+        serde_json::from_slice(&raw).with_context(|| format!("parse {}", sidecar.display()))?
+    } else {
+        json!({})
+    };
+
+    Ok(json!({
+        "imageData": image_data,
+        "difficultyLevel": args.level,
+        "weight": args.weight,
+        "metadata": metadata,
+        "isActive": args.is_active,
+    }))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+    let base_url = std::env::var("API_BASE_URL").unwrap_or_else(|_| "http://localhost:3001".into());
+    let token = std::env::var("ADMIN_API_TOKEN").context("ADMIN_API_TOKEN must be set")?;
+    let import_url = format!(
+        "{}/api/admin/fish/{}/import",
+        base_url.trim_end_matches('/'),
+        args.kind
+    );
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&args.dir)
+        .with_context(|| format!("read dir {}", args.dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| mime_for(p).is_some())
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        bail!("no images found in {}", args.dir.display());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .build()?;
+
+    let (mut imported, mut duplicates, mut failed) = (0u64, 0u64, 0u64);
+    for chunk in files.chunks(BATCH_SIZE) {
+        let items = chunk
+            .iter()
+            .map(|p| load_item(p, &args))
+            .collect::<Result<Vec<_>>>()?;
+
+        let resp = client
+            .post(&import_url)
+            .bearer_auth(&token)
+            .json(&json!({ "items": items }))
+            .send()
+            .await
+            .context("POST /api/admin/fish/:kind/import")?;
+        let status = resp.status();
+        let body = resp.bytes().await.unwrap_or_default();
+        if status != StatusCode::OK {
+            bail!(
+                "import failed: {} body={}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
+        }
+
+        let v: serde_json::Value = serde_json::from_slice(&body)?;
+        imported += v.get("imported").and_then(|x| x.as_u64()).unwrap_or(0);
+        duplicates += v.get("duplicates").and_then(|x| x.as_u64()).unwrap_or(0);
+        failed += v.get("failed").and_then(|x| x.as_u64()).unwrap_or(0);
+
+        for result in v
+            .get("results")
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let index = result.get("index").and_then(|x| x.as_u64()).unwrap_or(0) as usize;
+            let file = chunk.get(index).map(|p| p.display().to_string());
+            match result.get("status").and_then(|x| x.as_str()) {
+                Some("duplicate") => println!("skip (duplicate): {}", file.unwrap_or_default()),
+                Some("error") => println!(
+                    "error: {} {}",
+                    file.unwrap_or_default(),
+                    result.get("error").and_then(|x| x.as_str()).unwrap_or("")
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    println!(
+        "OK imported={} duplicates={} failed={}",
+        imported, duplicates, failed
+    );
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
 
+use crate::services::content_filter::FilterMode;
+use crate::services::rate_limit::RateLimit;
+
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
@@ -9,7 +12,53 @@ pub struct Config {
     pub n8n_webhook_url: String,
     pub callback_base_url: String,
     pub ai_generation_enabled: bool,
+    pub ai_generator_backend: String,
+    pub ai_fish_buffer_target: i64,
+    pub ai_fish_buffer_max_in_flight: i64,
+    pub ai_fish_buffer_daily_budget: i64,
+    pub ai_fish_buffer_interval_seconds: u64,
+    pub preset_fish_selection: String,
+    pub preset_scores_cache_seconds: u64,
+    pub ai_spawn_tick_seconds: u64,
+    pub ai_mimicry_enabled: bool,
+    pub ai_mimicry_references: i64,
+    pub tank_snapshot_interval_ms: u64,
+    pub room_event_log_max: isize,
+    pub room_event_log_ttl_seconds: i64,
+    pub rate_limit_enabled: bool,
+    pub rate_limit_trust_proxy: bool,
+    pub rate_limit_vote: RateLimit,
+    pub rate_limit_comment: RateLimit,
+    pub rate_limit_drawing: RateLimit,
+    pub rate_limit_guest_login: RateLimit,
+    pub rate_limit_game_start: RateLimit,
+    pub content_filter_mode: FilterMode,
+    pub content_filter_blocklist_path: Option<String>,
+    pub openai_image_api_url: String,
+    pub openai_image_api_key: Option<String>,
+    pub openai_image_model: String,
+    pub openai_image_size: String,
+    pub n8n_callback_secret: Option<String>,
+    pub n8n_callback_max_skew_seconds: i64,
+    pub n8n_max_attempts: i32,
+    pub n8n_retry_base_seconds: u64,
+    pub n8n_retry_max_seconds: u64,
+    pub n8n_task_timeout_seconds: u64,
+    pub n8n_job_poll_interval_seconds: u64,
     pub single_player_allow_duplicates_max_level: i32,
+    pub fish_calibration_interval_seconds: u64,
+    pub fish_calibration_min_samples: i64,
+    pub fish_calibration_levels: i32,
+    pub fish_calibration_max_weight: i32,
+    pub daily_challenge_level: i32,
+    pub endless_waves_per_tier: i32,
+    pub endless_max_difficulty: i32,
+    pub endless_idle_seconds: i32,
+    pub single_player_min_first_catch_ms: i64,
+    pub single_player_min_catch_interval_ms: i64,
+    pub single_player_flag_threshold: i32,
+    pub single_player_sweep_interval_seconds: u64,
+    pub single_player_max_active_runs: i64,
     pub image_storage_backend: String,
     pub s3_root: String,
     pub s3_bucket: Option<String>,
@@ -21,6 +70,7 @@ pub struct Config {
     pub wechat_mp_secret: Option<String>,
     pub auth_token_ttl_days: i64,
     pub dev_auth_enabled: bool,
+    pub admin_api_token: Option<String>,
     pub vote_threshold_ratio: f64,
     pub vote_min_threshold: i32,
     pub human_eliminated_ratio: f64,
@@ -56,12 +106,190 @@ impl Config {
             ai_generation_enabled: std::env::var("AI_GENERATION_ENABLED")
                 .map(|v| v.to_lowercase() == "true")
                 .unwrap_or(false),
+            ai_generator_backend: std::env::var("AI_GENERATOR_BACKEND")
+                .unwrap_or_else(|_| "n8n".to_string()),
+            ai_fish_buffer_target: std::env::var("AI_FISH_BUFFER_TARGET")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("AI_FISH_BUFFER_TARGET must be a valid number")?,
+            ai_fish_buffer_max_in_flight: std::env::var("AI_FISH_BUFFER_MAX_IN_FLIGHT")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("AI_FISH_BUFFER_MAX_IN_FLIGHT must be a valid number")?,
+            ai_fish_buffer_daily_budget: std::env::var("AI_FISH_BUFFER_DAILY_BUDGET")
+                .unwrap_or_else(|_| "200".to_string())
+                .parse()
+                .context("AI_FISH_BUFFER_DAILY_BUDGET must be a valid number")?,
+            ai_fish_buffer_interval_seconds: std::env::var("AI_FISH_BUFFER_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("AI_FISH_BUFFER_INTERVAL_SECONDS must be a valid number")?,
+            preset_fish_selection: std::env::var("PRESET_FISH_SELECTION")
+                .unwrap_or_else(|_| "least_used".to_string()),
+            preset_scores_cache_seconds: std::env::var("PRESET_SCORES_CACHE_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .context("PRESET_SCORES_CACHE_SECONDS must be a valid number")?,
+            ai_spawn_tick_seconds: std::env::var("AI_SPAWN_TICK_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("AI_SPAWN_TICK_SECONDS must be a valid number")?,
+            ai_mimicry_enabled: std::env::var("AI_MIMICRY_ENABLED")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            ai_mimicry_references: std::env::var("AI_MIMICRY_REFERENCES")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("AI_MIMICRY_REFERENCES must be a valid number")?,
+            tank_snapshot_interval_ms: std::env::var("TANK_SNAPSHOT_INTERVAL_MS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .context("TANK_SNAPSHOT_INTERVAL_MS must be a valid number")?,
+            room_event_log_max: std::env::var("ROOM_EVENT_LOG_MAX")
+                .unwrap_or_else(|_| "200".to_string())
+                .parse()
+                .context("ROOM_EVENT_LOG_MAX must be a valid number")?,
+            room_event_log_ttl_seconds: std::env::var("ROOM_EVENT_LOG_TTL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .context("ROOM_EVENT_LOG_TTL_SECONDS must be a valid number")?,
+            rate_limit_enabled: std::env::var("RATE_LIMIT_ENABLED")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(true),
+            rate_limit_trust_proxy: std::env::var("RATE_LIMIT_TRUST_PROXY")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            rate_limit_vote: std::env::var("RATE_LIMIT_VOTE")
+                .unwrap_or_else(|_| "10/60".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("RATE_LIMIT_VOTE must be <burst>/<per_minute>")?,
+            rate_limit_comment: std::env::var("RATE_LIMIT_COMMENT")
+                .unwrap_or_else(|_| "5/20".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("RATE_LIMIT_COMMENT must be <burst>/<per_minute>")?,
+            rate_limit_drawing: std::env::var("RATE_LIMIT_DRAWING")
+                .unwrap_or_else(|_| "3/6".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("RATE_LIMIT_DRAWING must be <burst>/<per_minute>")?,
+            rate_limit_guest_login: std::env::var("RATE_LIMIT_GUEST_LOGIN")
+                .unwrap_or_else(|_| "5/10".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("RATE_LIMIT_GUEST_LOGIN must be <burst>/<per_minute>")?,
+            rate_limit_game_start: std::env::var("RATE_LIMIT_GAME_START")
+                .unwrap_or_else(|_| "5/20".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("RATE_LIMIT_GAME_START must be <burst>/<per_minute>")?,
+            content_filter_mode: std::env::var("CONTENT_FILTER_MODE")
+                .unwrap_or_else(|_| "replace".to_string())
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .context("CONTENT_FILTER_MODE must be off, replace or reject")?,
+            content_filter_blocklist_path: std::env::var("CONTENT_FILTER_BLOCKLIST_PATH")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            openai_image_api_url: std::env::var("OPENAI_IMAGE_API_URL")
+                .unwrap_or_else(|_| "https://api.openai.com/v1/images/generations".to_string()),
+            openai_image_api_key: std::env::var("OPENAI_IMAGE_API_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            openai_image_model: std::env::var("OPENAI_IMAGE_MODEL")
+                .unwrap_or_else(|_| "dall-e-3".to_string()),
+            openai_image_size: std::env::var("OPENAI_IMAGE_SIZE")
+                .unwrap_or_else(|_| "1024x1024".to_string()),
+            n8n_callback_secret: std::env::var("N8N_CALLBACK_SECRET")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            n8n_callback_max_skew_seconds: std::env::var("N8N_CALLBACK_MAX_SKEW_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .context("N8N_CALLBACK_MAX_SKEW_SECONDS must be a valid number")?,
+            n8n_max_attempts: std::env::var("N8N_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("N8N_MAX_ATTEMPTS must be a valid number")?,
+            n8n_retry_base_seconds: std::env::var("N8N_RETRY_BASE_SECONDS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .context("N8N_RETRY_BASE_SECONDS must be a valid number")?,
+            n8n_retry_max_seconds: std::env::var("N8N_RETRY_MAX_SECONDS")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()
+                .context("N8N_RETRY_MAX_SECONDS must be a valid number")?,
+            n8n_task_timeout_seconds: std::env::var("N8N_TASK_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .context("N8N_TASK_TIMEOUT_SECONDS must be a valid number")?,
+            n8n_job_poll_interval_seconds: std::env::var("N8N_JOB_POLL_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("N8N_JOB_POLL_INTERVAL_SECONDS must be a valid number")?,
             single_player_allow_duplicates_max_level: std::env::var(
                 "SINGLE_PLAYER_ALLOW_DUPLICATES_MAX_LEVEL",
             )
             .unwrap_or_else(|_| "1".to_string())
             .parse()
             .context("SINGLE_PLAYER_ALLOW_DUPLICATES_MAX_LEVEL must be a valid number")?,
+            fish_calibration_interval_seconds: std::env::var("FISH_CALIBRATION_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .context("FISH_CALIBRATION_INTERVAL_SECONDS must be a valid number")?,
+            fish_calibration_min_samples: std::env::var("FISH_CALIBRATION_MIN_SAMPLES")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .context("FISH_CALIBRATION_MIN_SAMPLES must be a valid number")?,
+            fish_calibration_levels: std::env::var("FISH_CALIBRATION_LEVELS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("FISH_CALIBRATION_LEVELS must be a valid number")?,
+            fish_calibration_max_weight: std::env::var("FISH_CALIBRATION_MAX_WEIGHT")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("FISH_CALIBRATION_MAX_WEIGHT must be a valid number")?,
+            daily_challenge_level: std::env::var("DAILY_CHALLENGE_LEVEL")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .context("DAILY_CHALLENGE_LEVEL must be a valid number")?,
+            endless_waves_per_tier: std::env::var("ENDLESS_WAVES_PER_TIER")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .context("ENDLESS_WAVES_PER_TIER must be a valid number")?,
+            endless_max_difficulty: std::env::var("ENDLESS_MAX_DIFFICULTY")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("ENDLESS_MAX_DIFFICULTY must be a valid number")?,
+            endless_idle_seconds: std::env::var("ENDLESS_IDLE_SECONDS")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()
+                .context("ENDLESS_IDLE_SECONDS must be a valid number")?,
+            single_player_min_first_catch_ms: std::env::var("SINGLE_PLAYER_MIN_FIRST_CATCH_MS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .context("SINGLE_PLAYER_MIN_FIRST_CATCH_MS must be a valid number")?,
+            single_player_min_catch_interval_ms: std::env::var(
+                "SINGLE_PLAYER_MIN_CATCH_INTERVAL_MS",
+            )
+            .unwrap_or_else(|_| "150".to_string())
+            .parse()
+            .context("SINGLE_PLAYER_MIN_CATCH_INTERVAL_MS must be a valid number")?,
+            single_player_flag_threshold: std::env::var("SINGLE_PLAYER_FLAG_THRESHOLD")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .context("SINGLE_PLAYER_FLAG_THRESHOLD must be a valid number")?,
+            single_player_sweep_interval_seconds: std::env::var(
+                "SINGLE_PLAYER_SWEEP_INTERVAL_SECONDS",
+            )
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .context("SINGLE_PLAYER_SWEEP_INTERVAL_SECONDS must be a valid number")?,
+            single_player_max_active_runs: std::env::var("SINGLE_PLAYER_MAX_ACTIVE_RUNS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("SINGLE_PLAYER_MAX_ACTIVE_RUNS must be a valid number")?,
             image_storage_backend,
             s3_root: std::env::var("S3_ROOT").unwrap_or_else(|_| "/".to_string()),
             s3_bucket: if s3_enabled {
@@ -109,6 +337,10 @@ impl Config {
             dev_auth_enabled: std::env::var("DEV_AUTH_ENABLED")
                 .map(|v| v.to_lowercase() == "true")
                 .unwrap_or(false),
+            admin_api_token: std::env::var("ADMIN_API_TOKEN")
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
             vote_threshold_ratio: std::env::var("VOTE_THRESHOLD_RATIO")
                 .unwrap_or_else(|_| "0.6".to_string())
                 .parse()
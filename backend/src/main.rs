@@ -1,6 +1,6 @@
 use anyhow::Result;
 use axum::{
-    routing::{get, post},
+    routing::{get, patch, post},
     Extension, Router,
 };
 use socketioxide::handler::ConnectHandler;
@@ -19,6 +19,7 @@ mod services;
 mod ws;
 
 use config::Config;
+use services::rate_limit::{RateAction, RateLimitLayer};
 use services::AppState;
 
 #[tokio::main]
@@ -80,6 +81,37 @@ async fn main() -> Result<()> {
         state.clone(),
     ));
 
+    // 单人模式鱼难度校准
+    tokio::spawn(services::fish_calibration::start_fish_calibration(
+        state.clone(),
+    ));
+
+    // AI 鱼出场：定时类出场策略的周期检查
+    tokio::spawn(ws::ai_spawner::start_ai_spawn_ticker(
+        io.clone(),
+        state.clone(),
+    ));
+
+    // 鱼缸模拟：服务端统一推进鱼的位置并广播 tank:snapshot
+    tokio::spawn(ws::tank_sim::start_tank_simulation(
+        io.clone(),
+        state.clone(),
+    ));
+
+    // AI 鱼主题池：定期补到目标深度
+    tokio::spawn(services::ai_fish_buffer::start_ai_fish_buffer(
+        state.clone(),
+    ));
+
+    // n8n 生成任务：超时判定与失败重试
+    tokio::spawn(services::n8n_jobs::start_n8n_job_runner(state.clone()));
+
+    // 单人模式：结算超时未提交的对局
+    tokio::spawn(services::run_sweeper::start_run_sweeper(state.clone()));
+
+    // 单人模式排行榜：Redis 清空后从数据库重建
+    tokio::spawn(services::leaderboard::ensure_leaderboards(state.clone()));
+
     // CORS 配置
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -102,12 +134,19 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind(addr).await?;
     tracing::info!("Server listening on {}", addr);
 
-    axum::serve(listener, app).await?;
+    // 限流按 IP 计数时需要连接地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
 fn api_routes(state: Arc<AppState>, io: SocketIo) -> Router {
+    let limit = |action| RateLimitLayer::new(&state, action);
+
     Router::new()
         // Themes
         .route("/themes", get(routes::themes::list_themes))
@@ -116,13 +155,17 @@ fn api_routes(state: Arc<AppState>, io: SocketIo) -> Router {
             "/themes/:theme_id/room",
             get(routes::themes::get_or_create_room_by_theme),
         )
+        // Socket.IO 协议 schema
+        .route("/ws/schema", get(routes::ws_protocol::get_ws_schema))
         // Rooms
         .route("/rooms", post(routes::rooms::create_room))
         .route("/rooms/:room_code", get(routes::rooms::get_room))
         // Drawings - 合并 GET 和 POST 到同一路径
         .route(
             "/rooms/:room_code/drawings",
-            get(routes::rooms::list_drawings).post(routes::drawings::create_drawing),
+            post(routes::drawings::create_drawing)
+                .layer(limit(RateAction::DrawingSubmit))
+                .get(routes::rooms::list_drawings),
         )
         .route("/drawings/:drawing_id", get(routes::drawings::get_drawing))
         .route(
@@ -131,24 +174,101 @@ fn api_routes(state: Arc<AppState>, io: SocketIo) -> Router {
         )
         .route(
             "/drawings/:drawing_id/vote",
-            post(routes::drawings::vote_drawing),
+            post(routes::drawings::vote_drawing).layer(limit(RateAction::Vote)),
         )
         .route(
             "/drawings/:drawing_id/report",
             post(routes::drawings::report_drawing),
         )
         .route("/auth/wechat_mp/login", post(routes::auth::wechat_mp_login))
-        .route("/auth/guest/login", post(routes::auth::guest_login))
+        .route(
+            "/auth/guest/login",
+            post(routes::auth::guest_login).layer(limit(RateAction::GuestLogin)),
+        )
         .route("/auth/dev/login", post(routes::dev_auth::dev_login))
         .route("/auth/me", get(routes::auth::me))
         .route("/auth/logout", post(routes::auth::logout))
-        .route("/game/start", post(routes::game::start_game))
+        .route("/game/levels", get(routes::game::list_levels))
+        .route(
+            "/game/start",
+            post(routes::game::start_game).layer(limit(RateAction::GameStart)),
+        )
         .route("/game/catch", post(routes::game::catch_fish))
         .route("/game/submit", post(routes::game::submit_game))
+        .route("/game/next_wave", post(routes::game::next_wave))
+        .route(
+            "/game/endless/start",
+            post(routes::game::start_endless).layer(limit(RateAction::GameStart)),
+        )
+        .route("/game/leaderboard", get(routes::game::get_leaderboard))
+        .route("/game/daily", get(routes::game::get_daily))
+        .route(
+            "/game/daily/start",
+            post(routes::game::start_daily).layer(limit(RateAction::GameStart)),
+        )
+        .route("/game/runs", get(routes::game_history::list_runs))
+        .route("/game/runs/:run_id", get(routes::game_history::get_run))
         .route(
             "/game/fish/:fish_instance_id/image",
             get(routes::game::get_fish_image),
         )
+        // Admin: 单人模式鱼库
+        .route(
+            "/admin/fish/candidates",
+            get(routes::admin_fish::list_candidates),
+        )
+        .route(
+            "/admin/fish/calibration",
+            get(routes::admin_fish::calibration_report),
+        )
+        .route(
+            "/admin/fish/calibration/run",
+            post(routes::admin_fish::run_calibration_now),
+        )
+        .route(
+            "/admin/fish/promote",
+            post(routes::admin_fish::promote_drawing),
+        )
+        .route(
+            "/admin/fish/:kind",
+            get(routes::admin_fish::list_fish).post(routes::admin_fish::create_fish),
+        )
+        .route(
+            "/admin/fish/:kind/import",
+            post(routes::admin_fish::import_fish),
+        )
+        .route(
+            "/admin/fish/:kind/:fish_id",
+            patch(routes::admin_fish::update_fish),
+        )
+        .route(
+            "/admin/fish/:kind/:fish_id/image",
+            get(routes::admin_fish::get_fish_image),
+        )
+        // Admin: 单人模式关卡
+        .route(
+            "/admin/levels",
+            get(routes::admin_levels::list_levels).post(routes::admin_levels::create_level),
+        )
+        .route(
+            "/admin/levels/:level_id",
+            patch(routes::admin_levels::update_level),
+        )
+        // Admin: n8n 生成任务
+        .route("/admin/ai-tasks", get(routes::admin_ai_tasks::list_tasks))
+        .route(
+            "/admin/ai-tasks/:task_id/retry",
+            post(routes::admin_ai_tasks::retry_task),
+        )
+        // Admin: AI 鱼欺骗效果统计
+        .route(
+            "/admin/imposters",
+            get(routes::admin_imposters::list_imposters),
+        )
+        .route(
+            "/admin/imposters/summary",
+            get(routes::admin_imposters::imposter_summary),
+        )
         // n8n callback
         .route("/n8n/callback", post(routes::n8n_callback::callback))
         .with_state(state)
@@ -6,7 +6,8 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct AiTask {
     pub id: Uuid,
-    pub room_id: Uuid,
+    pub room_id: Option<Uuid>,
+    pub theme_id: Option<Uuid>,
     pub drawing_id: Option<Uuid>,
     pub status: String,
     pub n8n_execution_id: Option<String>,
@@ -17,6 +18,10 @@ pub struct AiTask {
     pub generated_description: Option<String>,
     pub error_message: Option<String>,
     pub retry_count: i32,
+    pub request_payload: Option<serde_json::Value>,
+    pub callback_nonce: Option<String>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub next_attempt_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
 }
@@ -26,6 +31,9 @@ pub struct AiTask {
 pub struct N8nCallbackRequest {
     pub task_id: Uuid,
     pub status: String,
+    /// 触发请求中下发的 nonce
+    #[serde(default)]
+    pub nonce: Option<String>,
     #[serde(default)]
     pub image_data: Option<String>,
     #[serde(default)]
@@ -36,17 +44,49 @@ pub struct N8nCallbackRequest {
     pub error_message: Option<String>,
 }
 
-/// 触发 n8n 的请求体
-#[derive(Debug, Serialize)]
-pub struct TriggerN8nRequest {
-    pub room_id: Uuid,
+/// 交给生成后端的请求，与具体后端无关；落库在 `ai_tasks.request_payload`，重试时原样重发。
+/// n8n 直接把它作为 webhook 请求体，字段名保持不变
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerationRequest {
+    /// 主题池任务没有房间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<Uuid>,
     pub task_id: Uuid,
-    pub theme: TriggerN8nTheme,
+    pub theme: GenerationTheme,
+    /// 异步出图的后端（n8n）完成后回调的位置；同步后端忽略
+    #[serde(flatten)]
+    pub callback: Option<CallbackTarget>,
+    /// 后端选定的关键词与完整提示词（旧任务没有）
+    #[serde(default)]
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// 拟态任务参考的本房间人类画作
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub style_references: Vec<StyleReference>,
+    /// 参考画作的主色，拟态任务用它代替主题调色板
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub style_palette: Vec<String>,
+}
+
+/// 拟态参考：房间里最近的一幅人类画作。只给图片地址，玩家写的名字和描述不进生成请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleReference {
+    pub drawing_id: Uuid,
+    /// `CALLBACK_BASE_URL` 下的 `/api/drawings/:id/image`
+    #[serde(default)]
+    pub image_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackTarget {
     pub callback_url: String,
+    /// 回调时须原样带回
+    pub nonce: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct TriggerN8nTheme {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerationTheme {
     pub palette: Vec<String>,
     pub keywords: Vec<String>,
     pub prompt_style: String,
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 单人模式鱼库类型：`human_fish` / `ai_fish`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FishKind {
+    Human,
+    Ai,
+}
+
+impl FishKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FishKind::Human => "human",
+            FishKind::Ai => "ai",
+        }
+    }
+
+    pub fn table(self) -> &'static str {
+        match self {
+            FishKind::Human => "human_fish",
+            FishKind::Ai => "ai_fish",
+        }
+    }
+
+    /// `single_player_run_fish.fish_kind` 中的取值
+    pub fn run_fish_kind(self) -> &'static str {
+        match self {
+            FishKind::Human => "HUMAN",
+            FishKind::Ai => "AI",
+        }
+    }
+
+    pub fn from_run_fish_kind(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "HUMAN" => Some(FishKind::Human),
+            "AI" => Some(FishKind::Ai),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LibraryFish {
+    pub id: Uuid,
+    pub image_data: String,
+    pub difficulty_level: i32,
+    pub metadata: serde_json::Value,
+    pub weight: i32,
+    pub is_active: bool,
+    pub content_hash: Option<String>,
+    pub source_drawing_id: Option<Uuid>,
+    pub auto_calibrate: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 管理端列表响应 (不含 image_data)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryFishSummary {
+    pub id: Uuid,
+    pub kind: FishKind,
+    pub image_url: String,
+    pub difficulty_level: i32,
+    pub metadata: serde_json::Value,
+    pub weight: i32,
+    pub is_active: bool,
+    pub content_hash: Option<String>,
+    pub source_drawing_id: Option<Uuid>,
+    pub auto_calibrate: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub fn library_fish_image_url(kind: FishKind, fish_id: Uuid) -> String {
+    format!("/api/admin/fish/{}/{}/image", kind.as_str(), fish_id)
+}
+
+impl LibraryFishSummary {
+    pub fn from_row(kind: FishKind, f: LibraryFish) -> Self {
+        Self {
+            id: f.id,
+            kind,
+            image_url: library_fish_image_url(kind, f.id),
+            difficulty_level: f.difficulty_level,
+            metadata: f.metadata,
+            weight: f.weight,
+            is_active: f.is_active,
+            content_hash: f.content_hash,
+            source_drawing_id: f.source_drawing_id,
+            auto_calibrate: f.auto_calibrate,
+            created_at: f.created_at,
+            updated_at: f.updated_at,
+        }
+    }
+}
+
+/// 难度校准报表行（`fish_calibration_stats` + 鱼库当前值）
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct FishCalibrationReportRow {
+    pub fish_kind: String,
+    pub fish_id: Uuid,
+    pub exposures: i32,
+    pub catches: i32,
+    pub fool_rate: f64,
+    pub previous_level: Option<i32>,
+    pub calibrated_level: Option<i32>,
+    pub previous_weight: Option<i32>,
+    pub calibrated_weight: Option<i32>,
+    pub calibrated_at: DateTime<Utc>,
+    pub difficulty_level: i32,
+    pub weight: i32,
+    pub is_active: bool,
+    pub auto_calibrate: bool,
+}
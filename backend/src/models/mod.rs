@@ -1,6 +1,7 @@
 pub mod ai_task;
 pub mod drawing;
 pub mod drawing_item_row;
+pub mod fish_library;
 pub mod room;
 pub mod single_player;
 pub mod theme;
@@ -9,6 +10,7 @@ pub mod user;
 pub use ai_task::*;
 pub use drawing::*;
 pub use drawing_item_row::*;
+pub use fish_library::*;
 pub use room::*;
 pub use single_player::*;
 pub use theme::*;
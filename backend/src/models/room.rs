@@ -17,6 +17,7 @@ pub struct Room {
     pub turbidity: f64,
     pub voting_started_at: Option<DateTime<Utc>>,
     pub voting_ends_at: Option<DateTime<Utc>>,
+    pub state_seq: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -1,8 +1,10 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::models::FishKind;
+
 pub fn single_player_fish_image_url(fish_instance_id: Uuid) -> String {
     format!("/api/game/fish/{}/image", fish_instance_id)
 }
@@ -21,6 +23,28 @@ pub struct SinglePlayerRun {
     pub started_at: DateTime<Utc>,
     pub ends_at: DateTime<Utc>,
     pub submitted_at: Option<DateTime<Utc>>,
+    pub level_id: Option<Uuid>,
+    pub time_bonus_seconds: i32,
+    pub mistake_penalty_seconds: i32,
+    /// 登录用户（匿名对局为空，不进入排行榜）
+    pub user_id: Option<Uuid>,
+    /// 对局结束（胜利/失败/超时）的时间，用于计算剩余时间
+    pub finished_at: Option<DateTime<Utc>>,
+    pub score: Option<i32>,
+    /// 每日挑战日期（普通对局为空）
+    pub challenge_date: Option<NaiveDate>,
+    /// 节奏异常的抓取次数
+    pub suspicious_catches: i32,
+    /// 可疑次数达到阈值后标记，不进入排行榜
+    pub flagged: bool,
+    /// `classic` / `daily` / `endless`
+    pub mode: String,
+    /// 无尽模式当前波次（其它模式恒为 1）
+    pub wave: i32,
+    /// 抽鱼使用的难度档位（无尽模式为当前波次的难度，`level` 保持基础关卡）
+    pub difficulty_level: i32,
+    /// 匿名对局的 owner token，按它限制同时进行中的局数
+    pub owner_token: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -30,6 +54,77 @@ pub struct SinglePlayerRunFish {
     pub fish_kind: String,
     pub fish_id: Uuid,
     pub order_index: i32,
+    pub wave: i32,
     pub is_caught: bool,
     pub caught_at: Option<DateTime<Utc>>,
 }
+
+/// 开局抽中的一条鱼（普通对局随机抽样，每日挑战按当天固定顺序）
+#[derive(Debug, Clone)]
+pub struct SelectedFish {
+    pub kind: FishKind,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyStreak {
+    pub current_streak: i32,
+    pub best_streak: i32,
+    pub last_date: NaiveDate,
+}
+
+/// 单人模式关卡定义；`theme_id` 为空表示通用关卡，主题关卡优先
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SinglePlayerLevel {
+    pub id: Uuid,
+    pub level: i32,
+    pub theme_id: Option<Uuid>,
+    pub total_fish: i32,
+    pub ai_count: i32,
+    pub seconds: i32,
+    pub max_mistakes: i32,
+    pub target_total: i32,
+    pub difficulty_level: i32,
+    /// 每抓对一条 AI 鱼延长的秒数
+    pub time_bonus_seconds: i32,
+    /// 每次误抓扣除的秒数
+    pub mistake_penalty_seconds: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SinglePlayerLevel {
+    pub fn human_count(&self) -> i32 {
+        self.total_fish - self.ai_count
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelResponse {
+    pub level: i32,
+    pub total_fish: i32,
+    pub ai_count: i32,
+    pub seconds: i32,
+    pub max_mistakes: i32,
+    pub target_total: i32,
+    pub time_bonus_seconds: i32,
+    pub mistake_penalty_seconds: i32,
+}
+
+impl From<SinglePlayerLevel> for LevelResponse {
+    fn from(l: SinglePlayerLevel) -> Self {
+        Self {
+            level: l.level,
+            total_fish: l.total_fish,
+            ai_count: l.ai_count,
+            seconds: l.seconds,
+            max_mistakes: l.max_mistakes,
+            target_total: l.target_total,
+            time_bonus_seconds: l.time_bonus_seconds,
+            mistake_penalty_seconds: l.mistake_penalty_seconds,
+        }
+    }
+}
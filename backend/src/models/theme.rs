@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -15,12 +16,14 @@ pub struct Theme {
     pub ai_prompt_style: String,
     pub spawn_rate: i32,
     pub max_imposters: i32,
+    pub spawn_policy: serde_json::Value, // JSONB: {"type": "fixed_ratio"}，见 services::spawn_policy
+    pub author_corpus: serde_json::Value, // JSONB: {"locales": ["zh"], "names": []}，见 services::author_names
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 /// 主题前端响应格式
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ThemeResponse {
     pub theme_id: String,
     pub theme_name: String,
@@ -30,20 +33,20 @@ pub struct ThemeResponse {
     pub game_rules: GameRules,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ThemeAssets {
     pub background_url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub particle_effect: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct AiSettings {
     pub keywords: Vec<String>,
     pub prompt_style: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct GameRules {
     pub spawn_rate: i32,
     pub max_imposters: i32,
@@ -0,0 +1,182 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::Theme;
+use crate::services::mimicry::MimicStyle;
+use crate::services::{ai_fish_buffer, auth, ApiError, AppState};
+
+type AdminAuth = TypedHeader<Authorization<Bearer>>;
+
+/// `failed` 是引入重试队列前的终态，旧任务仍可能停在这个状态，和 `dead` 一样可以重试
+const TASK_STATUSES: &[&str] = &["pending", "dispatched", "completed", "dead", "failed"];
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTasksQuery {
+    /// 默认只看死信
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+/// 任务概要（不含图片数据）
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminAiTaskResponse {
+    pub id: Uuid,
+    pub room_id: Option<Uuid>,
+    pub theme_id: Option<Uuid>,
+    pub status: String,
+    pub keyword: Option<String>,
+    pub prompt: Option<String>,
+    /// 产出并已投放的 drawing
+    pub drawing_id: Option<Uuid>,
+    pub style_reference_ids: Option<Vec<Uuid>>,
+    pub retry_count: i32,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+const TASK_COLUMNS: &str = "id, room_id, theme_id, status, keyword, prompt, drawing_id, \
+     style_reference_ids, retry_count, error_message, created_at, dispatched_at, next_attempt_at, completed_at";
+
+/// GET /api/admin/ai-tasks - 按状态列出 n8n 生成任务
+pub async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Query(query): Query<ListTasksQuery>,
+) -> Result<Json<Vec<AdminAiTaskResponse>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let status = query.status.unwrap_or_else(|| "dead".to_string());
+    if !TASK_STATUSES.contains(&status.as_str()) {
+        return Err(ApiError::BadRequest("Invalid status".to_string()));
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<AdminAiTaskResponse> = sqlx::query_as(&format!(
+        "SELECT {} FROM ai_tasks WHERE status = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
+        TASK_COLUMNS
+    ))
+    .bind(&status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(rows))
+}
+
+#[derive(sqlx::FromRow)]
+struct RetryTarget {
+    status: String,
+    room_id: Option<Uuid>,
+    theme_id: Option<Uuid>,
+    has_payload: bool,
+}
+
+/// POST /api/admin/ai-tasks/:task_id/retry - 死信任务重新入队（重置重试次数）
+///
+/// 引入任务队列前的旧任务没有请求体，按任务的主题和房间重建一份再入队
+pub async fn retry_task(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<AdminAiTaskResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let mut tx = state.db.begin().await?;
+    let target: RetryTarget = sqlx::query_as(
+        r#"
+        SELECT status, room_id, theme_id, request_payload IS NOT NULL AS has_payload
+        FROM ai_tasks
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(task_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+
+    if !["dead", "failed", "pending"].contains(&target.status.as_str()) {
+        return Err(ApiError::BadRequest("Task is not retryable".to_string()));
+    }
+
+    if !target.has_payload {
+        let theme: Theme = sqlx::query_as(
+            r#"
+            SELECT t.* FROM themes t
+            WHERE t.id = COALESCE($1, (SELECT r.theme_id FROM rooms r WHERE r.id = $2))
+            "#,
+        )
+        .bind(target.theme_id)
+        .bind(target.room_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "Task has no request payload and no theme to rebuild it from".to_string(),
+            )
+        })?;
+
+        let mut request = ai_fish_buffer::build_request(
+            &mut tx,
+            &state,
+            &theme,
+            target.room_id,
+            MimicStyle::default(),
+        )
+        .await?;
+        request.task_id = task_id;
+        let nonce = request.callback.as_ref().map(|c| c.nonce.clone());
+
+        sqlx::query(
+            r#"
+            UPDATE ai_tasks
+            SET request_payload = $2, callback_nonce = $3, keyword = $4, prompt = $5, theme_id = $6
+            WHERE id = $1
+            "#,
+        )
+        .bind(task_id)
+        .bind(serde_json::to_value(&request).unwrap_or_default())
+        .bind(&nonce)
+        .bind(&request.keyword)
+        .bind(&request.prompt)
+        .bind(theme.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let row: AdminAiTaskResponse = sqlx::query_as(&format!(
+        r#"
+        UPDATE ai_tasks
+        SET status = 'pending', retry_count = 0, dispatched_at = NULL, next_attempt_at = NOW()
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        TASK_COLUMNS
+    ))
+    .bind(task_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(row))
+}
@@ -0,0 +1,581 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{
+    drawing_image_url, Drawing, FishCalibrationReportRow, FishKind, LibraryFish, LibraryFishSummary,
+};
+use crate::services::fish_calibration::{run_calibration, CalibrationSummary};
+use crate::services::fish_library::{
+    insert_library_fish, validate_library_fields, InsertOutcome, NewLibraryFish, MAX_IMPORT_BATCH,
+};
+use crate::services::image_store::encode_data_url;
+use crate::services::{auth, ApiError, AppState};
+
+type AdminAuth = TypedHeader<Authorization<Bearer>>;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFishQuery {
+    #[serde(default)]
+    pub difficulty_level: Option<i32>,
+    #[serde(default)]
+    pub is_active: Option<bool>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFishRequest {
+    pub image_data: String,
+    #[serde(default = "default_difficulty_level")]
+    pub difficulty_level: i32,
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    #[serde(default = "default_metadata")]
+    pub metadata: serde_json::Value,
+    #[serde(default = "default_is_active")]
+    pub is_active: bool,
+}
+
+fn default_difficulty_level() -> i32 {
+    1
+}
+
+fn default_weight() -> i32 {
+    1
+}
+
+fn default_metadata() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+fn default_is_active() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFishRequest {
+    pub items: Vec<CreateFishRequest>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFishRequest {
+    #[serde(default)]
+    pub difficulty_level: Option<i32>,
+    #[serde(default)]
+    pub weight: Option<i32>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub is_active: Option<bool>,
+    /// 关闭后后台校准任务不再改动该鱼的难度与权重
+    #[serde(default)]
+    pub auto_calibrate: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidatesQuery {
+    #[serde(default)]
+    pub kind: Option<FishKind>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationQuery {
+    #[serde(default)]
+    pub kind: Option<FishKind>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoteDrawingRequest {
+    pub drawing_id: Uuid,
+    #[serde(default = "default_difficulty_level")]
+    pub difficulty_level: i32,
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    #[serde(default = "default_is_active")]
+    pub is_active: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateFishResponse {
+    pub duplicate: bool,
+    pub kind: FishKind,
+    pub fish_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fish: Option<LibraryFishSummary>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportItemResult {
+    pub index: usize,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fish_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<DuplicateRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateRef {
+    pub kind: FishKind,
+    pub fish_id: Uuid,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFishResponse {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub results: Vec<ImportItemResult>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromotionCandidate {
+    pub drawing_id: Uuid,
+    pub room_code: String,
+    pub kind: FishKind,
+    pub name: String,
+    pub description: Option<String>,
+    pub author_name: String,
+    pub image_url: String,
+    pub vote_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CreateFishResponse {
+    fn new(kind: FishKind, outcome: InsertOutcome) -> Self {
+        match outcome {
+            InsertOutcome::Inserted(row) => Self {
+                duplicate: false,
+                kind,
+                fish_id: row.id,
+                fish: Some(LibraryFishSummary::from_row(kind, row)),
+            },
+            InsertOutcome::Duplicate { kind, id } => Self {
+                duplicate: true,
+                kind,
+                fish_id: id,
+                fish: None,
+            },
+        }
+    }
+}
+
+impl From<CreateFishRequest> for NewLibraryFish {
+    fn from(req: CreateFishRequest) -> Self {
+        Self {
+            image_data: req.image_data,
+            difficulty_level: req.difficulty_level,
+            weight: req.weight,
+            metadata: req.metadata,
+            is_active: req.is_active,
+            source_drawing_id: None,
+        }
+    }
+}
+
+/// GET /api/admin/fish/:kind - 鱼库列表 (不含 image_data)
+pub async fn list_fish(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path(kind): Path<FishKind>,
+    Query(query): Query<ListFishQuery>,
+) -> Result<Json<Vec<LibraryFishSummary>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<LibraryFish> = sqlx::query_as(&format!(
+        r#"
+        SELECT * FROM {}
+        WHERE ($1::int IS NULL OR difficulty_level = $1)
+          AND ($2::bool IS NULL OR is_active = $2)
+        ORDER BY difficulty_level, created_at DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        kind.table()
+    ))
+    .bind(query.difficulty_level)
+    .bind(query.is_active)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|f| LibraryFishSummary::from_row(kind, f))
+            .collect(),
+    ))
+}
+
+/// POST /api/admin/fish/:kind - 上传单条鱼
+pub async fn create_fish(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path(kind): Path<FishKind>,
+    Json(req): Json<CreateFishRequest>,
+) -> Result<Json<CreateFishResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let outcome = insert_library_fish(&state, kind, req.into()).await?;
+    Ok(Json(CreateFishResponse::new(kind, outcome)))
+}
+
+/// POST /api/admin/fish/:kind/import - 批量导入（逐条入库，单条失败不影响其他）
+pub async fn import_fish(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path(kind): Path<FishKind>,
+    Json(req): Json<ImportFishRequest>,
+) -> Result<Json<ImportFishResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    if req.items.len() > MAX_IMPORT_BATCH {
+        return Err(ApiError::BadRequest(format!(
+            "At most {} items per import",
+            MAX_IMPORT_BATCH
+        )));
+    }
+
+    let mut resp = ImportFishResponse {
+        imported: 0,
+        duplicates: 0,
+        failed: 0,
+        results: Vec::with_capacity(req.items.len()),
+    };
+
+    for (index, item) in req.items.into_iter().enumerate() {
+        let result = match insert_library_fish(&state, kind, item.into()).await {
+            Ok(InsertOutcome::Inserted(row)) => {
+                resp.imported += 1;
+                ImportItemResult {
+                    index,
+                    status: "imported",
+                    fish_id: Some(row.id),
+                    duplicate_of: None,
+                    error: None,
+                }
+            }
+            Ok(InsertOutcome::Duplicate { kind, id }) => {
+                resp.duplicates += 1;
+                ImportItemResult {
+                    index,
+                    status: "duplicate",
+                    fish_id: None,
+                    duplicate_of: Some(DuplicateRef { kind, fish_id: id }),
+                    error: None,
+                }
+            }
+            Err(e) => {
+                resp.failed += 1;
+                let message = match e {
+                    ApiError::BadRequest(msg) | ApiError::NotFound(msg) => msg,
+                    _ => "Import failed".to_string(),
+                };
+                ImportItemResult {
+                    index,
+                    status: "error",
+                    fish_id: None,
+                    duplicate_of: None,
+                    error: Some(message),
+                }
+            }
+        };
+        resp.results.push(result);
+    }
+
+    tracing::info!(
+        "Fish import into {}: imported={} duplicates={} failed={}",
+        kind.table(),
+        resp.imported,
+        resp.duplicates,
+        resp.failed
+    );
+    Ok(Json(resp))
+}
+
+/// PATCH /api/admin/fish/:kind/:fish_id - 调整难度/权重/元数据/上下架
+pub async fn update_fish(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path((kind, fish_id)): Path<(FishKind, Uuid)>,
+    Json(req): Json<UpdateFishRequest>,
+) -> Result<Json<LibraryFishSummary>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    validate_library_fields(
+        req.difficulty_level.unwrap_or(1),
+        req.weight.unwrap_or(1),
+        req.metadata.as_ref().unwrap_or(&serde_json::json!({})),
+    )?;
+
+    let row: LibraryFish = sqlx::query_as(&format!(
+        r#"
+        UPDATE {}
+        SET difficulty_level = COALESCE($2, difficulty_level),
+            weight = COALESCE($3, weight),
+            metadata = COALESCE($4, metadata),
+            is_active = COALESCE($5, is_active),
+            auto_calibrate = COALESCE($6, auto_calibrate),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+        kind.table()
+    ))
+    .bind(fish_id)
+    .bind(req.difficulty_level)
+    .bind(req.weight)
+    .bind(&req.metadata)
+    .bind(req.is_active)
+    .bind(req.auto_calibrate)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(ApiError::NotFound("Fish not found".to_string()))?;
+
+    Ok(Json(LibraryFishSummary::from_row(kind, row)))
+}
+
+/// GET /api/admin/fish/:kind/:fish_id/image - 管理端预览原图
+pub async fn get_fish_image(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path((kind, fish_id)): Path<(FishKind, Uuid)>,
+) -> Result<Response, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let image_data: String = sqlx::query_scalar(&format!(
+        "SELECT image_data FROM {} WHERE id = $1",
+        kind.table()
+    ))
+    .bind(fish_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(ApiError::NotFound("Fish not found".to_string()))?;
+
+    let img = state.image_store.read_image_data(&image_data).await?;
+    let mut resp = (StatusCode::OK, img.bytes).into_response();
+    resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(img.content_type),
+    );
+    resp.headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    Ok(resp)
+}
+
+/// 可转入鱼库的画作条件（`d` 为 drawings，`r` 为所在房间），候选列表和转存共用
+const PROMOTABLE_DRAWING: &str = "r.status = 'gameover' \
+     AND d.is_hidden = FALSE AND d.report_count = 0 AND d.is_eliminated = FALSE";
+
+/// GET /api/admin/fish/candidates - 多人局中表现良好、可转入鱼库的画作
+///
+/// 条件：房间已结束、未被举报隐藏、存活到终局、尚未转存过。
+/// 人类画作 → `human_fish`；骗过所有人的 AI 画作 → `ai_fish`。
+pub async fn list_candidates(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Query(query): Query<CandidatesQuery>,
+) -> Result<Json<Vec<PromotionCandidate>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    #[derive(sqlx::FromRow)]
+    struct CandidateRow {
+        id: Uuid,
+        room_code: String,
+        is_ai: bool,
+        name: String,
+        description: Option<String>,
+        author_name: String,
+        vote_count: i32,
+        created_at: DateTime<Utc>,
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let is_ai = query.kind.map(|k| k == FishKind::Ai);
+
+    let rows: Vec<CandidateRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT d.id, r.room_code, d.is_ai, d.name, d.description, d.author_name,
+               d.vote_count, d.created_at
+        FROM drawings d
+        JOIN rooms r ON r.id = d.room_id
+        WHERE {}
+          AND ($1::bool IS NULL OR d.is_ai = $1)
+          AND NOT EXISTS (SELECT 1 FROM human_fish h WHERE h.source_drawing_id = d.id)
+          AND NOT EXISTS (SELECT 1 FROM ai_fish a WHERE a.source_drawing_id = d.id)
+        ORDER BY d.created_at DESC
+        LIMIT $2
+        "#,
+        PROMOTABLE_DRAWING
+    ))
+    .bind(is_ai)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|r| PromotionCandidate {
+                drawing_id: r.id,
+                room_code: r.room_code,
+                kind: if r.is_ai {
+                    FishKind::Ai
+                } else {
+                    FishKind::Human
+                },
+                name: r.name,
+                description: r.description,
+                author_name: r.author_name,
+                image_url: drawing_image_url(r.id),
+                vote_count: r.vote_count,
+                created_at: r.created_at,
+            })
+            .collect(),
+    ))
+}
+
+/// POST /api/admin/fish/promote - 将多人局画作转存到对应鱼库（条件同候选列表，不满足返回 409）
+pub async fn promote_drawing(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Json(req): Json<PromoteDrawingRequest>,
+) -> Result<Json<CreateFishResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let drawing: Drawing = sqlx::query_as("SELECT * FROM drawings WHERE id = $1")
+        .bind(req.drawing_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or(ApiError::NotFound("Drawing not found".to_string()))?;
+
+    let promotable: bool = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM drawings d JOIN rooms r ON r.id = d.room_id WHERE d.id = $1 AND {})",
+        PROMOTABLE_DRAWING
+    ))
+    .bind(drawing.id)
+    .fetch_one(&state.db)
+    .await?;
+    if !promotable {
+        return Err(ApiError::Conflict(
+            "Drawing is not eligible for promotion".to_string(),
+        ));
+    }
+
+    // 画作真实身份决定入哪个库，避免人为贴错标签
+    let kind = if drawing.is_ai {
+        FishKind::Ai
+    } else {
+        FishKind::Human
+    };
+
+    let img = state
+        .image_store
+        .read_image_data(&drawing.image_data)
+        .await?;
+    let metadata = serde_json::json!({
+        "authorName": drawing.author_name,
+        "createDate": drawing.created_at.format("%Y-%m-%d").to_string(),
+        "description": drawing.description.clone().unwrap_or_else(|| drawing.name.clone()),
+        "name": drawing.name,
+    });
+
+    let outcome = insert_library_fish(
+        &state,
+        kind,
+        NewLibraryFish {
+            image_data: encode_data_url(&img),
+            difficulty_level: req.difficulty_level,
+            weight: req.weight,
+            metadata,
+            is_active: req.is_active,
+            source_drawing_id: Some(drawing.id),
+        },
+    )
+    .await?;
+
+    Ok(Json(CreateFishResponse::new(kind, outcome)))
+}
+
+/// GET /api/admin/fish/calibration - 难度校准报表（按迷惑率降序）
+pub async fn calibration_report(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Query(query): Query<CalibrationQuery>,
+) -> Result<Json<Vec<FishCalibrationReportRow>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let mut rows: Vec<FishCalibrationReportRow> = Vec::new();
+    for kind in [FishKind::Human, FishKind::Ai] {
+        if query.kind.is_some_and(|k| k != kind) {
+            continue;
+        }
+        let part: Vec<FishCalibrationReportRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT s.fish_kind, s.fish_id, s.exposures, s.catches, s.fool_rate,
+                   s.previous_level, s.calibrated_level, s.previous_weight,
+                   s.calibrated_weight, s.calibrated_at,
+                   f.difficulty_level, f.weight, f.is_active, f.auto_calibrate
+            FROM fish_calibration_stats s
+            JOIN {} f ON f.id = s.fish_id
+            WHERE s.fish_kind = $1
+            ORDER BY s.fool_rate DESC
+            LIMIT $2
+            "#,
+            kind.table()
+        ))
+        .bind(kind.run_fish_kind())
+        .bind(limit)
+        .fetch_all(&state.db)
+        .await?;
+        rows.extend(part);
+    }
+
+    rows.sort_by(|a, b| b.fool_rate.total_cmp(&a.fool_rate));
+    rows.truncate(limit as usize);
+    Ok(Json(rows))
+}
+
+/// POST /api/admin/fish/calibration/run - 立即执行一次校准
+pub async fn run_calibration_now(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+) -> Result<Json<CalibrationSummary>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let summary = run_calibration(&state).await?;
+    Ok(Json(summary))
+}
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::services::imposter_stats::{self, SourceStats, StatsGroup};
+use crate::services::{auth, ApiError, AppState};
+
+type AdminAuth = TypedHeader<Authorization<Bearer>>;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListImpostersQuery {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryQuery {
+    /// 默认按预置鱼聚合
+    #[serde(default)]
+    pub group_by: Option<StatsGroup>,
+}
+
+/// 单条 AI 鱼的表现
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ImposterStats {
+    pub drawing_id: Uuid,
+    pub room_code: String,
+    pub name: String,
+    pub preset_fish_id: Option<i16>,
+    pub task_id: Option<Uuid>,
+    pub keyword: Option<String>,
+    /// eliminated / survived / alive
+    pub outcome: String,
+    pub survival_seconds: f64,
+    pub votes_received: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// GET /api/admin/imposters - 最近 AI 鱼的存活时长、累计得票与结局
+pub async fn list_imposters(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Query(query): Query<ListImpostersQuery>,
+) -> Result<Json<Vec<ImposterStats>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<ImposterStats> = sqlx::query_as(&format!(
+        r#"
+        SELECT d.id AS drawing_id, r.room_code, d.name, d.preset_fish_id,
+               t.id AS task_id, t.keyword,
+               {outcome} AS outcome,
+               {survival} AS survival_seconds,
+               COALESCE(d.votes_received, 0) AS votes_received,
+               d.created_at
+        FROM drawings d
+        JOIN rooms r ON r.id = d.room_id
+        LEFT JOIN ai_tasks t ON t.drawing_id = d.id
+        WHERE d.is_ai = TRUE
+        ORDER BY d.created_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+        outcome = imposter_stats::OUTCOME_SQL,
+        survival = imposter_stats::SURVIVAL_SECONDS_SQL,
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(rows))
+}
+
+/// GET /api/admin/imposters/summary?groupBy=preset|task|keyword - 按来源聚合，欺骗分从高到低
+pub async fn imposter_summary(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Json<Vec<SourceStats>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let group = query.group_by.unwrap_or(StatsGroup::Preset);
+    Ok(Json(imposter_stats::summarize(&state.db, group).await?))
+}
@@ -0,0 +1,300 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::SinglePlayerLevel;
+use crate::services::levels::{self, LibraryCapacity};
+use crate::services::{auth, ApiError, AppState};
+
+type AdminAuth = TypedHeader<Authorization<Bearer>>;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLevelRequest {
+    pub level: i32,
+    /// 主题 slug（`themes.theme_id`），为空表示通用关卡
+    #[serde(default)]
+    pub theme_id: Option<String>,
+    pub total_fish: i32,
+    pub ai_count: i32,
+    #[serde(default = "default_seconds")]
+    pub seconds: i32,
+    #[serde(default = "default_max_mistakes")]
+    pub max_mistakes: i32,
+    #[serde(default = "default_target_total")]
+    pub target_total: i32,
+    #[serde(default = "default_difficulty_level")]
+    pub difficulty_level: i32,
+    #[serde(default)]
+    pub time_bonus_seconds: i32,
+    #[serde(default)]
+    pub mistake_penalty_seconds: i32,
+    #[serde(default = "default_is_active")]
+    pub is_active: bool,
+}
+
+fn default_seconds() -> i32 {
+    60
+}
+
+fn default_max_mistakes() -> i32 {
+    3
+}
+
+fn default_target_total() -> i32 {
+    3
+}
+
+fn default_difficulty_level() -> i32 {
+    1
+}
+
+fn default_is_active() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateLevelRequest {
+    #[serde(default)]
+    pub total_fish: Option<i32>,
+    #[serde(default)]
+    pub ai_count: Option<i32>,
+    #[serde(default)]
+    pub seconds: Option<i32>,
+    #[serde(default)]
+    pub max_mistakes: Option<i32>,
+    #[serde(default)]
+    pub target_total: Option<i32>,
+    #[serde(default)]
+    pub difficulty_level: Option<i32>,
+    #[serde(default)]
+    pub time_bonus_seconds: Option<i32>,
+    #[serde(default)]
+    pub mistake_penalty_seconds: Option<i32>,
+    #[serde(default)]
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminLevelResponse {
+    pub id: Uuid,
+    pub level: i32,
+    pub theme_id: Option<String>,
+    pub total_fish: i32,
+    pub ai_count: i32,
+    pub seconds: i32,
+    pub max_mistakes: i32,
+    pub target_total: i32,
+    pub difficulty_level: i32,
+    pub time_bonus_seconds: i32,
+    pub mistake_penalty_seconds: i32,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub library: LibraryCapacity,
+    /// 当前鱼库是否足以开局（按不允许重复的严格口径）
+    pub library_sufficient: bool,
+}
+
+impl AdminLevelResponse {
+    fn new(l: SinglePlayerLevel, theme_slug: Option<String>, library: LibraryCapacity) -> Self {
+        let library_sufficient = library.check(&l, false).is_ok();
+        Self {
+            id: l.id,
+            level: l.level,
+            theme_id: theme_slug,
+            total_fish: l.total_fish,
+            ai_count: l.ai_count,
+            seconds: l.seconds,
+            max_mistakes: l.max_mistakes,
+            target_total: l.target_total,
+            difficulty_level: l.difficulty_level,
+            time_bonus_seconds: l.time_bonus_seconds,
+            mistake_penalty_seconds: l.mistake_penalty_seconds,
+            is_active: l.is_active,
+            created_at: l.created_at,
+            updated_at: l.updated_at,
+            library,
+            library_sufficient,
+        }
+    }
+}
+
+async fn to_response(
+    state: &AppState,
+    level: SinglePlayerLevel,
+) -> Result<AdminLevelResponse, ApiError> {
+    let mut conn = state.db.acquire().await?;
+    let theme_slug: Option<String> = match level.theme_id {
+        Some(id) => {
+            sqlx::query_scalar("SELECT theme_id FROM themes WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => None,
+    };
+    let library = levels::library_capacity(&mut conn, level.difficulty_level).await?;
+    Ok(AdminLevelResponse::new(level, theme_slug, library))
+}
+
+/// GET /api/admin/levels - 全部关卡（含停用），附带鱼库容量
+pub async fn list_levels(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+) -> Result<Json<Vec<AdminLevelResponse>>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let rows: Vec<SinglePlayerLevel> = sqlx::query_as(
+        "SELECT * FROM single_player_levels ORDER BY level ASC, theme_id NULLS FIRST",
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        out.push(to_response(&state, row).await?);
+    }
+    Ok(Json(out))
+}
+
+/// POST /api/admin/levels - 新建关卡
+pub async fn create_level(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Json(req): Json<CreateLevelRequest>,
+) -> Result<Json<AdminLevelResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let theme_uuid: Option<Uuid> = match req.theme_id.as_ref() {
+        Some(theme_id) => Some(
+            sqlx::query_scalar("SELECT id FROM themes WHERE theme_id = $1")
+                .bind(theme_id)
+                .fetch_optional(&state.db)
+                .await?
+                .ok_or(ApiError::BadRequest("Theme not found".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let now = Utc::now();
+    let level = SinglePlayerLevel {
+        id: Uuid::new_v4(),
+        level: req.level,
+        theme_id: theme_uuid,
+        total_fish: req.total_fish,
+        ai_count: req.ai_count,
+        seconds: req.seconds,
+        max_mistakes: req.max_mistakes,
+        target_total: req.target_total,
+        difficulty_level: req.difficulty_level,
+        time_bonus_seconds: req.time_bonus_seconds,
+        mistake_penalty_seconds: req.mistake_penalty_seconds,
+        is_active: req.is_active,
+        created_at: now,
+        updated_at: now,
+    };
+    levels::validate_level(&level)?;
+
+    let row: SinglePlayerLevel = sqlx::query_as(
+        r#"
+        INSERT INTO single_player_levels (
+            id, level, theme_id, total_fish, ai_count, seconds, max_mistakes,
+            target_total, difficulty_level, time_bonus_seconds, mistake_penalty_seconds, is_active
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING *
+        "#,
+    )
+    .bind(level.id)
+    .bind(level.level)
+    .bind(level.theme_id)
+    .bind(level.total_fish)
+    .bind(level.ai_count)
+    .bind(level.seconds)
+    .bind(level.max_mistakes)
+    .bind(level.target_total)
+    .bind(level.difficulty_level)
+    .bind(level.time_bonus_seconds)
+    .bind(level.mistake_penalty_seconds)
+    .bind(level.is_active)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::BadRequest("Level already exists".to_string())
+        }
+        other => other.into(),
+    })?;
+
+    Ok(Json(to_response(&state, row).await?))
+}
+
+/// PATCH /api/admin/levels/:level_id - 调整关卡参数 / 启停
+pub async fn update_level(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): AdminAuth,
+    Path(level_id): Path<Uuid>,
+    Json(req): Json<UpdateLevelRequest>,
+) -> Result<Json<AdminLevelResponse>, ApiError> {
+    auth::verify_admin_token(&state.config, auth_header.token())?;
+
+    let mut tx = state.db.begin().await?;
+
+    let mut level: SinglePlayerLevel =
+        sqlx::query_as("SELECT * FROM single_player_levels WHERE id = $1 FOR UPDATE")
+            .bind(level_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(ApiError::NotFound("Level not found".to_string()))?;
+
+    level.total_fish = req.total_fish.unwrap_or(level.total_fish);
+    level.ai_count = req.ai_count.unwrap_or(level.ai_count);
+    level.seconds = req.seconds.unwrap_or(level.seconds);
+    level.max_mistakes = req.max_mistakes.unwrap_or(level.max_mistakes);
+    level.target_total = req.target_total.unwrap_or(level.target_total);
+    level.difficulty_level = req.difficulty_level.unwrap_or(level.difficulty_level);
+    level.time_bonus_seconds = req.time_bonus_seconds.unwrap_or(level.time_bonus_seconds);
+    level.mistake_penalty_seconds = req
+        .mistake_penalty_seconds
+        .unwrap_or(level.mistake_penalty_seconds);
+    level.is_active = req.is_active.unwrap_or(level.is_active);
+    levels::validate_level(&level)?;
+
+    let row: SinglePlayerLevel = sqlx::query_as(
+        r#"
+        UPDATE single_player_levels
+        SET total_fish = $2, ai_count = $3, seconds = $4, max_mistakes = $5,
+            target_total = $6, difficulty_level = $7, time_bonus_seconds = $8,
+            mistake_penalty_seconds = $9, is_active = $10, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(level.id)
+    .bind(level.total_fish)
+    .bind(level.ai_count)
+    .bind(level.seconds)
+    .bind(level.max_mistakes)
+    .bind(level.target_total)
+    .bind(level.difficulty_level)
+    .bind(level.time_bonus_seconds)
+    .bind(level.mistake_penalty_seconds)
+    .bind(level.is_active)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(to_response(&state, row).await?))
+}
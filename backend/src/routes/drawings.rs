@@ -13,8 +13,10 @@ use uuid::Uuid;
 use crate::models::{
     CreateDrawingRequest, Drawing, DrawingResponse, ReportRequest, Room, Theme, VoteRequest,
 };
-use crate::services::{ApiError, AppState};
-use crate::ws::GameItemData;
+use crate::services::content_filter::Field;
+use crate::services::spawn_policy::SpawnTrigger;
+use crate::services::{mimicry, ApiError, AppState};
+use crate::ws::{ai_spawner, event_log, GameItemData};
 
 /// POST /api/rooms/:room_code/drawings - 提交绘画
 #[axum::debug_handler]
@@ -55,11 +57,16 @@ pub async fn create_drawing(
     let flip_x = rng.gen_bool(0.5);
 
     let drawing_id = Uuid::new_v4();
-    let name = if req.name.chars().count() > 8 {
-        req.name.chars().take(8).collect()
-    } else {
-        req.name.clone()
-    };
+    // 文本审核：截断到列宽并处理屏蔽词
+    let filter = &state.content_filter;
+    let name = filter.moderate(Field::DrawingName, &req.name)?;
+    let description = req
+        .description
+        .as_deref()
+        .map(|d| filter.moderate(Field::Description, d))
+        .transpose()?
+        .filter(|d| !d.is_empty());
+    let author_name = filter.moderate(Field::AuthorName, &req.author_name)?;
 
     let stored_image_data = state
         .image_store
@@ -81,8 +88,8 @@ pub async fn create_drawing(
     .bind(room.id)
     .bind(&stored_image_data)
     .bind(&name)
-    .bind(&req.description)
-    .bind(&req.author_name)
+    .bind(&description)
+    .bind(&author_name)
     .bind(position_x)
     .bind(position_y)
     .bind(velocity_x)
@@ -95,74 +102,41 @@ pub async fn create_drawing(
     // 广播人类玩家的 drawing 给房间所有人
     // 注意：提交者会收到两次（REST 响应 + Socket.IO 广播），前端需去重
     let user_item_data: GameItemData = drawing.clone().into();
-    if let Err(e) = io
-        .within(room_code.clone())
-        .emit("item:add", &user_item_data)
-    {
-        tracing::error!("Failed to emit item:add for user drawing: {}", e);
-    } else {
-        tracing::info!(
-            "Emitted item:add for user drawing {} to room {}",
-            drawing.id,
-            room_code
-        );
-    }
+    event_log::broadcast(
+        &state,
+        io.within(room_code.clone()),
+        &room_code,
+        &user_item_data,
+    )
+    .await;
+    tracing::info!(
+        "Emitted item:add for user drawing {} to room {}",
+        drawing.id,
+        room_code
+    );
 
     // 更新房间计数
-    let new_total: i32 = sqlx::query_scalar(
-        "UPDATE rooms SET total_items = total_items + 1, updated_at = NOW() WHERE id = $1 RETURNING total_items",
-    )
-    .bind(room.id)
-    .fetch_one(&state.db)
-    .await?;
+    sqlx::query("UPDATE rooms SET total_items = total_items + 1, updated_at = NOW() WHERE id = $1")
+        .bind(room.id)
+        .execute(&state.db)
+        .await?;
 
-    // 检查是否需要生成 AI 鱼
-    // spawn_rate = 5 表示每 5 条人类画作后生成 1 条 AI 鱼
-    if new_total % theme.spawn_rate == 0 {
-        let ai_fish_index = new_total / theme.spawn_rate; // 第几条 AI 鱼 (1, 2, 3...)
-
-        tracing::info!(
-            "AI fish trigger: total_items={}, ai_fish_index={}",
-            new_total,
-            ai_fish_index
-        );
-
-        // 尝试生成 AI 鱼的逻辑:
-        // 1. 前 5 条 AI 鱼: 优先从预置池随机选择
-        // 2. 第 6 条开始: 优先从 n8n 队列取，若为空则继续用预置池
-        let ai_drawing = if ai_fish_index <= 5 {
-            // 前 5 条 AI 鱼：从预置池随机选择
-            state.spawn_preset_ai_fish(room.id).await
-        } else {
-            // 第 6 条开始：优先用 n8n 队列
-            if let Some(d) = state.try_spawn_from_n8n_queue(room.id).await {
-                Some(d)
-            } else {
-                // n8n 队列为空，继续用预置池
-                state.spawn_preset_ai_fish(room.id).await
-            }
-        };
-
-        if let Some(ref ai_drawing) = ai_drawing {
-            tracing::info!("AI fish spawned: {} ({})", ai_drawing.name, ai_drawing.id);
-
-            // 广播 item:add 事件通知前端
-            let item_data: GameItemData = ai_drawing.clone().into();
-            if let Err(e) = io.within(room_code.clone()).emit("item:add", &item_data) {
-                tracing::error!("Failed to emit item:add for AI fish: {}", e);
-            } else {
-                tracing::info!(
-                    "Emitted item:add for AI fish {} to room {}",
-                    ai_drawing.id,
-                    room_code
-                );
-            }
-        } else {
-            tracing::warn!("Failed to spawn AI fish for room {}", room_code);
-        }
+    // 按主题出场策略检查是否需要投放 AI 鱼
+    if let Err(e) =
+        ai_spawner::maybe_spawn_ai(&io, &state, &room, &theme, SpawnTrigger::HumanDrawing).await
+    {
+        tracing::warn!("AI fish spawn check failed for room {}: {:?}", room_code, e);
+    }
 
-        // 后台继续触发 n8n 生成（填充队列供后续使用）
-        state.trigger_ai_generation(room.id, &theme).await;
+    // 拟态：后台为房间准备一条参考本房间画风的 AI 鱼
+    {
+        let state = state.clone();
+        let room = room.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mimicry::ensure_room_mimic(&state, &room, &theme).await {
+                tracing::warn!("Mimic task for room {} failed: {:?}", room.room_code, e);
+            }
+        });
     }
 
     // 注意: Socket.IO 广播由 socketio_handler 处理
@@ -244,7 +218,7 @@ pub async fn vote_drawing(
 
     // 更新投票计数
     let new_count: i32 = sqlx::query_scalar(
-        "UPDATE drawings SET vote_count = vote_count + 1, updated_at = NOW() WHERE id = $1 RETURNING vote_count",
+        "UPDATE drawings SET vote_count = vote_count + 1, votes_received = votes_received + 1, updated_at = NOW() WHERE id = $1 RETURNING vote_count",
     )
     .bind(drawing_id)
     .fetch_one(&state.db)
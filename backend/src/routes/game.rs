@@ -1,16 +1,40 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use chrono::{Duration, Utc};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{Duration, NaiveDate, Utc};
 use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use sqlx::PgConnection;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::models::{single_player_fish_image_url, SinglePlayerRun, SinglePlayerRunFish};
-use crate::services::{image_store::decode_image_data, ApiError, AppState};
+use crate::models::{
+    single_player_fish_image_url, DailyStreak, FishKind, LevelResponse, SelectedFish,
+    SinglePlayerLevel, SinglePlayerRun, SinglePlayerRunFish,
+};
+use crate::services::anti_cheat::{self, CatchTiming};
+use crate::services::leaderboard::{self, Board, LeaderboardEntry};
+use crate::services::run_sweeper::{self, RunOwner};
+use crate::services::{auth, daily_challenge, endless, levels, ApiError, AppState};
+
+type OptionalAuth = Option<TypedHeader<Authorization<Bearer>>>;
+
+/// 可选登录：带 token 时必须有效，返回用户 ID
+async fn optional_user_id(
+    state: &AppState,
+    auth_header: &OptionalAuth,
+) -> Result<Option<Uuid>, ApiError> {
+    match auth_header {
+        Some(TypedHeader(h)) => Ok(Some(auth::user_id_from_token(&state.db, h.token()).await?)),
+        None => Ok(None),
+    }
+}
 
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,17 +43,27 @@ pub struct StartGameRequest {
     pub level: i32,
     #[serde(default)]
     pub theme_id: Option<String>,
+    /// 上次开局下发的 owner token（匿名玩家）
+    #[serde(default)]
+    pub owner_token: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StartGameResponse {
     pub run_id: String,
+    pub mode: String,
+    pub wave: i32,
     pub ends_at: chrono::DateTime<chrono::Utc>,
     pub lives_remaining: i32,
     pub target_total: i32,
     pub targets_found: i32,
+    pub time_bonus_seconds: i32,
+    pub mistake_penalty_seconds: i32,
     pub fish: Vec<FishCard>,
+    /// 匿名对局的 owner token，下次开局带回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_token: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -37,71 +71,50 @@ pub struct StartGameResponse {
 pub struct FishCard {
     pub fish_instance_id: String,
     pub image_url: String,
-    pub difficulty_level: i32,
-    pub metadata: serde_json::Value,
 }
 
-#[derive(Clone, Copy)]
-struct LevelConfig {
-    total: i32,
-    ai: i32,
-    seconds: i32,
-    max_mistakes: i32,
-    target_total: i32,
-    difficulty_level: i32,
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListLevelsQuery {
+    #[serde(default)]
+    pub theme_id: Option<String>,
 }
 
-fn level_config(level: i32) -> Option<LevelConfig> {
-    match level {
-        1 => Some(LevelConfig {
-            total: 10,
-            ai: 3,
-            seconds: 60,
-            max_mistakes: 3,
-            target_total: 3,
-            difficulty_level: 1,
-        }),
-        2 => Some(LevelConfig {
-            total: 20,
-            ai: 5,
-            seconds: 60,
-            max_mistakes: 3,
-            target_total: 3,
-            difficulty_level: 2,
-        }),
-        3 => Some(LevelConfig {
-            total: 30,
-            ai: 7,
-            seconds: 60,
-            max_mistakes: 3,
-            target_total: 3,
-            difficulty_level: 3,
-        }),
-        _ => None,
-    }
+/// GET /api/game/levels - 可玩关卡列表（主题专属关卡覆盖通用关卡）
+pub async fn list_levels(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListLevelsQuery>,
+) -> Result<Json<Vec<LevelResponse>>, ApiError> {
+    let mut conn = state.db.acquire().await?;
+
+    let theme_uuid: Option<Uuid> = match query.theme_id.as_ref() {
+        Some(theme_id) => {
+            sqlx::query_scalar("SELECT id FROM themes WHERE theme_id = $1")
+                .bind(theme_id)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        None => None,
+    };
+
+    let rows = levels::list_levels(&mut conn, theme_uuid).await?;
+    Ok(Json(rows.into_iter().map(Into::into).collect()))
 }
 
 #[derive(Clone, sqlx::FromRow)]
 struct FishLibraryRow {
     id: Uuid,
-    difficulty_level: i32,
-    metadata: serde_json::Value,
 }
 
 pub async fn start_game(
     State(state): State<Arc<AppState>>,
+    auth_header: OptionalAuth,
     Json(req): Json<StartGameRequest>,
 ) -> Result<Json<StartGameResponse>, ApiError> {
-    let cfg = level_config(req.level).ok_or(ApiError::BadRequest("Invalid level".to_string()))?;
+    let user_id = optional_user_id(&state, &auth_header).await?;
+    let owner = RunOwner::new(user_id, req.owner_token.as_deref());
     let allow_duplicates = req.level <= state.config.single_player_allow_duplicates_max_level;
 
-    let human_needed = cfg.total - cfg.ai;
-    if human_needed < 0 {
-        return Err(ApiError::BadRequest("Invalid level config".to_string()));
-    }
-
-    let ends_at = Utc::now() + Duration::seconds(cfg.seconds as i64);
-
     let mut tx = state.db.begin().await?;
     let mut rng = StdRng::from_entropy();
 
@@ -116,160 +129,542 @@ pub async fn start_game(
         None
     };
 
-    let run_id = Uuid::new_v4();
-    let run: SinglePlayerRun = sqlx::query_as(
-        r#"
-        INSERT INTO single_player_runs (
-            id, session_id, theme_id, level, status,
-            max_mistakes, mistakes, target_total, targets_found, ends_at
-        )
-        VALUES ($1, $2, $3, $4, 'active', $5, 0, $6, 0, $7)
-        RETURNING *
-        "#,
-    )
-    .bind(run_id)
-    .bind(&req.session_id)
-    .bind(theme_uuid)
-    .bind(req.level)
-    .bind(cfg.max_mistakes)
-    .bind(cfg.target_total)
-    .bind(ends_at)
-    .fetch_one(&mut *tx)
-    .await?;
+    let cfg = levels::resolve_level(&mut tx, req.level, theme_uuid)
+        .await?
+        .ok_or(ApiError::BadRequest("Invalid level".to_string()))?;
 
-    let mut humans: Vec<FishLibraryRow> = sqlx::query_as(
-        r#"
-        SELECT id, difficulty_level, metadata
-        FROM human_fish
-        WHERE is_active = TRUE AND difficulty_level = $1
-        ORDER BY (-LN(random()) / GREATEST(weight, 1)) ASC
-        LIMIT $2
-        "#,
+    let human_needed = cfg.human_count();
+    if human_needed < 0 {
+        return Err(ApiError::BadRequest("Invalid level config".to_string()));
+    }
+
+    levels::library_capacity(&mut tx, cfg.difficulty_level)
+        .await?
+        .check(&cfg, allow_duplicates)
+        .map_err(|msg| ApiError::BadRequest(msg.to_string()))?;
+
+    run_sweeper::ensure_run_capacity(&mut tx, state.config.single_player_max_active_runs, &owner)
+        .await?;
+
+    let run = insert_run(
+        &mut tx,
+        &req.session_id,
+        theme_uuid,
+        &cfg,
+        &owner,
+        None,
+        "classic",
     )
-    .bind(cfg.difficulty_level)
-    .bind(human_needed)
-    .fetch_all(&mut *tx)
     .await?;
 
-    let mut ais: Vec<FishLibraryRow> = sqlx::query_as(
-        r#"
-        SELECT id, difficulty_level, metadata
-        FROM ai_fish
-        WHERE is_active = TRUE AND difficulty_level = $1
-        ORDER BY (-LN(random()) / GREATEST(weight, 1)) ASC
-        LIMIT $2
-        "#,
+    let selected = sample_fish(
+        &mut tx,
+        cfg.difficulty_level,
+        human_needed,
+        cfg.ai_count,
+        allow_duplicates,
+        None,
+        &mut rng,
     )
-    .bind(cfg.difficulty_level)
-    .bind(cfg.ai)
-    .fetch_all(&mut *tx)
     .await?;
 
-    if human_needed > 0 && humans.is_empty() {
-        return Err(ApiError::BadRequest(
-            "Not enough human fish library data".to_string(),
-        ));
-    }
-    if cfg.ai > 0 && ais.is_empty() {
-        return Err(ApiError::BadRequest(
-            "Not enough ai fish library data".to_string(),
-        ));
-    }
+    let fish_cards = insert_run_fish(&mut tx, run.id, 1, 0, selected).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(StartGameResponse::new(&run, fish_cards)))
+}
 
-    if humans.len() < human_needed as usize || ais.len() < cfg.ai as usize {
-        if !allow_duplicates {
+/// 按权重从鱼库抽取本局/本波的鱼并打乱顺序；`exclude_run` 排除该对局已出现过的鱼
+async fn sample_fish(
+    conn: &mut PgConnection,
+    difficulty_level: i32,
+    human_needed: i32,
+    ai_needed: i32,
+    allow_duplicates: bool,
+    exclude_run: Option<Uuid>,
+    rng: &mut StdRng,
+) -> Result<Vec<SelectedFish>, ApiError> {
+    let mut selected: Vec<SelectedFish> = Vec::with_capacity((human_needed + ai_needed) as usize);
+
+    for (kind, needed) in [(FishKind::Human, human_needed), (FishKind::Ai, ai_needed)] {
+        if needed <= 0 {
+            continue;
+        }
+
+        let mut rows: Vec<FishLibraryRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT id
+            FROM {}
+            WHERE is_active = TRUE AND difficulty_level = $1
+              AND ($3::uuid IS NULL
+                   OR id NOT IN (SELECT fish_id FROM single_player_run_fish WHERE run_id = $3))
+            ORDER BY (-LN(random()) / GREATEST(weight, 1)) ASC
+            LIMIT $2
+            "#,
+            kind.table()
+        ))
+        .bind(difficulty_level)
+        .bind(needed)
+        .bind(exclude_run)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        if rows.is_empty() {
+            return Err(ApiError::BadRequest(format!(
+                "Not enough {} fish library data",
+                kind.as_str()
+            )));
+        }
+        if rows.len() < needed as usize && !allow_duplicates {
             return Err(ApiError::BadRequest(
                 "Not enough fish library data".to_string(),
             ));
         }
-
-        while humans.len() < human_needed as usize {
-            let picked = humans
-                .choose(&mut rng)
-                .cloned()
-                .ok_or(ApiError::BadRequest(
-                    "Not enough human fish library data".to_string(),
-                ))?;
-            humans.push(picked);
-        }
-        while ais.len() < cfg.ai as usize {
-            let picked = ais.choose(&mut rng).cloned().ok_or(ApiError::BadRequest(
-                "Not enough ai fish library data".to_string(),
+        while rows.len() < needed as usize {
+            let picked = rows.choose(rng).cloned().ok_or(ApiError::BadRequest(
+                "Not enough fish library data".to_string(),
             ))?;
-            ais.push(picked);
+            rows.push(picked);
         }
-    }
 
-    enum Kind {
-        Human,
-        Ai,
+        selected.extend(rows.into_iter().map(|f| SelectedFish { kind, id: f.id }));
     }
 
-    struct SelectedFish {
-        kind: Kind,
-        id: Uuid,
-        difficulty_level: i32,
-        metadata: serde_json::Value,
+    selected.shuffle(rng);
+    Ok(selected)
+}
+
+impl StartGameResponse {
+    fn new(run: &SinglePlayerRun, fish: Vec<FishCard>) -> Self {
+        Self {
+            run_id: run.id.to_string(),
+            mode: run.mode.clone(),
+            wave: run.wave,
+            ends_at: run.ends_at,
+            lives_remaining: run.max_mistakes - run.mistakes,
+            target_total: run.target_total,
+            targets_found: run.targets_found,
+            time_bonus_seconds: run.time_bonus_seconds,
+            mistake_penalty_seconds: run.mistake_penalty_seconds,
+            fish,
+            owner_token: run.owner_token.clone(),
+        }
     }
+}
+
+async fn insert_run(
+    conn: &mut PgConnection,
+    session_id: &str,
+    theme_uuid: Option<Uuid>,
+    cfg: &SinglePlayerLevel,
+    owner: &RunOwner,
+    challenge_date: Option<NaiveDate>,
+    mode: &str,
+) -> Result<SinglePlayerRun, sqlx::Error> {
+    let ends_at = Utc::now() + Duration::seconds(cfg.seconds as i64);
 
-    let mut selected: Vec<SelectedFish> = Vec::with_capacity(cfg.total as usize);
-    selected.extend(humans.drain(..).map(|f| SelectedFish {
-        kind: Kind::Human,
-        id: f.id,
-        difficulty_level: f.difficulty_level,
-        metadata: f.metadata,
-    }));
-    selected.extend(ais.drain(..).map(|f| SelectedFish {
-        kind: Kind::Ai,
-        id: f.id,
-        difficulty_level: f.difficulty_level,
-        metadata: f.metadata,
-    }));
-
-    selected.shuffle(&mut rng);
+    sqlx::query_as(
+        r#"
+        INSERT INTO single_player_runs (
+            id, session_id, theme_id, level, status,
+            max_mistakes, mistakes, target_total, targets_found, ends_at,
+            level_id, time_bonus_seconds, mistake_penalty_seconds, user_id, challenge_date, mode,
+            difficulty_level, owner_token
+        )
+        VALUES ($1, $2, $3, $4, 'active', $5, 0, $6, 0, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(session_id)
+    .bind(theme_uuid)
+    .bind(cfg.level)
+    .bind(cfg.max_mistakes)
+    .bind(cfg.target_total)
+    .bind(ends_at)
+    .bind(cfg.id)
+    .bind(cfg.time_bonus_seconds)
+    .bind(cfg.mistake_penalty_seconds)
+    .bind(owner.user_id)
+    .bind(challenge_date)
+    .bind(mode)
+    .bind(cfg.difficulty_level)
+    .bind(&owner.owner_token)
+    .fetch_one(conn)
+    .await
+}
 
+/// 按给定顺序写入本局的鱼，返回给客户端的卡片（不暴露真实类型）
+async fn insert_run_fish(
+    conn: &mut PgConnection,
+    run_id: Uuid,
+    wave: i32,
+    first_order_index: i32,
+    selected: Vec<SelectedFish>,
+) -> Result<Vec<FishCard>, ApiError> {
     let mut fish_cards: Vec<FishCard> = Vec::with_capacity(selected.len());
 
-    for (order_index, fish) in selected.into_iter().enumerate() {
+    for (i, fish) in selected.into_iter().enumerate() {
+        let order_index = first_order_index + i as i32;
         let fish_instance_id = Uuid::new_v4();
-        let fish_kind = match fish.kind {
-            Kind::Human => "HUMAN",
-            Kind::Ai => "AI",
-        };
 
         let _run_fish: SinglePlayerRunFish = sqlx::query_as(
             r#"
             INSERT INTO single_player_run_fish (
-                id, run_id, fish_kind, fish_id, order_index, is_caught
+                id, run_id, fish_kind, fish_id, order_index, wave, is_caught
             )
-            VALUES ($1, $2, $3, $4, $5, FALSE)
+            VALUES ($1, $2, $3, $4, $5, $6, FALSE)
             RETURNING *
             "#,
         )
         .bind(fish_instance_id)
-        .bind(run.id)
-        .bind(fish_kind)
+        .bind(run_id)
+        .bind(fish.kind.run_fish_kind())
         .bind(fish.id)
-        .bind(order_index as i32)
-        .fetch_one(&mut *tx)
+        .bind(order_index)
+        .bind(wave)
+        .fetch_one(&mut *conn)
         .await?;
 
         fish_cards.push(FishCard {
             fish_instance_id: fish_instance_id.to_string(),
             image_url: single_player_fish_image_url(fish_instance_id),
-            difficulty_level: fish.difficulty_level,
-            metadata: fish.metadata,
         });
     }
 
+    Ok(fish_cards)
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartDailyRequest {
+    pub session_id: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyChallengeResponse {
+    pub date: NaiveDate,
+    pub level: LevelResponse,
+    pub attempted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streak: Option<DailyStreak>,
+}
+
+async fn daily_level(
+    state: &AppState,
+    conn: &mut PgConnection,
+) -> Result<SinglePlayerLevel, ApiError> {
+    levels::resolve_level(conn, state.config.daily_challenge_level, None)
+        .await?
+        .ok_or(ApiError::Internal(
+            "Daily challenge level not configured".to_string(),
+        ))
+}
+
+/// GET /api/game/daily - 今日挑战信息、是否已挑战、连续天数
+pub async fn get_daily(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<DailyChallengeResponse>, ApiError> {
+    let user_id = auth::user_id_from_token(&state.db, auth_header.token()).await?;
+    let date = Utc::now().date_naive();
+
+    let mut conn = state.db.acquire().await?;
+    let cfg = daily_level(&state, &mut conn).await?;
+
+    let attempt: Option<(Uuid, Option<i32>)> = sqlx::query_as(
+        "SELECT id, score FROM single_player_runs WHERE user_id = $1 AND challenge_date = $2",
+    )
+    .bind(user_id)
+    .bind(date)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let streak = daily_challenge::get_streak(&mut conn, user_id).await?;
+
+    Ok(Json(DailyChallengeResponse {
+        date,
+        level: cfg.into(),
+        attempted: attempt.is_some(),
+        run_id: attempt.map(|(id, _)| id),
+        score: attempt.and_then(|(_, score)| score),
+        streak,
+    }))
+}
+
+/// POST /api/game/daily/start - 开始今日挑战（每个用户每天一次）
+pub async fn start_daily(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<StartDailyRequest>,
+) -> Result<Json<StartGameResponse>, ApiError> {
+    let user_id = auth::user_id_from_token(&state.db, auth_header.token()).await?;
+    let owner = RunOwner::new(Some(user_id), None);
+    let date = Utc::now().date_naive();
+
+    let mut tx = state.db.begin().await?;
+    let cfg = daily_level(&state, &mut tx).await?;
+
+    let attempted: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM single_player_runs WHERE user_id = $1 AND challenge_date = $2)",
+    )
+    .bind(user_id)
+    .bind(date)
+    .fetch_one(&mut *tx)
+    .await?;
+    if attempted {
+        return Err(ApiError::BadRequest(
+            "Daily challenge already attempted".to_string(),
+        ));
+    }
+
+    run_sweeper::ensure_run_capacity(&mut tx, state.config.single_player_max_active_runs, &owner)
+        .await?;
+
+    let allow_duplicates = cfg.level <= state.config.single_player_allow_duplicates_max_level;
+    let selected = daily_challenge::ensure_challenge(&mut tx, date, &cfg, allow_duplicates).await?;
+
+    let run = insert_run(
+        &mut tx,
+        &req.session_id,
+        None,
+        &cfg,
+        &owner,
+        Some(date),
+        "daily",
+    )
+    .await
+    .map_err(|e| match e {
+        // 并发开局被唯一索引拦下
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::BadRequest("Daily challenge already attempted".to_string())
+        }
+        other => other.into(),
+    })?;
+    let fish_cards = insert_run_fish(&mut tx, run.id, 1, 0, selected).await?;
+
     tx.commit().await?;
 
-    Ok(Json(StartGameResponse {
+    Ok(Json(StartGameResponse::new(&run, fish_cards)))
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartEndlessRequest {
+    pub session_id: String,
+    #[serde(default)]
+    pub theme_id: Option<String>,
+    #[serde(default)]
+    pub owner_token: Option<String>,
+}
+
+/// 无尽模式以第 1 关为基础配置，生命值取其 `max_mistakes`
+async fn endless_base(
+    conn: &mut PgConnection,
+    theme_uuid: Option<Uuid>,
+) -> Result<SinglePlayerLevel, ApiError> {
+    levels::resolve_level(conn, 1, theme_uuid)
+        .await?
+        .ok_or(ApiError::Internal("Base level not configured".to_string()))
+}
+
+/// 抽取无尽模式的一波鱼：优先本局没出现过的，鱼库用尽后允许重复
+async fn sample_wave(
+    conn: &mut PgConnection,
+    plan: endless::WavePlan,
+    run_id: Option<Uuid>,
+    rng: &mut StdRng,
+) -> Result<Vec<SelectedFish>, ApiError> {
+    let fresh = sample_fish(
+        conn,
+        plan.difficulty_level,
+        plan.human_count,
+        plan.ai_count,
+        true,
+        run_id,
+        rng,
+    )
+    .await;
+    match fresh {
+        Err(ApiError::BadRequest(_)) if run_id.is_some() => {
+            sample_fish(
+                conn,
+                plan.difficulty_level,
+                plan.human_count,
+                plan.ai_count,
+                true,
+                None,
+                rng,
+            )
+            .await
+        }
+        other => other,
+    }
+}
+
+/// POST /api/game/endless/start - 开始无尽模式
+pub async fn start_endless(
+    State(state): State<Arc<AppState>>,
+    auth_header: OptionalAuth,
+    Json(req): Json<StartEndlessRequest>,
+) -> Result<Json<StartGameResponse>, ApiError> {
+    let user_id = optional_user_id(&state, &auth_header).await?;
+    let owner = RunOwner::new(user_id, req.owner_token.as_deref());
+
+    let mut tx = state.db.begin().await?;
+    let mut rng = StdRng::from_entropy();
+
+    let theme_uuid: Option<Uuid> = match req.theme_id.as_ref() {
+        Some(theme_id) => {
+            sqlx::query_scalar("SELECT id FROM themes WHERE theme_id = $1")
+                .bind(theme_id)
+                .fetch_optional(&mut *tx)
+                .await?
+        }
+        None => None,
+    };
+
+    let base = endless_base(&mut tx, theme_uuid).await?;
+    let plan = endless::wave_plan(
+        1,
+        &base,
+        state.config.endless_waves_per_tier,
+        state.config.endless_max_difficulty,
+    );
+
+    // 无尽模式没有倒计时与时间奖惩，`ends_at` 为闲置截止时间，目标数按波次累计；
+    // `level` 保留基础关卡，当前波次难度单独记在 `difficulty_level`
+    let cfg = SinglePlayerLevel {
+        difficulty_level: plan.difficulty_level,
+        seconds: state.config.endless_idle_seconds,
+        target_total: plan.ai_count,
+        time_bonus_seconds: 0,
+        mistake_penalty_seconds: 0,
+        ..base
+    };
+
+    run_sweeper::ensure_run_capacity(&mut tx, state.config.single_player_max_active_runs, &owner)
+        .await?;
+
+    let run = insert_run(
+        &mut tx,
+        &req.session_id,
+        theme_uuid,
+        &cfg,
+        &owner,
+        None,
+        "endless",
+    )
+    .await?;
+
+    let selected = sample_wave(&mut tx, plan, None, &mut rng).await?;
+    let fish_cards = insert_run_fish(&mut tx, run.id, 1, 0, selected).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(StartGameResponse::new(&run, fish_cards)))
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextWaveRequest {
+    pub session_id: String,
+    pub run_id: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextWaveResponse {
+    pub run_id: String,
+    pub wave: i32,
+    pub difficulty_level: i32,
+    pub lives_remaining: i32,
+    pub targets_found: i32,
+    pub target_total: i32,
+    pub fish: Vec<FishCard>,
+}
+
+/// POST /api/game/next_wave - 无尽模式：当前波次的 AI 鱼找齐后下发下一波
+pub async fn next_wave(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<NextWaveRequest>,
+) -> Result<Json<NextWaveResponse>, ApiError> {
+    let run_id = Uuid::parse_str(&req.run_id)
+        .map_err(|_| ApiError::BadRequest("Invalid runId".to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+    let mut rng = StdRng::from_entropy();
+
+    let run: SinglePlayerRun =
+        sqlx::query_as("SELECT * FROM single_player_runs WHERE id = $1 FOR UPDATE")
+            .bind(run_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(ApiError::NotFound("Run not found".to_string()))?;
+
+    if run.session_id != req.session_id {
+        return Err(ApiError::NotFound("Run not found".to_string()));
+    }
+    if run.mode != "endless" {
+        return Err(ApiError::BadRequest("Not an endless run".to_string()));
+    }
+    if run.status != "active" || Utc::now() >= run.ends_at {
+        return Err(ApiError::BadRequest("Game over".to_string()));
+    }
+    if run.targets_found < run.target_total {
+        return Err(ApiError::BadRequest("Wave not cleared".to_string()));
+    }
+
+    let base = endless_base(&mut tx, run.theme_id).await?;
+    let wave = run.wave + 1;
+    let plan = endless::wave_plan(
+        wave,
+        &base,
+        state.config.endless_waves_per_tier,
+        state.config.endless_max_difficulty,
+    );
+
+    let next_order_index: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM single_player_run_fish WHERE run_id = $1")
+            .bind(run.id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let selected = sample_wave(&mut tx, plan, Some(run.id), &mut rng).await?;
+    let fish_cards =
+        insert_run_fish(&mut tx, run.id, wave, next_order_index as i32, selected).await?;
+
+    let ends_at = endless::idle_deadline(Utc::now(), state.config.endless_idle_seconds);
+    let run: SinglePlayerRun = sqlx::query_as(
+        r#"
+        UPDATE single_player_runs
+        SET wave = $2, difficulty_level = $3, target_total = target_total + $4, ends_at = $5
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(run.id)
+    .bind(wave)
+    .bind(plan.difficulty_level)
+    .bind(plan.ai_count)
+    .bind(ends_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(NextWaveResponse {
         run_id: run.id.to_string(),
-        ends_at: run.ends_at,
+        wave: run.wave,
+        difficulty_level: run.difficulty_level,
         lives_remaining: run.max_mistakes - run.mistakes,
-        target_total: run.target_total,
         targets_found: run.targets_found,
+        target_total: run.target_total,
         fish: fish_cards,
     }))
 }
@@ -320,7 +715,7 @@ pub async fn catch_fish(
     let now = Utc::now();
     if run.status == "active" && now > run.ends_at {
         run = sqlx::query_as(
-            "UPDATE single_player_runs SET status = 'expired' WHERE id = $1 RETURNING *",
+            "UPDATE single_player_runs SET status = 'expired', finished_at = ends_at WHERE id = $1 RETURNING *",
         )
         .bind(run.id)
         .fetch_one(&mut *tx)
@@ -366,6 +761,21 @@ pub async fn catch_fish(
 
     let correct = run_fish.fish_kind.to_uppercase() == "AI";
 
+    // 服务端校验抓取节奏，机器人速度的抓取记为可疑
+    let last_catch_at: Option<chrono::DateTime<Utc>> =
+        sqlx::query_scalar("SELECT MAX(created_at) FROM single_player_catches WHERE run_id = $1")
+            .bind(run.id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let timing = CatchTiming {
+        min_first_catch_ms: state.config.single_player_min_first_catch_ms,
+        min_catch_interval_ms: state.config.single_player_min_catch_interval_ms,
+    };
+    let flag_reason = anti_cheat::assess_catch(timing, run.started_at, last_catch_at, now);
+    if let Some(reason) = flag_reason {
+        tracing::warn!("[AntiCheat] run {} suspicious catch: {}", run.id, reason);
+    }
+
     let _ = sqlx::query(
         "UPDATE single_player_run_fish SET is_caught = TRUE, caught_at = NOW() WHERE id = $1",
     )
@@ -374,15 +784,19 @@ pub async fn catch_fish(
     .await?;
 
     let _ = sqlx::query(
-        "INSERT INTO single_player_catches (id, run_id, run_fish_id, correct) VALUES ($1, $2, $3, $4)",
+        "INSERT INTO single_player_catches (id, run_id, run_fish_id, correct, flag_reason) VALUES ($1, $2, $3, $4, $5)",
     )
     .bind(Uuid::new_v4())
     .bind(run.id)
     .bind(run_fish.id)
     .bind(correct)
+    .bind(flag_reason)
     .execute(&mut *tx)
     .await?;
 
+    let suspicious_catches = run.suspicious_catches + i32::from(flag_reason.is_some());
+    let flagged = run.flagged || suspicious_catches >= state.config.single_player_flag_threshold;
+
     let new_mistakes = if correct {
         run.mistakes
     } else {
@@ -394,10 +808,28 @@ pub async fn catch_fish(
         run.targets_found
     };
 
-    let new_status = if new_targets_found >= run.target_total {
+    // 关卡配置的时间奖惩：抓对加时，误抓扣时；无尽模式每次抓取都顺延闲置截止时间
+    let new_ends_at = if run.mode == "endless" {
+        endless::idle_deadline(now, state.config.endless_idle_seconds)
+    } else if correct {
+        run.ends_at + Duration::seconds(run.time_bonus_seconds as i64)
+    } else {
+        run.ends_at - Duration::seconds(run.mistake_penalty_seconds as i64)
+    };
+
+    let new_status = if run.mode == "endless" {
+        // 无尽模式只在生命耗尽时结束，闲置超时由开头的过期判断处理
+        if new_mistakes >= run.max_mistakes {
+            "defeat"
+        } else {
+            "active"
+        }
+    } else if new_targets_found >= run.target_total {
         "victory"
     } else if new_mistakes >= run.max_mistakes {
         "defeat"
+    } else if now >= new_ends_at {
+        "expired"
     } else {
         "active"
     };
@@ -405,7 +837,9 @@ pub async fn catch_fish(
     run = sqlx::query_as(
         r#"
         UPDATE single_player_runs
-        SET mistakes = $2, targets_found = $3, status = $4
+        SET mistakes = $2, targets_found = $3, status = $4, ends_at = $5,
+            finished_at = CASE WHEN $4 <> 'active' THEN LEAST($6, $5) ELSE NULL END,
+            suspicious_catches = $7, flagged = $8
         WHERE id = $1
         RETURNING *
         "#,
@@ -414,6 +848,10 @@ pub async fn catch_fish(
     .bind(new_mistakes)
     .bind(new_targets_found)
     .bind(new_status)
+    .bind(new_ends_at)
+    .bind(now)
+    .bind(suspicious_catches)
+    .bind(flagged)
     .fetch_one(&mut *tx)
     .await?;
 
@@ -450,10 +888,15 @@ pub struct SubmitResponse {
     pub max_mistakes: i32,
     pub targets_found: i32,
     pub target_total: i32,
+    pub score: i32,
+    /// 每日挑战首次提交时返回更新后的连续天数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streak: Option<DailyStreak>,
 }
 
 pub async fn submit_game(
     State(state): State<Arc<AppState>>,
+    auth_header: OptionalAuth,
     Json(req): Json<SubmitRequest>,
 ) -> Result<Json<SubmitResponse>, ApiError> {
     let run_id = Uuid::parse_str(&req.run_id)
@@ -471,46 +914,59 @@ pub async fn submit_game(
     if run.session_id != req.session_id {
         return Err(ApiError::NotFound("Run not found".to_string()));
     }
+    // 登录对局只能由本人提交，避免冒名刷榜
+    if let Some(owner) = run.user_id {
+        match optional_user_id(&state, &auth_header).await? {
+            Some(user_id) if user_id == owner => {}
+            Some(_) => return Err(ApiError::NotFound("Run not found".to_string())),
+            None => return Err(ApiError::Unauthorized("Unauthorized".to_string())),
+        }
+    }
 
     let now = Utc::now();
-    let finished = run.status != "active"
-        || now >= run.ends_at
-        || run.targets_found >= run.target_total
-        || run.mistakes >= run.max_mistakes;
-
-    if !finished {
+    let Some(result) = run_sweeper::outcome(&run, now) else {
         return Err(ApiError::BadRequest("Game not finished".to_string()));
-    }
-
-    let final_status = if run.targets_found >= run.target_total {
-        "victory"
-    } else if run.mistakes >= run.max_mistakes {
-        "defeat"
-    } else if now >= run.ends_at {
-        "expired"
-    } else {
-        run.status.as_str()
     };
 
+    let first_submit = run.submitted_at.is_none();
+
     run = sqlx::query_as(
         r#"
         UPDATE single_player_runs
-        SET status = $2, submitted_at = COALESCE(submitted_at, NOW())
+        SET status = $2,
+            submitted_at = COALESCE(submitted_at, NOW()),
+            finished_at = COALESCE(finished_at, $3),
+            score = COALESCE(score, $4)
         WHERE id = $1
         RETURNING *
         "#,
     )
     .bind(run.id)
-    .bind(final_status)
+    .bind(result.status)
+    .bind(result.finished_at)
+    .bind(result.score)
     .fetch_one(&mut *tx)
     .await?;
 
+    let streak = match (first_submit, run.user_id, run.challenge_date) {
+        (true, Some(user_id), Some(date)) => {
+            Some(daily_challenge::record_attempt(&mut tx, user_id, date).await?)
+        }
+        _ => None,
+    };
+
     tx.commit().await?;
 
     let submitted_at = run
         .submitted_at
         .ok_or(ApiError::Internal("Submit failed".to_string()))?;
 
+    if first_submit {
+        if let Err(e) = leaderboard::record_run(&state, &run).await {
+            tracing::error!("[Leaderboard] record run {} failed: {:?}", run.id, e);
+        }
+    }
+
     Ok(Json(SubmitResponse {
         run_id: run.id.to_string(),
         status: run.status,
@@ -521,9 +977,94 @@ pub async fn submit_game(
         max_mistakes: run.max_mistakes,
         targets_found: run.targets_found,
         target_total: run.target_total,
+        score: run.score.unwrap_or(result.score),
+        streak,
     }))
 }
 
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardQuery {
+    pub board: Board,
+    #[serde(default)]
+    pub level: Option<i32>,
+    /// theme 榜必填；level 榜可选，指定时查该主题专属关卡的榜单
+    #[serde(default)]
+    pub theme_id: Option<String>,
+    /// 周榜的 ISO 周（如 `2026-W07`），默认本周
+    #[serde(default)]
+    pub week: Option<String>,
+    /// 每日挑战榜日期，默认今天（UTC）
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardResponse {
+    pub entries: Vec<LeaderboardEntry>,
+    /// 带登录 token 请求时返回自己的名次
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub me: Option<LeaderboardEntry>,
+}
+
+async fn resolve_theme_uuid(state: &AppState, theme_id: &str) -> Result<Uuid, ApiError> {
+    sqlx::query_scalar("SELECT id FROM themes WHERE theme_id = $1")
+        .bind(theme_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or(ApiError::NotFound("Theme not found".to_string()))
+}
+
+/// GET /api/game/leaderboard - 单人模式排行榜（all / weekly / level / theme / daily / endless）
+pub async fn get_leaderboard(
+    State(state): State<Arc<AppState>>,
+    auth_header: OptionalAuth,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse>, ApiError> {
+    let key = match query.board {
+        Board::All => leaderboard::all_time_key(),
+        Board::Weekly => leaderboard::weekly_key(
+            &query
+                .week
+                .clone()
+                .unwrap_or_else(|| leaderboard::week_id(Utc::now())),
+        ),
+        Board::Level => leaderboard::level_key(
+            query
+                .level
+                .ok_or(ApiError::BadRequest("level is required".to_string()))?,
+            match query.theme_id.as_deref() {
+                Some(theme_id) => Some(resolve_theme_uuid(&state, theme_id).await?),
+                None => None,
+            },
+        ),
+        Board::Theme => {
+            let theme_id = query
+                .theme_id
+                .as_deref()
+                .ok_or(ApiError::BadRequest("themeId is required".to_string()))?;
+            leaderboard::theme_key(resolve_theme_uuid(&state, theme_id).await?)
+        }
+        Board::Endless => leaderboard::endless_key(),
+        Board::Daily => {
+            leaderboard::daily_key(query.date.unwrap_or_else(|| Utc::now().date_naive()))
+        }
+    };
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let entries = leaderboard::top(&state, &key, limit).await?;
+    let me = match optional_user_id(&state, &auth_header).await? {
+        Some(user_id) => leaderboard::rank_of(&state, &key, user_id).await?,
+        None => None,
+    };
+
+    Ok(Json(LeaderboardResponse { entries, me }))
+}
+
+/// GET /api/game/fish/:fish_instance_id/image - 对局内鱼图（按实例重新编码）
 pub async fn get_fish_image(
     State(state): State<Arc<AppState>>,
     Path(fish_instance_id): Path<Uuid>,
@@ -563,7 +1104,14 @@ pub async fn get_fish_image(
         return Err(ApiError::NotFound("Fish not found".to_string()));
     };
 
-    let img = decode_image_data(&image_data)?;
+    // 每个对局实例重新编码，避免客户端按图片哈希跨局识别同一条鱼
+    let original = state.image_store.read_image_data(&image_data).await?;
+    let img = tokio::task::spawn_blocking(move || {
+        anti_cheat::reencode_for_instance(&original, fish_instance_id)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(format!("Image task failed: {}", e)))??;
+
     let mut resp = (StatusCode::OK, img.bytes).into_response();
     resp.headers_mut().insert(
         header::CONTENT_TYPE,
@@ -571,7 +1119,7 @@ pub async fn get_fish_image(
     );
     resp.headers_mut().insert(
         header::CACHE_CONTROL,
-        HeaderValue::from_static("public, max-age=31536000, immutable"),
+        HeaderValue::from_static("private, max-age=86400, immutable"),
     );
     Ok(resp)
 }
@@ -0,0 +1,235 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{single_player_fish_image_url, FishKind, SinglePlayerRun};
+use crate::services::{auth, ApiError, AppState};
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListRunsQuery {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub run_id: Uuid,
+    pub mode: String,
+    pub wave: i32,
+    pub level: i32,
+    pub difficulty_level: i32,
+    pub theme_id: Option<String>,
+    pub challenge_date: Option<NaiveDate>,
+    pub status: String,
+    pub score: Option<i32>,
+    pub mistakes: i32,
+    pub max_mistakes: i32,
+    pub targets_found: i32,
+    pub target_total: i32,
+    pub started_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+impl RunSummary {
+    fn new(run: SinglePlayerRun, theme_slug: Option<String>) -> Self {
+        Self {
+            run_id: run.id,
+            mode: run.mode,
+            wave: run.wave,
+            level: run.level,
+            difficulty_level: run.difficulty_level,
+            theme_id: theme_slug,
+            challenge_date: run.challenge_date,
+            status: run.status,
+            score: run.score,
+            mistakes: run.mistakes,
+            max_mistakes: run.max_mistakes,
+            targets_found: run.targets_found,
+            target_total: run.target_total,
+            started_at: run.started_at,
+            ends_at: run.ends_at,
+            finished_at: run.finished_at,
+            submitted_at: run.submitted_at,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFishReplay {
+    pub fish_instance_id: Uuid,
+    pub order_index: i32,
+    pub kind: FishKind,
+    pub image_url: String,
+    pub is_caught: bool,
+    pub caught_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatchReplay {
+    pub fish_instance_id: Uuid,
+    pub correct: bool,
+    pub caught_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDetailResponse {
+    #[serde(flatten)]
+    pub run: RunSummary,
+    pub fish: Vec<RunFishReplay>,
+    /// 按时间顺序的抓取记录（含误抓）
+    pub catches: Vec<CatchReplay>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RunRow {
+    #[sqlx(flatten)]
+    run: SinglePlayerRun,
+    theme_slug: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RunFishRow {
+    id: Uuid,
+    fish_kind: String,
+    order_index: i32,
+    is_caught: bool,
+    caught_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CatchRow {
+    run_fish_id: Uuid,
+    correct: bool,
+    created_at: DateTime<Utc>,
+}
+
+/// GET /api/game/runs - 当前用户最近的单人对局
+pub async fn list_runs(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Query(query): Query<ListRunsQuery>,
+) -> Result<Json<Vec<RunSummary>>, ApiError> {
+    let user_id = auth::user_id_from_token(&state.db, auth_header.token()).await?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let rows: Vec<RunRow> = sqlx::query_as(
+        r#"
+        SELECT r.*, t.theme_id AS theme_slug
+        FROM single_player_runs r
+        LEFT JOIN themes t ON t.id = r.theme_id
+        WHERE r.user_id = $1
+        ORDER BY r.started_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|r| RunSummary::new(r.run, r.theme_slug))
+            .collect(),
+    ))
+}
+
+/// GET /api/game/runs/:run_id - 对局回放：鱼的真实类型、抓取顺序与误抓
+pub async fn get_run(
+    State(state): State<Arc<AppState>>,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<RunDetailResponse>, ApiError> {
+    let user_id = auth::user_id_from_token(&state.db, auth_header.token()).await?;
+
+    let row: RunRow = sqlx::query_as(
+        r#"
+        SELECT r.*, t.theme_id AS theme_slug
+        FROM single_player_runs r
+        LEFT JOIN themes t ON t.id = r.theme_id
+        WHERE r.id = $1 AND r.user_id = $2
+        "#,
+    )
+    .bind(run_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(ApiError::NotFound("Run not found".to_string()))?;
+
+    // 进行中的对局不能揭晓答案
+    if row.run.status == "active" && Utc::now() < row.run.ends_at {
+        return Err(ApiError::BadRequest("Game not finished".to_string()));
+    }
+
+    let fish_rows: Vec<RunFishRow> = sqlx::query_as(
+        r#"
+        SELECT id, fish_kind, order_index, is_caught, caught_at
+        FROM single_player_run_fish
+        WHERE run_id = $1
+        ORDER BY order_index ASC
+        "#,
+    )
+    .bind(run_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let catch_rows: Vec<CatchRow> = sqlx::query_as(
+        r#"
+        SELECT run_fish_id, correct, created_at
+        FROM single_player_catches
+        WHERE run_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(run_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let fish = fish_rows
+        .into_iter()
+        .filter_map(|f| {
+            Some(RunFishReplay {
+                fish_instance_id: f.id,
+                order_index: f.order_index,
+                kind: FishKind::from_run_fish_kind(&f.fish_kind)?,
+                image_url: single_player_fish_image_url(f.id),
+                is_caught: f.is_caught,
+                caught_at: f.caught_at,
+            })
+        })
+        .collect();
+
+    let catches = catch_rows
+        .into_iter()
+        .map(|c| CatchReplay {
+            fish_instance_id: c.run_fish_id,
+            correct: c.correct,
+            caught_at: c.created_at,
+        })
+        .collect();
+
+    Ok(Json(RunDetailResponse {
+        run: RunSummary::new(row.run, row.theme_slug),
+        fish,
+        catches,
+    }))
+}
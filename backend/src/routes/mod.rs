@@ -1,7 +1,13 @@
+pub mod admin_ai_tasks;
+pub mod admin_fish;
+pub mod admin_imposters;
+pub mod admin_levels;
 pub mod auth;
 pub mod dev_auth;
 pub mod drawings;
 pub mod game;
+pub mod game_history;
 pub mod n8n_callback;
 pub mod rooms;
 pub mod themes;
+pub mod ws_protocol;
@@ -1,10 +1,79 @@
-use axum::{extract::State, Json};
+use axum::{body::Bytes, extract::State, http::HeaderMap, Json};
 use chrono::Utc;
+use deadpool_redis::redis;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::models::N8nCallbackRequest;
-use crate::services::{ApiError, AppState};
+use crate::services::ai_generator::GeneratedFish;
+use crate::services::{auth, n8n_jobs, n8n_signature, ApiError, AppState};
+
+/// 校验签名与时间戳，并拒绝窗口内重复的签名；生成后端为 n8n 却没配 `N8N_CALLBACK_SECRET` 时
+/// 一律拒绝，不能因为漏配密钥就退化成只靠 nonce
+async fn verify_signature(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), ApiError> {
+    let Some(secret) = state.config.n8n_callback_secret.as_deref() else {
+        if state.config.ai_generator_backend == "n8n" {
+            tracing::error!("n8n callback rejected: N8N_CALLBACK_SECRET not configured");
+            return Err(ApiError::Unauthorized(
+                "Callback secret not configured".to_string(),
+            ));
+        }
+        return Ok(());
+    };
+
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(ApiError::Unauthorized(
+                "Missing callback signature".to_string(),
+            ))
+    };
+    let timestamp: i64 = header(n8n_signature::TIMESTAMP_HEADER)?
+        .trim()
+        .parse()
+        .map_err(|_| ApiError::Unauthorized("Invalid callback timestamp".to_string()))?;
+    let signature = header(n8n_signature::SIGNATURE_HEADER)?;
+
+    let max_skew = state.config.n8n_callback_max_skew_seconds;
+    n8n_signature::verify(
+        secret,
+        timestamp,
+        body,
+        signature,
+        Utc::now().timestamp(),
+        max_skew,
+    )
+    .map_err(|reason| ApiError::Unauthorized(reason.to_string()))?;
+
+    // 时间窗口内同一签名只接受一次
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    let fresh: Option<String> = redis::cmd("SET")
+        .arg(format!(
+            "n8n:callback:sig:{}",
+            signature.trim().to_lowercase()
+        ))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(max_skew.max(1) * 2)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    if fresh.is_none() {
+        return Err(ApiError::Unauthorized("Replayed callback".to_string()));
+    }
+
+    Ok(())
+}
 
 /// POST /api/n8n/callback - AI 生成完成回调
 ///
@@ -13,8 +82,14 @@ use crate::services::{ApiError, AppState};
 #[axum::debug_handler]
 pub async fn callback(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<N8nCallbackRequest>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<Json<CallbackResponse>, ApiError> {
+    verify_signature(&state, &headers, &body).await?;
+
+    let req: N8nCallbackRequest = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid callback body: {}", e)))?;
+
     tracing::info!("n8n callback received for task {}", req.task_id);
 
     // 获取任务
@@ -24,46 +99,46 @@ pub async fn callback(
         .await?
         .ok_or(ApiError::NotFound("Task not found".to_string()))?;
 
+    // 回调必须带回触发时下发的 nonce
+    let nonce_ok = match (task.callback_nonce.as_deref(), req.nonce.as_deref()) {
+        (Some(expected), Some(got)) => auth::constant_time_eq(expected.as_bytes(), got.as_bytes()),
+        _ => false,
+    };
+    if !nonce_ok {
+        return Err(ApiError::Unauthorized("Invalid callback nonce".to_string()));
+    }
+
     if req.status == "completed" {
         // 成功：将 AI 鱼数据加入队列
         let image_data = req
             .image_data
             .ok_or(ApiError::BadRequest("Missing image_data".to_string()))?;
-        let name = req.name.unwrap_or_else(|| "小东西".to_string());
-        let description = req.description;
+        let fish = GeneratedFish {
+            image_data,
+            name: req.name.unwrap_or_else(|| "小东西".to_string()),
+            description: req.description,
+        };
+        n8n_jobs::complete_task(&state, req.task_id, &fish).await?;
 
-        let result = sqlx::query(
-            "UPDATE ai_tasks SET status = 'completed', image_data = $1, generated_name = $2, generated_description = $3, completed_at = $4 WHERE id = $5 AND status != 'completed'",
-        )
-        .bind(&image_data)
-        .bind(&name)
-        .bind(&description)
-        .bind(Utc::now())
-        .bind(req.task_id)
-        .execute(&state.db)
-        .await?;
-
-        if result.rows_affected() == 1 {
-            let fish_data = serde_json::json!({
-                "task_id": req.task_id
-            });
-            state.push_ai_fish_to_queue(task.room_id, &fish_data).await;
-        }
-
-        tracing::info!("AI fish added to queue for room {}", task.room_id);
+        tracing::info!(
+            "AI fish added to queue (room {:?}, theme {:?})",
+            task.room_id,
+            task.theme_id
+        );
         Ok(Json(CallbackResponse {
             success: true,
             drawing_id: None, // 不再直接创建 drawing
             message: "AI fish added to queue".to_string(),
         }))
     } else {
-        // 失败：更新任务状态
-        sqlx::query(
-            "UPDATE ai_tasks SET status = 'failed', error_message = $1, retry_count = retry_count + 1 WHERE id = $2 AND status != 'completed'",
+        // 失败：按退避策略重试，超过上限进入死信
+        let error = req.error_message.as_deref().unwrap_or("Unknown error");
+        n8n_jobs::record_failure(
+            &state.db,
+            n8n_jobs::RetryPolicy::from_config(&state.config),
+            req.task_id,
+            error,
         )
-        .bind(&req.error_message)
-        .bind(req.task_id)
-        .execute(&state.db)
         .await?;
 
         tracing::warn!("AI task failed: {:?}", req.error_message);
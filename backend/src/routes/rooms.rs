@@ -9,7 +9,7 @@ use uuid::Uuid;
 use crate::models::{
     CreateRoomRequest, DrawingItemRow, DrawingListItem, Room, RoomResponse, Theme, ThemeResponse,
 };
-use crate::services::{ApiError, AppState};
+use crate::services::{ai_fish_buffer, ApiError, AppState};
 
 /// POST /api/rooms - 创建房间
 pub async fn create_room(
@@ -44,6 +44,17 @@ pub async fn create_room(
     .fetch_one(&state.db)
     .await?;
 
+    // 开房即给主题池预生成 AI 鱼
+    {
+        let state = state.clone();
+        let theme = theme.clone();
+        tokio::spawn(async move {
+            if let Err(e) = ai_fish_buffer::ensure_theme_buffer(&state, &theme).await {
+                tracing::warn!("AI fish buffer refill failed: {:?}", e);
+            }
+        });
+    }
+
     Ok(Json(RoomWithTheme {
         room: room.into(),
         theme: theme.into(),
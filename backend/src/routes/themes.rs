@@ -6,7 +6,7 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::sync::Arc;
 
 use crate::models::{Room, Theme, ThemeResponse};
-use crate::services::{ApiError, AppState};
+use crate::services::{ai_fish_buffer, ApiError, AppState};
 
 /// GET /api/themes - 获取所有主题
 pub async fn list_themes(
@@ -64,7 +64,7 @@ pub async fn get_or_create_room_by_theme(
             .map(|_| chars[rng.gen_range(0..chars.len())])
             .collect();
 
-        sqlx::query_as(
+        let room: Room = sqlx::query_as(
             r#"
             INSERT INTO rooms (id, theme_id, room_code, status, total_items, ai_count, online_count, turbidity)
             VALUES ($1, $2, $3, 'active', 0, 0, 0, 0.0)
@@ -75,7 +75,20 @@ pub async fn get_or_create_room_by_theme(
         .bind(theme.id)
         .bind(&room_code)
         .fetch_one(&state.db)
-        .await?
+        .await?;
+
+        // 开房即给主题池预生成 AI 鱼
+        {
+            let state = state.clone();
+            let theme = theme.clone();
+            tokio::spawn(async move {
+                if let Err(e) = ai_fish_buffer::ensure_theme_buffer(&state, &theme).await {
+                    tracing::warn!("AI fish buffer refill failed: {:?}", e);
+                }
+            });
+        }
+
+        room
     };
 
     Ok(Json(ThemeRoomResponse {
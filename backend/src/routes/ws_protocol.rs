@@ -0,0 +1,8 @@
+use axum::Json;
+
+use crate::ws::messages::protocol_schema;
+
+/// GET /api/ws/schema - Socket.IO 协议的 JSON Schema
+pub async fn get_ws_schema() -> Json<serde_json::Value> {
+    Json(protocol_schema())
+}
@@ -0,0 +1,292 @@
+//! 主题级 AI 鱼预生成缓冲
+//!
+//! 每个主题在 Redis 维护一个共享池 `theme:{id}:ai_fish_pool`（元素同房间队列，`{"task_id"}`），
+//! 同主题的房间都从这里取鱼。开房和出鱼时补货，后台任务再定期给有活跃房间的主题补到目标深度；
+//! 每个主题同时在生成中的任务数和每日生成总数都有上限。
+
+use chrono::{DateTime, Utc};
+use deadpool_redis::redis::AsyncCommands;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{CallbackTarget, GenerationRequest, GenerationTheme, Theme};
+use crate::services::ai_prompt::{self, KeywordUsage};
+use crate::services::mimicry::MimicStyle;
+use crate::services::{mimicry, n8n_jobs, n8n_signature, ApiError, AppState};
+
+pub fn theme_pool_key(theme_id: Uuid) -> String {
+    format!("theme:{}:ai_fish_pool", theme_id)
+}
+
+/// 缓冲参数
+#[derive(Debug, Clone, Copy)]
+pub struct BufferLimits {
+    /// 目标深度：池中现成的 + 生成中的
+    pub target: i64,
+    /// 同一主题同时生成中的任务上限
+    pub max_in_flight: i64,
+    /// 同一主题每天最多创建的任务数，0 表示不限
+    pub daily_budget: i64,
+}
+
+/// 本次应新建的生成任务数
+pub fn tasks_to_start(limits: BufferLimits, ready: i64, in_flight: i64, used_today: i64) -> i64 {
+    let deficit = limits.target - ready - in_flight;
+    let concurrency_left = limits.max_in_flight - in_flight;
+    let budget_left = if limits.daily_budget > 0 {
+        limits.daily_budget - used_today
+    } else {
+        i64::MAX
+    };
+    deficit.min(concurrency_left).min(budget_left).max(0)
+}
+
+async fn pool_len(state: &AppState, theme_id: Uuid) -> Result<i64, ApiError> {
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    conn.llen(theme_pool_key(theme_id))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))
+}
+
+/// 把主题池补到目标深度，返回新建的任务数
+pub async fn ensure_theme_buffer(state: &Arc<AppState>, theme: &Theme) -> Result<i64, ApiError> {
+    if !state.config.ai_generation_enabled {
+        return Ok(0);
+    }
+
+    let limits = BufferLimits {
+        target: state.config.ai_fish_buffer_target,
+        max_in_flight: state.config.ai_fish_buffer_max_in_flight,
+        daily_budget: state.config.ai_fish_buffer_daily_budget,
+    };
+    let ready = pool_len(state, theme.id).await?;
+
+    let mut tx = state.db.begin().await?;
+    // 同一主题的补货串行化，避免并发开房时超发
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("ai_fish_buffer:{}", theme.id))
+        .execute(&mut *tx)
+        .await?;
+
+    let (in_flight, used_today): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE status IN ('pending', 'dispatched')),
+            COUNT(*) FILTER (WHERE created_at >= date_trunc('day', NOW()))
+        FROM ai_tasks
+        WHERE theme_id = $1
+        "#,
+    )
+    .bind(theme.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let n = tasks_to_start(limits, ready, in_flight, used_today);
+    let mut requests = Vec::with_capacity(n as usize);
+    for _ in 0..n {
+        requests.push(insert_task(&mut tx, state, theme, None, MimicStyle::default()).await?);
+    }
+    tx.commit().await?;
+
+    for request in requests {
+        tokio::spawn(n8n_jobs::dispatch(state.clone(), request));
+    }
+    if n > 0 {
+        tracing::info!(
+            "[AiFishBuffer] theme {}: ready={}, in_flight={}, started {}",
+            theme.theme_id,
+            ready,
+            in_flight,
+            n
+        );
+    }
+    Ok(n)
+}
+
+#[derive(sqlx::FromRow)]
+struct KeywordUsageRow {
+    keyword: String,
+    uses: i64,
+    last_used: DateTime<Utc>,
+}
+
+/// 创建生成任务：轮换关键词、组装提示词，连同请求体一起落库（失败后由任务队列重发）
+///
+/// 带 `room_id` 的是拟态任务，结果进入房间队列；`style` 是它的参考画作与主色
+pub async fn insert_task(
+    conn: &mut sqlx::PgConnection,
+    state: &AppState,
+    theme: &Theme,
+    room_id: Option<Uuid>,
+    style: MimicStyle,
+) -> Result<GenerationRequest, ApiError> {
+    let request = build_request(conn, state, theme, room_id, style).await?;
+    let reference_ids: Vec<Uuid> = request
+        .style_references
+        .iter()
+        .map(|r| r.drawing_id)
+        .collect();
+    let nonce = request.callback.as_ref().map(|c| c.nonce.clone());
+
+    let payload = serde_json::to_value(&request).unwrap_or_default();
+    sqlx::query(
+        r#"
+        INSERT INTO ai_tasks (
+            id, room_id, theme_id, status, request_payload, callback_nonce, keyword, prompt,
+            style_reference_ids, dispatched_at
+        )
+        VALUES ($1, $2, $3, 'dispatched', $4, $5, $6, $7, $8, NOW())
+        "#,
+    )
+    .bind(request.task_id)
+    .bind(room_id)
+    .bind(theme.id)
+    .bind(&payload)
+    .bind(&nonce)
+    .bind(&request.keyword)
+    .bind(&request.prompt)
+    .bind((!reference_ids.is_empty()).then_some(&reference_ids))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(request)
+}
+
+/// 组装生成请求（新的任务 id 和回调 nonce），不落库；重试没有请求体的旧任务时也用它重建
+pub async fn build_request(
+    conn: &mut sqlx::PgConnection,
+    state: &AppState,
+    theme: &Theme,
+    room_id: Option<Uuid>,
+    style: MimicStyle,
+) -> Result<GenerationRequest, ApiError> {
+    let palette: Vec<String> = serde_json::from_value(theme.palette.clone()).unwrap_or_default();
+    let keywords: Vec<String> =
+        serde_json::from_value(theme.ai_keywords.clone()).unwrap_or_default();
+
+    let usage: HashMap<String, KeywordUsage> = sqlx::query_as::<_, KeywordUsageRow>(
+        r#"
+        SELECT keyword, COUNT(*) AS uses, MAX(created_at) AS last_used
+        FROM ai_tasks
+        WHERE theme_id = $1 AND keyword IS NOT NULL
+        GROUP BY keyword
+        "#,
+    )
+    .bind(theme.id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        (
+            r.keyword,
+            KeywordUsage {
+                uses: r.uses,
+                last_used: r.last_used,
+            },
+        )
+    })
+    .collect();
+
+    let keyword = ai_prompt::next_keyword(&keywords, &usage).unwrap_or_else(|| "fish".to_string());
+    let variant = usage.get(&keyword).map(|u| u.uses).unwrap_or(0) as usize;
+    let prompt = ai_prompt::compose_prompt(&theme.ai_prompt_style, &keyword, &palette, variant);
+    let prompt = mimicry::mimic_prompt(&prompt, &style.references);
+
+    let callback = CallbackTarget {
+        callback_url: format!("{}/api/n8n/callback", state.config.callback_base_url),
+        nonce: n8n_signature::new_nonce(),
+    };
+    Ok(GenerationRequest {
+        room_id,
+        task_id: Uuid::new_v4(),
+        theme: GenerationTheme {
+            palette,
+            keywords,
+            prompt_style: theme.ai_prompt_style.clone(),
+        },
+        callback: Some(callback),
+        keyword: Some(keyword),
+        prompt: Some(prompt),
+        style_references: style.references,
+        style_palette: style.palette,
+    })
+}
+
+/// 后台任务：按 `AI_FISH_BUFFER_INTERVAL_SECONDS` 周期给有活跃房间的主题补货，0 表示关闭
+pub async fn start_ai_fish_buffer(state: Arc<AppState>) {
+    let interval_secs = state.config.ai_fish_buffer_interval_seconds;
+    if interval_secs == 0 || !state.config.ai_generation_enabled {
+        tracing::info!("[AiFishBuffer] disabled");
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    loop {
+        ticker.tick().await;
+        let themes: Vec<Theme> = match sqlx::query_as(
+            r#"
+            SELECT * FROM themes t
+            WHERE EXISTS (
+                SELECT 1 FROM rooms r
+                WHERE r.theme_id = t.id
+                  AND r.status IN ('active', 'voting')
+                  AND r.updated_at > NOW() - INTERVAL '1 hour'
+            )
+            "#,
+        )
+        .fetch_all(&state.db)
+        .await
+        {
+            Ok(themes) => themes,
+            Err(err) => {
+                tracing::warn!("[AiFishBuffer] failed to load active themes: {}", err);
+                continue;
+            }
+        };
+
+        for theme in &themes {
+            if let Err(err) = ensure_theme_buffer(&state, theme).await {
+                tracing::warn!(
+                    "[AiFishBuffer] refill for theme {} failed: {:?}",
+                    theme.theme_id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: BufferLimits = BufferLimits {
+        target: 5,
+        max_in_flight: 2,
+        daily_budget: 10,
+    };
+
+    #[test]
+    fn refill_respects_concurrency() {
+        assert_eq!(tasks_to_start(LIMITS, 0, 0, 0), 2);
+        assert_eq!(tasks_to_start(LIMITS, 0, 2, 0), 0);
+        assert_eq!(tasks_to_start(LIMITS, 4, 0, 0), 1);
+        assert_eq!(tasks_to_start(LIMITS, 6, 0, 0), 0);
+    }
+
+    #[test]
+    fn refill_respects_daily_budget() {
+        assert_eq!(tasks_to_start(LIMITS, 0, 0, 9), 1);
+        assert_eq!(tasks_to_start(LIMITS, 0, 0, 12), 0);
+        let unlimited = BufferLimits {
+            daily_budget: 0,
+            ..LIMITS
+        };
+        assert_eq!(tasks_to_start(unlimited, 0, 0, 1000), 2);
+    }
+}
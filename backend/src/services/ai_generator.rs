@@ -0,0 +1,212 @@
+//! AI 鱼生成后端
+//!
+//! - `n8n`：调用 n8n webhook，结果通过 `/api/n8n/callback` 异步回来
+//! - `openai`：直接调用 OpenAI 兼容的图片生成接口（`/v1/images/generations`）
+//! - `procedural`：本地程序化绘制涂鸦鱼（见 `procedural_fish`），离线开发与测试用
+//!
+//! 同步返回结果的后端由任务队列直接落库并推入房间的 AI 鱼队列，
+//! 与 n8n 回调走同一条路径。
+
+use anyhow::{Context, Result};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::models::GenerationRequest;
+use crate::services::image_store::{encode_data_url, ImageBytes};
+use crate::services::n8n_client::trigger_n8n;
+use crate::services::{ai_prompt, procedural_fish, ApiError};
+
+/// `ai_tasks.generated_name` / `generated_description` 的列宽
+const NAME_MAX_CHARS: usize = 24;
+const DESCRIPTION_MAX_CHARS: usize = 60;
+
+/// 生成好的一条 AI 鱼
+pub struct GeneratedFish {
+    /// data URL
+    pub image_data: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+pub enum Generation {
+    /// 已受理，结果稍后通过回调返回
+    Pending,
+    Completed(GeneratedFish),
+}
+
+#[async_trait::async_trait]
+pub trait AiGenerator: Send + Sync {
+    async fn generate(&self, request: &GenerationRequest) -> Result<Generation>;
+}
+
+pub struct N8nGenerator {
+    webhook_url: String,
+}
+
+pub struct OpenAiImageGenerator {
+    client: reqwest::Client,
+    api_url: String,
+    api_key: String,
+    model: String,
+    size: String,
+}
+
+pub struct ProceduralGenerator;
+
+pub fn build_ai_generator(config: &Config) -> Result<Arc<dyn AiGenerator>, ApiError> {
+    match config.ai_generator_backend.as_str() {
+        "n8n" => {
+            if config.n8n_callback_secret.is_none() {
+                tracing::warn!("N8N_CALLBACK_SECRET not set, n8n callbacks will be rejected");
+            }
+            Ok(Arc::new(N8nGenerator {
+                webhook_url: config.n8n_webhook_url.clone(),
+            }))
+        }
+        "openai" => {
+            let Some(api_key) = config.openai_image_api_key.clone() else {
+                return Err(ApiError::Internal(
+                    "OPENAI_IMAGE_API_KEY missing".to_string(),
+                ));
+            };
+            Ok(Arc::new(OpenAiImageGenerator {
+                client: reqwest::Client::new(),
+                api_url: config.openai_image_api_url.clone(),
+                api_key,
+                model: config.openai_image_model.clone(),
+                size: config.openai_image_size.clone(),
+            }))
+        }
+        "procedural" => Ok(Arc::new(ProceduralGenerator)),
+        other => Err(ApiError::Internal(format!(
+            "Unsupported AI_GENERATOR_BACKEND: {}",
+            other
+        ))),
+    }
+}
+
+/// 任务指定的关键词；旧任务没有时按任务 id 确定性地选，重试时生成同一种鱼
+fn task_keyword(request: &GenerationRequest) -> String {
+    if let Some(keyword) = request.keyword.clone() {
+        return keyword;
+    }
+    let mut rng = StdRng::seed_from_u64(request.task_id.as_u64_pair().0);
+    request
+        .theme
+        .keywords
+        .choose(&mut rng)
+        .cloned()
+        .unwrap_or_else(|| "fish".to_string())
+}
+
+fn fish_text(request: &GenerationRequest, keyword: &str) -> (String, Option<String>) {
+    let name = keyword.chars().take(NAME_MAX_CHARS).collect();
+    let description: String = request
+        .theme
+        .prompt_style
+        .chars()
+        .take(DESCRIPTION_MAX_CHARS)
+        .collect();
+    (name, Some(description).filter(|d| !d.is_empty()))
+}
+
+#[async_trait::async_trait]
+impl AiGenerator for N8nGenerator {
+    async fn generate(&self, request: &GenerationRequest) -> Result<Generation> {
+        trigger_n8n(&self.webhook_url, request).await?;
+        Ok(Generation::Pending)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ImagesResponse {
+    data: Vec<ImagesResponseItem>,
+}
+
+#[derive(serde::Deserialize)]
+struct ImagesResponseItem {
+    b64_json: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl AiGenerator for OpenAiImageGenerator {
+    async fn generate(&self, request: &GenerationRequest) -> Result<Generation> {
+        let keyword = task_keyword(request);
+        let prompt = request.prompt.clone().unwrap_or_else(|| {
+            ai_prompt::compose_prompt(
+                &request.theme.prompt_style,
+                &keyword,
+                &request.theme.palette,
+                0,
+            )
+        });
+        let body = serde_json::json!({
+            "model": self.model,
+            "prompt": prompt,
+            "n": 1,
+            "size": self.size,
+            "response_format": "b64_json",
+        });
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .timeout(std::time::Duration::from_secs(120))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Image API failed: {} - {}", status, body);
+        }
+
+        let parsed: ImagesResponse = response.json().await?;
+        let b64 = parsed
+            .data
+            .into_iter()
+            .next()
+            .and_then(|item| item.b64_json)
+            .context("Image API returned no image")?;
+
+        let (name, description) = fish_text(request, &keyword);
+        Ok(Generation::Completed(GeneratedFish {
+            image_data: format!("data:image/png;base64,{}", b64),
+            name,
+            description,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl AiGenerator for ProceduralGenerator {
+    async fn generate(&self, request: &GenerationRequest) -> Result<Generation> {
+        // 拟态任务用参考画作的主色
+        let palette = if request.style_palette.is_empty() {
+            request.theme.palette.clone()
+        } else {
+            request.style_palette.clone()
+        };
+        let keywords = match request.keyword.clone() {
+            Some(keyword) => vec![keyword],
+            None => request.theme.keywords.clone(),
+        };
+        let seed = request.task_id.as_u64_pair().0;
+        let fish =
+            tokio::task::spawn_blocking(move || procedural_fish::render(&palette, &keywords, seed))
+                .await?
+                .map_err(|e| anyhow::anyhow!("Procedural render failed: {:?}", e))?;
+
+        Ok(Generation::Completed(GeneratedFish {
+            image_data: encode_data_url(&ImageBytes {
+                content_type: "image/png",
+                bytes: fish.png,
+            }),
+            name: fish.name,
+            description: Some(fish.description),
+        }))
+    }
+}
@@ -0,0 +1,121 @@
+//! AI 生成的关键词轮换与提示词组装
+//!
+//! 每个任务由后端选定一个关键词并拼出完整提示词，写入 `ai_tasks.keyword` / `prompt`，
+//! 生成的 drawing 通过 `ai_tasks.drawing_id` 关联回来，便于统计哪些提示词最能以假乱真。
+//! 关键词按主题轮换：优先没用过的，其次最久没用的；同一关键词的多次生成轮流使用不同模板和配色。
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// `ai_tasks.keyword` 列宽
+const KEYWORD_MAX_CHARS: usize = 50;
+
+/// 提示词模板：`{style}` / `{keyword}` / `{colors}` 会被替换
+const PROMPT_TEMPLATES: &[&str] = &[
+    "A {style} of a {keyword}. Simple clean background. High contrast, crisp silhouette. Colors: {colors}. No text, no watermark, no logo.",
+    "A {style} of a single {keyword}, drawn quickly by a kid on a tablet. Plain white background. Colors: {colors}. No text, no watermark, no logo.",
+    "A {style} of a cute {keyword} swimming to the right. Flat fill, thick outline, plain background. Colors: {colors}. No text, no watermark, no logo.",
+    "A {style} of a funny {keyword} with big eyes. One subject only, empty background. Colors: {colors}. No text, no watermark, no logo.",
+];
+
+/// 关键词使用记录
+#[derive(Debug, Clone, Copy)]
+pub struct KeywordUsage {
+    pub uses: i64,
+    pub last_used: DateTime<Utc>,
+}
+
+/// 选下一个关键词：没用过的按列表顺序优先，都用过则取最久没用的
+pub fn next_keyword(keywords: &[String], usage: &HashMap<String, KeywordUsage>) -> Option<String> {
+    let keywords: Vec<String> = keywords
+        .iter()
+        .map(|k| k.trim().chars().take(KEYWORD_MAX_CHARS).collect::<String>())
+        .filter(|k| !k.is_empty())
+        .collect();
+
+    if let Some(unused) = keywords.iter().find(|k| !usage.contains_key(*k)) {
+        return Some(unused.clone());
+    }
+    keywords
+        .into_iter()
+        .min_by_key(|k| usage.get(k).map(|u| u.last_used))
+}
+
+/// 组装提示词；`variant` 通常取该关键词已用次数，使同一关键词轮流换模板和配色
+pub fn compose_prompt(style: &str, keyword: &str, palette: &[String], variant: usize) -> String {
+    let template = PROMPT_TEMPLATES[variant % PROMPT_TEMPLATES.len()];
+    let colors = if palette.is_empty() {
+        String::new()
+    } else {
+        let offset = variant % palette.len();
+        palette
+            .iter()
+            .cycle()
+            .skip(offset)
+            .take(palette.len().min(3))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    template
+        .replace("{style}", style)
+        .replace("{keyword}", keyword)
+        .replace("{colors}", &colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn rotation_prefers_unused_then_least_recent() {
+        let keywords = words(&["fish", "crab", "whale"]);
+        let now = Utc::now();
+        let mut usage = HashMap::new();
+        assert_eq!(next_keyword(&keywords, &usage).as_deref(), Some("fish"));
+
+        usage.insert(
+            "fish".to_string(),
+            KeywordUsage {
+                uses: 1,
+                last_used: now,
+            },
+        );
+        assert_eq!(next_keyword(&keywords, &usage).as_deref(), Some("crab"));
+
+        usage.insert(
+            "crab".to_string(),
+            KeywordUsage {
+                uses: 1,
+                last_used: now - Duration::minutes(5),
+            },
+        );
+        usage.insert(
+            "whale".to_string(),
+            KeywordUsage {
+                uses: 1,
+                last_used: now - Duration::minutes(1),
+            },
+        );
+        assert_eq!(next_keyword(&keywords, &usage).as_deref(), Some("crab"));
+        assert_eq!(next_keyword(&[], &usage), None);
+    }
+
+    #[test]
+    fn prompt_variants_rotate() {
+        let palette = words(&["#111111", "#222222", "#333333", "#444444"]);
+        let a = compose_prompt("doodle", "crab", &palette, 0);
+        let b = compose_prompt("doodle", "crab", &palette, 1);
+        assert!(a.starts_with("A doodle of a crab."));
+        assert!(a.contains("#111111, #222222, #333333"));
+        assert!(b.contains("#222222, #333333, #444444"));
+        assert_ne!(a, b);
+        assert!(!a.contains('{'));
+    }
+}
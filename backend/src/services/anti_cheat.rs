@@ -0,0 +1,132 @@
+//! 单人模式防作弊
+//!
+//! - 图片按对局重新编码并加入不可见扰动，同一条鱼在不同对局里字节不同，无法按哈希指纹识别
+//! - 名称、描述、作者等固定元数据不下发：回放会揭晓鱼的类型，否则可据此建立「元数据 → 类型」对照表
+//! - 服务端校验抓鱼节奏，过快的抓取记为可疑，累计超过阈值的对局不进入排行榜
+
+use chrono::{DateTime, Utc};
+use image::{ImageFormat, RgbaImage};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::Cursor;
+use uuid::Uuid;
+
+use crate::services::image_store::ImageBytes;
+use crate::services::ApiError;
+
+/// 每张图扰动的像素比例（千分之）
+const NOISE_PER_MILLE: u32 = 20;
+
+/// 对不透明像素的最低位做随机 ±1 扰动（肉眼不可见）
+fn perturb(img: &mut RgbaImage, rng: &mut StdRng) {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return;
+    }
+    let count = ((w as u64 * h as u64 * NOISE_PER_MILLE as u64) / 1000).max(1);
+    for _ in 0..count {
+        let px = img.get_pixel_mut(rng.gen_range(0..w), rng.gen_range(0..h));
+        if px[3] == 0 {
+            continue;
+        }
+        let channel = rng.gen_range(0..3);
+        px[channel] ^= 1;
+    }
+}
+
+/// 按对局内鱼实例重新编码图片：解码 → 扰动 → 输出 PNG（顺带丢弃原图的元数据块）
+pub fn reencode_for_instance(
+    original: &ImageBytes,
+    fish_instance_id: Uuid,
+) -> Result<ImageBytes, ApiError> {
+    let decoded = image::load_from_memory(&original.bytes)
+        .map_err(|e| ApiError::Internal(format!("Image decode failed: {}", e)))?;
+    let mut rgba = decoded.to_rgba8();
+
+    let mut rng = StdRng::seed_from_u64(fish_instance_id.as_u64_pair().0);
+    perturb(&mut rgba, &mut rng);
+
+    let mut out = Cursor::new(Vec::new());
+    rgba.write_to(&mut out, ImageFormat::Png)
+        .map_err(|e| ApiError::Internal(format!("Image encode failed: {}", e)))?;
+
+    Ok(ImageBytes {
+        content_type: "image/png",
+        bytes: out.into_inner(),
+    })
+}
+
+/// 抓鱼节奏校验参数
+#[derive(Debug, Clone, Copy)]
+pub struct CatchTiming {
+    /// 开局后第一抓的最短间隔
+    pub min_first_catch_ms: i64,
+    /// 相邻两次抓取的最短间隔
+    pub min_catch_interval_ms: i64,
+}
+
+/// 判断一次抓取是否可疑，返回原因
+pub fn assess_catch(
+    timing: CatchTiming,
+    started_at: DateTime<Utc>,
+    last_catch_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<&'static str> {
+    match last_catch_at {
+        Some(last) if (now - last).num_milliseconds() < timing.min_catch_interval_ms => {
+            Some("catch_interval_too_short")
+        }
+        None if (now - started_at).num_milliseconds() < timing.min_first_catch_ms => {
+            Some("first_catch_too_fast")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn reencode_differs_per_instance() {
+        let img = RgbaImage::from_pixel(32, 32, image::Rgba([120, 80, 200, 255]));
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, ImageFormat::Png).unwrap();
+        let original = ImageBytes {
+            content_type: "image/png",
+            bytes: buf.into_inner(),
+        };
+
+        let a = reencode_for_instance(&original, Uuid::new_v4()).unwrap();
+        let b = reencode_for_instance(&original, Uuid::new_v4()).unwrap();
+        assert_ne!(a.bytes, b.bytes);
+        assert_ne!(a.bytes, original.bytes);
+    }
+
+    #[test]
+    fn assess_catch_flags_bot_speed() {
+        let timing = CatchTiming {
+            min_first_catch_ms: 500,
+            min_catch_interval_ms: 200,
+        };
+        let start = Utc::now();
+
+        assert_eq!(
+            assess_catch(timing, start, None, start + Duration::milliseconds(100)),
+            Some("first_catch_too_fast")
+        );
+        assert_eq!(
+            assess_catch(timing, start, None, start + Duration::seconds(2)),
+            None
+        );
+        let last = start + Duration::seconds(2);
+        assert_eq!(
+            assess_catch(timing, start, Some(last), last + Duration::milliseconds(50)),
+            Some("catch_interval_too_short")
+        );
+        assert_eq!(
+            assess_catch(timing, start, Some(last), last + Duration::seconds(1)),
+            None
+        );
+    }
+}
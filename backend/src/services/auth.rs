@@ -367,6 +367,26 @@ pub async fn login_guest_device(
     })
 }
 
+/// 校验管理端 Bearer token；未配置 `ADMIN_API_TOKEN` 时管理接口整体不可见
+pub fn verify_admin_token(config: &Config, token: &str) -> Result<(), ApiError> {
+    let Some(expected) = config.admin_api_token.as_deref() else {
+        return Err(ApiError::NotFound("Not found".to_string()));
+    };
+
+    if constant_time_eq(expected.as_bytes(), token.trim().as_bytes()) {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized("Unauthorized".to_string()))
+    }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 pub async fn user_id_from_token(db: &PgPool, token: &str) -> Result<Uuid, ApiError> {
     let user_id: Option<Uuid> = sqlx::query_scalar(
         r#"
@@ -421,7 +441,7 @@ pub async fn list_identities_for_user(
 
 #[cfg(test)]
 mod tests {
-    use super::{is_valid_guest_device_token, normalize_guest_openid};
+    use super::{constant_time_eq, is_valid_guest_device_token, normalize_guest_openid};
 
     #[test]
     fn normalize_guest_openid_accepts_existing_server_token() {
@@ -449,4 +469,11 @@ mod tests {
         assert!(!is_valid_guest_device_token("device_123"));
         assert!(!is_valid_guest_device_token("guest_device:contains space"));
     }
+
+    #[test]
+    fn constant_time_eq_compares_full_length() {
+        assert!(constant_time_eq(b"admin-secret", b"admin-secret"));
+        assert!(!constant_time_eq(b"admin-secret", b"admin-secreT"));
+        assert!(!constant_time_eq(b"admin-secret", b"admin"));
+    }
 }
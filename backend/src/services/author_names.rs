@@ -0,0 +1,245 @@
+//! AI 鱼的作者名
+//!
+//! 从多语种名字库（`author_corpus/*.txt`，每行一个）加上主题自带的名字中抽取，
+//! 主题在 `themes.author_corpus`（`{"locales": ["zh", "en"], "names": [...]}`）里选语种、追加名字。
+//! 抽取时参考房间里最近人类作者名的语种分布、名字长度和使用默认名的比例，
+//! 同一房间内不重复（默认名除外）。
+
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashSet;
+
+/// 人类不填作者名时的默认值，见 `CreateDrawingRequest`
+pub const DEFAULT_AUTHOR: &str = "匿名艺术家";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Zh,
+    En,
+    Ja,
+}
+
+impl Locale {
+    fn parse(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_lowercase().as_str() {
+            "zh" => Some(Locale::Zh),
+            "en" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+
+    /// 按字符判断名字的语种：含假名为日文，含汉字为中文，含拉丁字母为英文
+    pub fn detect(name: &str) -> Option<Self> {
+        let mut han = false;
+        let mut latin = false;
+        for c in name.chars() {
+            match c {
+                '\u{3040}'..='\u{30FF}' => return Some(Locale::Ja),
+                '\u{4E00}'..='\u{9FFF}' => han = true,
+                c if c.is_ascii_alphabetic() => latin = true,
+                _ => {}
+            }
+        }
+        if han {
+            Some(Locale::Zh)
+        } else if latin {
+            Some(Locale::En)
+        } else {
+            None
+        }
+    }
+
+    fn builtin(self) -> &'static [&'static str] {
+        match self {
+            Locale::Zh => &ZH,
+            Locale::En => &EN,
+            Locale::Ja => &JA,
+        }
+    }
+}
+
+fn lines(text: &'static str) -> Vec<&'static str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+static ZH: Lazy<Vec<&'static str>> = Lazy::new(|| lines(include_str!("author_corpus/zh.txt")));
+static EN: Lazy<Vec<&'static str>> = Lazy::new(|| lines(include_str!("author_corpus/en.txt")));
+static JA: Lazy<Vec<&'static str>> = Lazy::new(|| lines(include_str!("author_corpus/ja.txt")));
+
+/// 主题的名字库配置
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct AuthorCorpusConfig {
+    #[serde(default)]
+    pub locales: Vec<String>,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+/// 一个主题可用的名字
+pub struct AuthorCorpus {
+    locales: Vec<Locale>,
+    names: Vec<(Locale, String)>,
+}
+
+impl AuthorCorpus {
+    /// 未配置语种时只用中文
+    pub fn from_config(config: &AuthorCorpusConfig) -> Self {
+        let mut locales: Vec<Locale> = Vec::new();
+        for locale in config.locales.iter().filter_map(|l| Locale::parse(l)) {
+            if !locales.contains(&locale) {
+                locales.push(locale);
+            }
+        }
+        if locales.is_empty() {
+            locales.push(Locale::Zh);
+        }
+
+        let mut names: Vec<(Locale, String)> = locales
+            .iter()
+            .flat_map(|&l| l.builtin().iter().map(move |n| (l, n.to_string())))
+            .collect();
+        for name in &config.names {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let locale = Locale::detect(name).unwrap_or(locales[0]);
+            names.push((locale, name.to_string()));
+        }
+        Self { locales, names }
+    }
+
+    pub fn from_theme_value(value: &serde_json::Value) -> Self {
+        let config: AuthorCorpusConfig = serde_json::from_value(value.clone()).unwrap_or_default();
+        Self::from_config(&config)
+    }
+
+    /// 抽一个作者名
+    ///
+    /// `human_names` 为房间最近的人类作者名，`used` 为房间里已出现的全部作者名
+    pub fn pick<R: Rng>(
+        &self,
+        human_names: &[String],
+        used: &HashSet<String>,
+        rng: &mut R,
+    ) -> String {
+        // 1. 人类常用默认名时，AI 也按同样比例用默认名
+        if !human_names.is_empty() {
+            let anonymous = human_names
+                .iter()
+                .filter(|n| n.as_str() == DEFAULT_AUTHOR)
+                .count();
+            if rng.gen_ratio(anonymous as u32, human_names.len() as u32) {
+                return DEFAULT_AUTHOR.to_string();
+            }
+        }
+
+        // 2. 随机挑一个非默认的人类名字作为样本，沿用它的语种和长度
+        let samples: Vec<&String> = human_names
+            .iter()
+            .filter(|n| n.as_str() != DEFAULT_AUTHOR)
+            .filter(|n| Locale::detect(n).is_some_and(|l| self.locales.contains(&l)))
+            .collect();
+        let (locale, target_len) = match samples.choose(rng) {
+            Some(sample) => (
+                Locale::detect(sample).unwrap_or(self.locales[0]),
+                Some(sample.chars().count()),
+            ),
+            None => (*self.locales.choose(rng).unwrap_or(&Locale::Zh), None),
+        };
+
+        // 3. 先在同语种、长度相近且没用过的名字里挑，逐步放宽
+        let unused: Vec<&(Locale, String)> = self
+            .names
+            .iter()
+            .filter(|(_, n)| !used.contains(n))
+            .collect();
+        let same_locale: Vec<&(Locale, String)> = unused
+            .iter()
+            .copied()
+            .filter(|(l, _)| *l == locale)
+            .collect();
+        let tiers: [Vec<&(Locale, String)>; 3] = [
+            match target_len {
+                Some(len) => same_locale
+                    .iter()
+                    .copied()
+                    .filter(|(_, n)| n.chars().count().abs_diff(len) <= 1)
+                    .collect(),
+                None => Vec::new(),
+            },
+            same_locale,
+            unused,
+        ];
+        for tier in &tiers {
+            if let Some((_, name)) = tier.choose(rng) {
+                return name.clone();
+            }
+        }
+
+        // 4. 名字库用尽：加数字后缀
+        let base = self
+            .names
+            .choose(rng)
+            .map(|(_, n)| n.as_str())
+            .unwrap_or(DEFAULT_AUTHOR);
+        (2..)
+            .map(|i| format!("{}{}", base, i))
+            .find(|n| !used.contains(n))
+            .unwrap_or_else(|| base.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn corpus(locales: &[&str], names: &[&str]) -> AuthorCorpus {
+        AuthorCorpus::from_config(&AuthorCorpusConfig {
+            locales: locales.iter().map(|s| s.to_string()).collect(),
+            names: names.iter().map(|s| s.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn follows_room_locale_and_never_repeats() {
+        let c = corpus(&["zh", "en", "ja"], &[]);
+        let humans: Vec<String> = vec!["Tom".into(), "lily_b".into(), "Jack".into()];
+        let mut used: HashSet<String> = humans.iter().cloned().collect();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let name = c.pick(&humans, &used, &mut rng);
+            assert_eq!(Locale::detect(&name), Some(Locale::En), "{}", name);
+            assert!(used.insert(name));
+        }
+    }
+
+    #[test]
+    fn exhausted_corpus_gets_suffix_and_defaults_are_mirrored() {
+        let c = AuthorCorpus {
+            locales: vec![Locale::Zh],
+            names: vec![(Locale::Zh, "小明".to_string())],
+        };
+        let mut rng = StdRng::seed_from_u64(2);
+        let used: HashSet<String> = ["小明".to_string(), "小明2".to_string()].into();
+        assert_eq!(c.pick(&[], &used, &mut rng), "小明3");
+
+        let humans = vec![DEFAULT_AUTHOR.to_string(); 3];
+        assert_eq!(c.pick(&humans, &used, &mut rng), DEFAULT_AUTHOR);
+
+        assert_eq!(Locale::detect("さくら"), Some(Locale::Ja));
+        assert_eq!(Locale::detect("小明"), Some(Locale::Zh));
+        assert_eq!(Locale::detect("123"), None);
+        let themed = corpus(&[], &["Nemo"]);
+        assert!(themed
+            .names
+            .iter()
+            .any(|(l, n)| *l == Locale::En && n == "Nemo"));
+    }
+}
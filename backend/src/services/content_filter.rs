@@ -0,0 +1,416 @@
+//! 用户文本审核：画作名称/描述、作者名与评论
+//!
+//! 先清理（去控制字符与零宽字符、合并空白、按字段截断），再与屏蔽词比对。
+//! 比对在归一化后的「骨架」上进行：全角转半角、小写、形近的西里尔/希腊字母与 leet 写法还原，
+//! 空白和标点不参与比对。逐字隔开的写法（分隔符两侧都只有一个字）会拼成一个词，
+//! 所以 `傻 逼`、`ｆｕｃｋ`、`f.u.c.k`、`fцck` 都能命中；多字片段之间的分隔符不能跨越。
+//! 纯字母数字的屏蔽词按词边界匹配（`class` 不会因 `ass` 被误伤，`U.S.B` 拼成 `usb` 后也不含 `sb`），其余按子串匹配。
+//!
+//! 屏蔽词为内置词表（`content_blocklist.txt`）加上 `CONTENT_FILTER_BLOCKLIST_PATH` 指向的文件；
+//! `CONTENT_FILTER_MODE` 为 `replace`（命中部分替换为 `*`，默认）、`reject`（整条拒绝）或 `off`。
+
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::services::ApiError;
+
+const BUILTIN_BLOCKLIST: &str = include_str!("content_blocklist.txt");
+
+/// 命中时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    Off,
+    Replace,
+    Reject,
+}
+
+impl FromStr for FilterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(FilterMode::Off),
+            "replace" => Ok(FilterMode::Replace),
+            "reject" => Ok(FilterMode::Reject),
+            other => Err(format!("unknown content filter mode {:?}", other)),
+        }
+    }
+}
+
+/// 需要审核的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    DrawingName,
+    Description,
+    AuthorName,
+    CommentAuthor,
+    CommentContent,
+}
+
+impl Field {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Field::DrawingName => "name",
+            Field::Description => "description",
+            Field::AuthorName => "authorName",
+            Field::CommentAuthor => "comment.author",
+            Field::CommentContent => "comment.content",
+        }
+    }
+
+    /// 最大字符数；`drawings.name` 列宽 24，但画作名称有意保持原来截到 8 个字的行为，
+    /// `description` 与列宽一致为 60
+    pub fn max_chars(self) -> usize {
+        match self {
+            Field::DrawingName => 8,
+            Field::Description => 60,
+            Field::AuthorName => 20,
+            Field::CommentAuthor => 20,
+            Field::CommentContent => 100,
+        }
+    }
+}
+
+/// `reject` 模式下文本含屏蔽词
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected {
+    pub field: Field,
+}
+
+impl From<Rejected> for ApiError {
+    fn from(r: Rejected) -> Self {
+        ApiError::BadRequest(format!("{} contains blocked content", r.field.as_str()))
+    }
+}
+
+struct Term {
+    chars: Vec<char>,
+    /// 纯字母数字，按词边界匹配
+    word: bool,
+}
+
+pub struct ContentFilter {
+    mode: FilterMode,
+    terms: Vec<Term>,
+}
+
+/// 骨架：归一化后的字符，以及每个字符在原文中的位置、前面是否隔着不可跨越的分隔符
+/// （逐字隔开的单字之间的分隔符可以跨越，不算）
+struct Skeleton {
+    chars: Vec<char>,
+    origin: Vec<usize>,
+    break_before: Vec<bool>,
+}
+
+/// 形近字母与 leet 写法
+fn fold_homoglyph(c: char) -> char {
+    match c {
+        'а' | 'α' | '@' | '4' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ς' => 'c',
+        'е' | 'ε' | 'ё' | '3' => 'e',
+        'н' => 'h',
+        'і' | 'ι' | 'ї' | '1' => 'i',
+        'к' | 'κ' => 'k',
+        'м' => 'm',
+        'η' => 'n',
+        'о' | 'ο' | 'σ' | '0' => 'o',
+        'р' | 'ρ' => 'p',
+        'ѕ' | '$' | '5' => 's',
+        'т' | 'τ' | '7' => 't',
+        'ц' | 'υ' | 'μ' => 'u',
+        'ν' => 'v',
+        'ш' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        _ => c,
+    }
+}
+
+fn normalize_char(c: char) -> char {
+    // 全角 ASCII 与全角空格
+    let c = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    };
+    let c = c.to_lowercase().next().unwrap_or(c);
+    fold_homoglyph(c)
+}
+
+/// 比对时跳过的字符：空白、零宽字符与各类标点
+fn is_separator(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_control()
+        || c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{00B7}'
+                | '\u{2000}'..='\u{206F}'
+                | '\u{3000}'..='\u{303F}'
+                | '\u{FE30}'..='\u{FE4F}'
+                | '\u{FF01}'..='\u{FF0F}'
+                | '\u{FEFF}'
+        )
+}
+
+/// 不可见的格式字符：零宽、方向控制等
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+fn skeleton(text: &[char]) -> Skeleton {
+    let mut chars = Vec::with_capacity(text.len());
+    let mut origin = Vec::with_capacity(text.len());
+    let mut gap_before = Vec::with_capacity(text.len());
+    let mut gap = true;
+    for (i, &c) in text.iter().enumerate() {
+        let n = normalize_char(c);
+        if is_separator(n) {
+            gap = true;
+            continue;
+        }
+        chars.push(n);
+        origin.push(i);
+        gap_before.push(gap);
+        gap = false;
+    }
+
+    // 每个字符所在片段（被分隔符隔开的一段）的长度；两侧片段都是单字时分隔符可以跨越
+    let n = chars.len();
+    let mut run_len = vec![0; n];
+    let mut run_start = 0;
+    for i in 1..=n {
+        if i == n || gap_before[i] {
+            run_len[run_start..i].fill(i - run_start);
+            run_start = i;
+        }
+    }
+    let break_before = (0..n)
+        .map(|i| gap_before[i] && !(i > 0 && run_len[i - 1] == 1 && run_len[i] == 1))
+        .collect();
+
+    Skeleton {
+        chars,
+        origin,
+        break_before,
+    }
+}
+
+/// 去掉控制字符与零宽字符，合并连续空白并去掉首尾空白
+pub fn clean(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pending_space = false;
+    for c in text.chars() {
+        if is_invisible(c) || (c.is_control() && !c.is_whitespace()) {
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = !out.is_empty();
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl ContentFilter {
+    pub fn new<'a>(mode: FilterMode, terms: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut parsed: Vec<Term> = Vec::new();
+        for line in terms {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let chars: Vec<char> = line.chars().collect();
+            let chars = skeleton(&chars).chars;
+            if chars.is_empty() || parsed.iter().any(|t| t.chars == chars) {
+                continue;
+            }
+            let word = chars.iter().all(|c| c.is_ascii_alphanumeric());
+            parsed.push(Term { chars, word });
+        }
+        Self {
+            mode,
+            terms: parsed,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, ApiError> {
+        let extra = match config.content_filter_blocklist_path.as_deref() {
+            Some(path) => std::fs::read_to_string(path).map_err(|e| {
+                ApiError::Internal(format!("Failed to read blocklist {}: {}", path, e))
+            })?,
+            None => String::new(),
+        };
+        let filter = Self::new(
+            config.content_filter_mode,
+            BUILTIN_BLOCKLIST.lines().chain(extra.lines()),
+        );
+        tracing::info!(
+            "Content filter: mode={:?}, {} terms",
+            filter.mode,
+            filter.terms.len()
+        );
+        Ok(filter)
+    }
+
+    /// 命中的原文区间（按字符下标，左闭右开）
+    fn find_matches(&self, text: &[char]) -> Vec<(usize, usize)> {
+        let sk = skeleton(text);
+        let n = sk.chars.len();
+        let mut found = Vec::new();
+        for term in &self.terms {
+            let m = term.chars.len();
+            if m > n {
+                continue;
+            }
+            for start in 0..=n - m {
+                if sk.chars[start..start + m] != term.chars[..] {
+                    continue;
+                }
+                let end = start + m;
+                if sk.break_before[start + 1..end].contains(&true) {
+                    continue;
+                }
+                if term.word {
+                    let left = start == 0
+                        || sk.break_before[start]
+                        || !sk.chars[start - 1].is_ascii_alphanumeric();
+                    let right =
+                        end == n || sk.break_before[end] || !sk.chars[end].is_ascii_alphanumeric();
+                    if !(left && right) {
+                        continue;
+                    }
+                }
+                found.push((sk.origin[start], sk.origin[end - 1] + 1));
+            }
+        }
+        found
+    }
+
+    /// 清理、截断并审核一个字段，返回可存储/广播的文本
+    pub fn moderate(&self, field: Field, text: &str) -> Result<String, Rejected> {
+        let mut chars: Vec<char> = clean(text).chars().collect();
+        chars.truncate(field.max_chars());
+        while chars.last().is_some_and(|c| c.is_whitespace()) {
+            chars.pop();
+        }
+
+        if self.mode == FilterMode::Off {
+            return Ok(chars.into_iter().collect());
+        }
+        let matches = self.find_matches(&chars);
+        if matches.is_empty() {
+            return Ok(chars.into_iter().collect());
+        }
+        if self.mode == FilterMode::Reject {
+            return Err(Rejected { field });
+        }
+
+        for (start, end) in matches {
+            for c in &mut chars[start..end] {
+                if !c.is_whitespace() {
+                    *c = '*';
+                }
+            }
+        }
+        Ok(chars.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(mode: FilterMode) -> ContentFilter {
+        ContentFilter::new(mode, BUILTIN_BLOCKLIST.lines())
+    }
+
+    #[test]
+    fn masks_terms_through_homoglyphs_and_spacing() {
+        let f = filter(FilterMode::Replace);
+        let moderate = |text: &str| f.moderate(Field::CommentContent, text).unwrap();
+
+        assert_eq!(moderate("你这个傻逼"), "你这个**");
+        assert_eq!(moderate("傻 逼"), "* *");
+        assert_eq!(moderate("傻\u{200B}逼"), "**");
+        assert_eq!(moderate("ＦＵＣＫ off"), "**** off");
+        assert_eq!(moderate("f.u.c.k"), "*******");
+        assert_eq!(moderate("fцck"), "****");
+        assert_eq!(moderate("sh1t!"), "****!");
+        assert_eq!(moderate("好看的鱼"), "好看的鱼");
+    }
+
+    #[test]
+    fn ascii_terms_respect_word_boundaries() {
+        let f = filter(FilterMode::Replace);
+        let moderate = |text: &str| f.moderate(Field::CommentContent, text).unwrap();
+
+        assert_eq!(moderate("classic grass"), "classic grass");
+        assert_eq!(moderate("Dickens"), "Dickens");
+        assert_eq!(moderate("sb2024"), "sb2024");
+        assert_eq!(moderate("你是sb吗"), "你是**吗");
+        assert_eq!(moderate("what an asshole"), "what an *******");
+    }
+
+    #[test]
+    fn separators_only_join_single_characters() {
+        let f = filter(FilterMode::Replace);
+        let moderate = |text: &str| f.moderate(Field::CommentContent, text).unwrap();
+
+        assert_eq!(moderate("U.S.B"), "U.S.B");
+        assert_eq!(moderate("a usb cable"), "a usb cable");
+        assert_eq!(moderate("s b"), "* *");
+        assert_eq!(moderate("sh it"), "sh it");
+        assert_eq!(moderate("傻 逼"), "* *");
+    }
+
+    #[test]
+    fn rejects_in_reject_mode_and_passes_when_off() {
+        let f = filter(FilterMode::Reject);
+        assert_eq!(
+            f.moderate(Field::AuthorName, "草泥马"),
+            Err(Rejected {
+                field: Field::AuthorName
+            })
+        );
+        assert_eq!(
+            f.moderate(Field::AuthorName, "小明"),
+            Ok("小明".to_string())
+        );
+
+        let off = filter(FilterMode::Off);
+        assert_eq!(
+            off.moderate(Field::AuthorName, "草泥马"),
+            Ok("草泥马".to_string())
+        );
+        assert!("Replace".parse::<FilterMode>() == Ok(FilterMode::Replace));
+        assert!("block".parse::<FilterMode>().is_err());
+    }
+
+    #[test]
+    fn cleans_and_caps_length() {
+        let f = filter(FilterMode::Replace);
+        assert_eq!(
+            f.moderate(Field::DrawingName, "  小丑\u{202E}鱼\t\n 一号鱼鱼鱼鱼鱼 "),
+            Ok("小丑鱼 一号鱼鱼".to_string())
+        );
+        assert_eq!(clean("a\u{0007}b   c\u{FEFF}"), "ab c");
+
+        let custom = ContentFilter::new(FilterMode::Replace, ["# comment", "", "鲨鱼", "鲨 鱼"]);
+        assert_eq!(custom.terms.len(), 1);
+        assert_eq!(
+            custom.moderate(Field::Description, "大鲨鱼"),
+            Ok("大**".to_string())
+        );
+    }
+}
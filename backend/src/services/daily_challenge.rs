@@ -0,0 +1,259 @@
+//! 每日挑战：按日期种子确定性抽鱼，当天所有玩家同一组鱼、同一顺序
+//!
+//! 当天第一次开局时生成并写入 `daily_challenge_fish`，之后直接复用，
+//! 鱼库在当天内的改动不会影响已生成的挑战。
+
+use chrono::NaiveDate;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::{DailyStreak, FishKind, SelectedFish, SinglePlayerLevel};
+use crate::services::ApiError;
+
+/// 日期 → 抽样种子
+pub fn seed_for(date: NaiveDate) -> u64 {
+    let digest = Sha256::digest(format!("daily-challenge:{}", date).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// 加权无放回抽样（与 `start_game` 中 SQL 的 `-LN(random()) / weight` 同一算法）；
+/// 候选不足且允许重复时，从已抽中的里补齐
+pub fn weighted_sample<R: Rng>(
+    candidates: &[(Uuid, i32)],
+    n: usize,
+    allow_duplicates: bool,
+    rng: &mut R,
+) -> Vec<Uuid> {
+    let mut keyed: Vec<(f64, Uuid)> = candidates
+        .iter()
+        .map(|(id, weight)| {
+            let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+            (-u.ln() / (*weight).max(1) as f64, *id)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut picked: Vec<Uuid> = keyed.into_iter().take(n).map(|(_, id)| id).collect();
+    if allow_duplicates && !picked.is_empty() {
+        while picked.len() < n {
+            let extra = picked[rng.gen_range(0..picked.len())];
+            picked.push(extra);
+        }
+    }
+    picked
+}
+
+/// 下一次连续挑战天数：昨天参加过则 +1，同一天不变，否则重新从 1 开始
+pub fn next_streak(last_date: Option<NaiveDate>, current: i32, date: NaiveDate) -> i32 {
+    match last_date {
+        Some(last) if last == date => current,
+        Some(last) if last.succ_opt() == Some(date) => current + 1,
+        _ => 1,
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CandidateRow {
+    id: Uuid,
+    weight: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChallengeFishRow {
+    fish_kind: String,
+    fish_id: Uuid,
+}
+
+/// 取当天的挑战鱼（不存在则按种子生成）
+pub async fn ensure_challenge(
+    conn: &mut PgConnection,
+    date: NaiveDate,
+    level: &SinglePlayerLevel,
+    allow_duplicates: bool,
+) -> Result<Vec<SelectedFish>, ApiError> {
+    let existing = load_challenge(conn, date).await?;
+    if !existing.is_empty() {
+        return Ok(existing);
+    }
+
+    let seed = seed_for(date);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut picked: Vec<(FishKind, Uuid)> = Vec::with_capacity(level.total_fish as usize);
+    for (kind, n) in [
+        (FishKind::Human, level.human_count()),
+        (FishKind::Ai, level.ai_count),
+    ] {
+        // 按 id 排序保证同一鱼库下候选顺序稳定
+        let candidates: Vec<CandidateRow> = sqlx::query_as(&format!(
+            "SELECT id, weight FROM {} WHERE is_active = TRUE AND difficulty_level = $1 ORDER BY id",
+            kind.table()
+        ))
+        .bind(level.difficulty_level)
+        .fetch_all(&mut *conn)
+        .await?;
+        let candidates: Vec<(Uuid, i32)> =
+            candidates.into_iter().map(|c| (c.id, c.weight)).collect();
+
+        let ids = weighted_sample(&candidates, n.max(0) as usize, allow_duplicates, &mut rng);
+        if ids.len() < n.max(0) as usize {
+            return Err(ApiError::BadRequest(
+                "Not enough fish library data".to_string(),
+            ));
+        }
+        picked.extend(ids.into_iter().map(|id| (kind, id)));
+    }
+    picked.shuffle(&mut rng);
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO daily_challenges (challenge_date, level_id, seed)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (challenge_date) DO NOTHING
+        "#,
+    )
+    .bind(date)
+    .bind(level.id)
+    .bind(seed as i64)
+    .execute(&mut *conn)
+    .await?;
+
+    // 并发开局时别人已经生成好了
+    if inserted.rows_affected() == 0 {
+        return load_challenge(conn, date).await;
+    }
+
+    for (order_index, (kind, fish_id)) in picked.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO daily_challenge_fish (challenge_date, order_index, fish_kind, fish_id)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(date)
+        .bind(order_index as i32)
+        .bind(kind.run_fish_kind())
+        .bind(fish_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    load_challenge(conn, date).await
+}
+
+async fn load_challenge(
+    conn: &mut PgConnection,
+    date: NaiveDate,
+) -> Result<Vec<SelectedFish>, ApiError> {
+    let rows: Vec<ChallengeFishRow> = sqlx::query_as(
+        r#"
+        SELECT fish_kind, fish_id
+        FROM daily_challenge_fish
+        WHERE challenge_date = $1
+        ORDER BY order_index ASC
+        "#,
+    )
+    .bind(date)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| SelectedFish {
+            kind: FishKind::from_run_fish_kind(&r.fish_kind).unwrap_or(FishKind::Human),
+            id: r.fish_id,
+        })
+        .collect())
+}
+
+pub async fn get_streak(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<DailyStreak>, ApiError> {
+    let row: Option<DailyStreak> = sqlx::query_as(
+        "SELECT current_streak, best_streak, last_date FROM daily_challenge_streaks WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(row)
+}
+
+/// 提交每日挑战后更新连续天数
+pub async fn record_attempt(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    date: NaiveDate,
+) -> Result<DailyStreak, ApiError> {
+    let prev = get_streak(&mut *conn, user_id).await?;
+    let current = next_streak(
+        prev.as_ref().map(|s| s.last_date),
+        prev.as_ref().map(|s| s.current_streak).unwrap_or(0),
+        date,
+    );
+    let best = current.max(prev.as_ref().map(|s| s.best_streak).unwrap_or(0));
+
+    let row: DailyStreak = sqlx::query_as(
+        r#"
+        INSERT INTO daily_challenge_streaks (user_id, current_streak, best_streak, last_date)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE SET
+            current_streak = EXCLUDED.current_streak,
+            best_streak = EXCLUDED.best_streak,
+            last_date = EXCLUDED.last_date,
+            updated_at = NOW()
+        RETURNING current_streak, best_streak, last_date
+        "#,
+    )
+    .bind(user_id)
+    .bind(current)
+    .bind(best)
+    .bind(date)
+    .fetch_one(conn)
+    .await?;
+    Ok(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn sampling_is_deterministic_per_day() {
+        let candidates: Vec<(Uuid, i32)> = (0..20)
+            .map(|i| (Uuid::from_u128(i), 1 + (i % 3) as i32))
+            .collect();
+        let pick = |d: NaiveDate| {
+            let mut rng = StdRng::seed_from_u64(seed_for(d));
+            weighted_sample(&candidates, 5, false, &mut rng)
+        };
+
+        assert_eq!(pick(date(2026, 3, 1)), pick(date(2026, 3, 1)));
+        assert_ne!(pick(date(2026, 3, 1)), pick(date(2026, 3, 2)));
+    }
+
+    #[test]
+    fn sampling_fills_with_duplicates_only_when_allowed() {
+        let candidates = vec![(Uuid::from_u128(1), 1), (Uuid::from_u128(2), 1)];
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_eq!(weighted_sample(&candidates, 4, false, &mut rng).len(), 2);
+        assert_eq!(weighted_sample(&candidates, 4, true, &mut rng).len(), 4);
+    }
+
+    #[test]
+    fn streak_continues_only_on_consecutive_days() {
+        let today = date(2026, 3, 2);
+        assert_eq!(next_streak(None, 0, today), 1);
+        assert_eq!(next_streak(Some(date(2026, 3, 1)), 4, today), 5);
+        assert_eq!(next_streak(Some(today), 4, today), 4);
+        assert_eq!(next_streak(Some(date(2026, 2, 27)), 4, today), 1);
+    }
+}
@@ -0,0 +1,90 @@
+//! 无尽模式：找齐当前波次的 AI 鱼后请求下一波，难度随波次逐级上升，
+//! 生命值沿用关卡的 `max_mistakes` 且跨波次累计，只有生命耗尽才结束。
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::SinglePlayerLevel;
+
+/// 无尽模式没有倒计时，`ends_at` 是闲置截止时间：开局、每次抓取与下一波都会
+/// 顺延到 `now + idle_seconds`，玩家离开后由清理任务回收，不再长期占用进行中名额
+pub fn idle_deadline(now: DateTime<Utc>, idle_seconds: i32) -> DateTime<Utc> {
+    now + Duration::seconds(idle_seconds.max(1) as i64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavePlan {
+    pub difficulty_level: i32,
+    pub human_count: i32,
+    pub ai_count: i32,
+}
+
+/// 第 `wave` 波（从 1 开始）的构成：每 `waves_per_tier` 波升一档难度、多一条 AI 鱼，
+/// 难度不超过 `max_difficulty`，AI 鱼不超过一波的一半（基础关卡更多时以基础为准）
+pub fn wave_plan(
+    wave: i32,
+    base: &SinglePlayerLevel,
+    waves_per_tier: i32,
+    max_difficulty: i32,
+) -> WavePlan {
+    let steps = (wave.max(1) - 1) / waves_per_tier.max(1);
+    let difficulty_level =
+        (base.difficulty_level + steps).min(max_difficulty.max(base.difficulty_level));
+    let ai_cap = (base.total_fish / 2).max(base.ai_count);
+    let ai_count = (base.ai_count + steps).min(ai_cap);
+
+    WavePlan {
+        difficulty_level,
+        human_count: base.total_fish - ai_count,
+        ai_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn base() -> SinglePlayerLevel {
+        SinglePlayerLevel {
+            id: Uuid::new_v4(),
+            level: 1,
+            theme_id: None,
+            total_fish: 10,
+            ai_count: 3,
+            seconds: 60,
+            max_mistakes: 3,
+            target_total: 3,
+            difficulty_level: 1,
+            time_bonus_seconds: 0,
+            mistake_penalty_seconds: 0,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn waves_escalate_per_tier() {
+        let b = base();
+        assert_eq!(
+            wave_plan(1, &b, 2, 3),
+            WavePlan {
+                difficulty_level: 1,
+                human_count: 7,
+                ai_count: 3
+            }
+        );
+        assert_eq!(wave_plan(2, &b, 2, 3).difficulty_level, 1);
+        assert_eq!(wave_plan(3, &b, 2, 3).difficulty_level, 2);
+        assert_eq!(wave_plan(3, &b, 2, 3).ai_count, 4);
+    }
+
+    #[test]
+    fn waves_are_capped() {
+        let b = base();
+        let plan = wave_plan(100, &b, 2, 3);
+        assert_eq!(plan.difficulty_level, 3);
+        assert_eq!(plan.ai_count, 5);
+        assert_eq!(plan.human_count, 5);
+    }
+}
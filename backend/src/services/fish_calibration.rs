@@ -0,0 +1,305 @@
+//! 单人模式鱼难度自动校准
+//!
+//! 按 `single_player_catches` 统计每条鱼在已结束对局中的"迷惑率"（fool rate）：
+//! - AI 鱼：玩家找到最后仍没被抓 → 越难识破，迷惑率越高
+//! - 人类鱼：被误抓 → 越像 AI，迷惑率越高
+//!
+//! 样本足够的鱼按迷惑率分位数重新分桶到难度等级，并按迷惑率调整抽样权重。
+//! `auto_calibrate = FALSE` 的鱼只记录统计，不改动难度与权重。
+
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::FishKind;
+use crate::services::{ApiError, AppState};
+
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationSummary {
+    pub evaluated: usize,
+    pub rebucketed: usize,
+    pub reweighted: usize,
+}
+
+/// 后台校准任务：按 `FISH_CALIBRATION_INTERVAL_SECONDS` 周期运行，0 表示关闭
+pub async fn start_fish_calibration(state: Arc<AppState>) {
+    let interval_secs = state.config.fish_calibration_interval_seconds;
+    if interval_secs == 0 {
+        tracing::info!("[FishCalibration] disabled");
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    // 跳过启动时的立即触发，避免和服务启动抢资源
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        match run_calibration(&state).await {
+            Ok(summary) => tracing::info!(
+                "[FishCalibration] evaluated={} rebucketed={} reweighted={}",
+                summary.evaluated,
+                summary.rebucketed,
+                summary.reweighted
+            ),
+            Err(err) => tracing::warn!("[FishCalibration] run failed: {:?}", err),
+        }
+    }
+}
+
+/// 迷惑率：AI 鱼为未被抓比例，人类鱼为被误抓比例
+pub fn fool_rate(kind: FishKind, exposures: i64, catches: i64) -> f64 {
+    if exposures <= 0 {
+        return 0.0;
+    }
+    let caught = (catches.clamp(0, exposures) as f64) / (exposures as f64);
+    match kind {
+        FishKind::Ai => 1.0 - caught,
+        FishKind::Human => caught,
+    }
+}
+
+/// 按迷惑率分位数分桶到 `1..=levels`，样本数不足 `levels` 时不分桶
+pub fn bucket_levels(rates: &[(Uuid, f64)], levels: i32) -> Vec<(Uuid, i32)> {
+    let levels = levels.max(1);
+    if rates.len() < levels as usize {
+        return Vec::new();
+    }
+
+    let mut sorted: Vec<(Uuid, f64)> = rates.to_vec();
+    sorted.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+    let n = sorted.len();
+    sorted
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (id, _))| (id, 1 + (rank * levels as usize / n) as i32))
+        .collect()
+}
+
+/// 抽样权重：迷惑率越高越常出现，范围 `1..=max_weight`
+pub fn weight_for(fool_rate: f64, max_weight: i32) -> i32 {
+    let max_weight = max_weight.max(1);
+    1 + (fool_rate.clamp(0.0, 1.0) * (max_weight - 1) as f64).round() as i32
+}
+
+/// 一次出场是否算作玩家做过判断：被抓的鱼总算；没被抓的鱼只在玩家一直找到最后的对局里算
+///
+/// 超时/弃局的对局里剩下的鱼没人判断过；胜利时目标已凑齐，剩下的 AI 鱼也只是没必要再抓，
+/// 都不能记成"骗过了玩家"。人类鱼在胜利局里一直没被误抓，则是实打实的判断
+pub fn is_judged_exposure(kind: FishKind, run_status: &str, caught: bool) -> bool {
+    caught
+        || matches!(
+            (kind, run_status),
+            (_, "defeat") | (FishKind::Human, "victory")
+        )
+}
+
+#[derive(sqlx::FromRow)]
+struct FishStatsRow {
+    id: Uuid,
+    difficulty_level: i32,
+    weight: i32,
+    auto_calibrate: bool,
+    #[sqlx(skip)]
+    exposures: i64,
+    #[sqlx(skip)]
+    catches: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExposureRow {
+    fish_id: Uuid,
+    run_status: String,
+    caught: bool,
+    count: i64,
+}
+
+/// 立即执行一次校准（后台任务与管理端手动触发共用）
+pub async fn run_calibration(state: &AppState) -> Result<CalibrationSummary, ApiError> {
+    let mut summary = CalibrationSummary::default();
+    for kind in [FishKind::Human, FishKind::Ai] {
+        calibrate_kind(state, kind, &mut summary).await?;
+    }
+    Ok(summary)
+}
+
+async fn calibrate_kind(
+    state: &AppState,
+    kind: FishKind,
+    summary: &mut CalibrationSummary,
+) -> Result<(), ApiError> {
+    let min_samples = state.config.fish_calibration_min_samples.max(1);
+
+    let mut rows: Vec<FishStatsRow> = sqlx::query_as(&format!(
+        "SELECT id, difficulty_level, weight, auto_calibrate FROM {}",
+        kind.table()
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    let exposures: Vec<ExposureRow> = sqlx::query_as(
+        r#"
+        SELECT
+            rf.fish_id,
+            r.status AS run_status,
+            EXISTS (SELECT 1 FROM single_player_catches c WHERE c.run_fish_id = rf.id) AS caught,
+            COUNT(*) AS count
+        FROM single_player_run_fish rf
+        JOIN single_player_runs r ON r.id = rf.run_id
+        WHERE rf.fish_kind = $1 AND r.status <> 'active'
+        GROUP BY 1, 2, 3
+        "#,
+    )
+    .bind(kind.run_fish_kind())
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut tallies: std::collections::HashMap<Uuid, (i64, i64)> = std::collections::HashMap::new();
+    for e in exposures
+        .iter()
+        .filter(|e| is_judged_exposure(kind, &e.run_status, e.caught))
+    {
+        let tally = tallies.entry(e.fish_id).or_default();
+        tally.0 += e.count;
+        if e.caught {
+            tally.1 += e.count;
+        }
+    }
+    for row in &mut rows {
+        (row.exposures, row.catches) = tallies.get(&row.id).copied().unwrap_or_default();
+    }
+
+    let eligible: Vec<&FishStatsRow> = rows.iter().filter(|r| r.exposures >= min_samples).collect();
+    let rates: Vec<(Uuid, f64)> = eligible
+        .iter()
+        .filter(|r| r.auto_calibrate)
+        .map(|r| (r.id, fool_rate(kind, r.exposures, r.catches)))
+        .collect();
+    let new_levels: std::collections::HashMap<Uuid, i32> =
+        bucket_levels(&rates, state.config.fish_calibration_levels)
+            .into_iter()
+            .collect();
+
+    let mut tx = state.db.begin().await?;
+    for row in eligible {
+        summary.evaluated += 1;
+        let rate = fool_rate(kind, row.exposures, row.catches);
+
+        let (calibrated_level, calibrated_weight) = if row.auto_calibrate {
+            let level = new_levels
+                .get(&row.id)
+                .copied()
+                .unwrap_or(row.difficulty_level);
+            let weight = weight_for(rate, state.config.fish_calibration_max_weight);
+            if level != row.difficulty_level {
+                summary.rebucketed += 1;
+            }
+            if weight != row.weight {
+                summary.reweighted += 1;
+            }
+            if level != row.difficulty_level || weight != row.weight {
+                sqlx::query(&format!(
+                    "UPDATE {} SET difficulty_level = $2, weight = $3, updated_at = NOW() WHERE id = $1",
+                    kind.table()
+                ))
+                .bind(row.id)
+                .bind(level)
+                .bind(weight)
+                .execute(&mut *tx)
+                .await?;
+            }
+            (Some(level), Some(weight))
+        } else {
+            (None, None)
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO fish_calibration_stats (
+                fish_kind, fish_id, exposures, catches, fool_rate,
+                previous_level, calibrated_level, previous_weight, calibrated_weight, calibrated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+            ON CONFLICT (fish_kind, fish_id) DO UPDATE SET
+                exposures = EXCLUDED.exposures,
+                catches = EXCLUDED.catches,
+                fool_rate = EXCLUDED.fool_rate,
+                previous_level = EXCLUDED.previous_level,
+                calibrated_level = EXCLUDED.calibrated_level,
+                previous_weight = EXCLUDED.previous_weight,
+                calibrated_weight = EXCLUDED.calibrated_weight,
+                calibrated_at = EXCLUDED.calibrated_at
+            "#,
+        )
+        .bind(kind.run_fish_kind())
+        .bind(row.id)
+        .bind(row.exposures as i32)
+        .bind(row.catches as i32)
+        .bind(rate)
+        .bind(row.difficulty_level)
+        .bind(calibrated_level)
+        .bind(row.weight)
+        .bind(calibrated_weight)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fool_rate_inverts_for_ai_fish() {
+        assert!((fool_rate(FishKind::Ai, 10, 2) - 0.8).abs() < 1e-9);
+        assert!((fool_rate(FishKind::Human, 10, 2) - 0.2).abs() < 1e-9);
+        assert_eq!(fool_rate(FishKind::Ai, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn uncaught_fish_count_only_when_the_player_kept_looking() {
+        // 胜利时目标已凑齐，剩下的 AI 鱼没被判断过
+        assert!(!is_judged_exposure(FishKind::Ai, "victory", false));
+        assert!(!is_judged_exposure(FishKind::Ai, "expired", false));
+        assert!(!is_judged_exposure(FishKind::Human, "expired", false));
+        assert!(is_judged_exposure(FishKind::Ai, "defeat", false));
+        assert!(is_judged_exposure(FishKind::Human, "victory", false));
+        assert!(is_judged_exposure(FishKind::Ai, "expired", true));
+    }
+
+    #[test]
+    fn bucket_levels_splits_by_quantile() {
+        let ids: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        let rates: Vec<(Uuid, f64)> = ids
+            .iter()
+            .zip([0.9, 0.1, 0.5, 0.2, 0.8, 0.4])
+            .map(|(id, r)| (*id, r))
+            .collect();
+        let levels: std::collections::HashMap<Uuid, i32> =
+            bucket_levels(&rates, 3).into_iter().collect();
+
+        assert_eq!(levels[&ids[1]], 1);
+        assert_eq!(levels[&ids[3]], 1);
+        assert_eq!(levels[&ids[5]], 2);
+        assert_eq!(levels[&ids[2]], 2);
+        assert_eq!(levels[&ids[4]], 3);
+        assert_eq!(levels[&ids[0]], 3);
+    }
+
+    #[test]
+    fn bucket_levels_skips_small_samples() {
+        let rates = vec![(Uuid::new_v4(), 0.9)];
+        assert!(bucket_levels(&rates, 3).is_empty());
+    }
+
+    #[test]
+    fn weight_scales_with_fool_rate() {
+        assert_eq!(weight_for(0.0, 5), 1);
+        assert_eq!(weight_for(0.5, 5), 3);
+        assert_eq!(weight_for(1.0, 5), 5);
+        assert_eq!(weight_for(0.7, 1), 1);
+    }
+}
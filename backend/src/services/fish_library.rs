@@ -0,0 +1,160 @@
+//! 单人模式鱼库（`human_fish` / `ai_fish`）入库逻辑
+//!
+//! 管理端接口、批量导入与多人局画作转存共用同一入口，按图片内容哈希去重。
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::models::{FishKind, LibraryFish};
+use crate::services::image_store::decode_image_data;
+use crate::services::{ApiError, AppState};
+
+/// 单次批量导入上限
+pub const MAX_IMPORT_BATCH: usize = 100;
+
+/// 待入库的鱼
+pub struct NewLibraryFish {
+    pub image_data: String,
+    pub difficulty_level: i32,
+    pub weight: i32,
+    pub metadata: serde_json::Value,
+    pub is_active: bool,
+    pub source_drawing_id: Option<Uuid>,
+}
+
+pub enum InsertOutcome {
+    Inserted(LibraryFish),
+    /// 鱼库中已存在相同图片（可能在另一个库中）
+    Duplicate {
+        kind: FishKind,
+        id: Uuid,
+    },
+}
+
+/// 图片内容哈希（sha256 hex），用于去重
+pub fn content_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub fn validate_library_fields(
+    difficulty_level: i32,
+    weight: i32,
+    metadata: &serde_json::Value,
+) -> Result<(), ApiError> {
+    if difficulty_level < 1 {
+        return Err(ApiError::BadRequest(
+            "difficultyLevel must be >= 1".to_string(),
+        ));
+    }
+    if weight < 1 {
+        return Err(ApiError::BadRequest("weight must be >= 1".to_string()));
+    }
+    if !metadata.is_object() {
+        return Err(ApiError::BadRequest(
+            "metadata must be a JSON object".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// 查找任一鱼库中哈希相同的鱼
+async fn find_by_hash(state: &AppState, hash: &str) -> Result<Option<(FishKind, Uuid)>, ApiError> {
+    for kind in [FishKind::Human, FishKind::Ai] {
+        let existing: Option<Uuid> = sqlx::query_scalar(&format!(
+            "SELECT id FROM {} WHERE content_hash = $1",
+            kind.table()
+        ))
+        .bind(hash)
+        .fetch_optional(&state.db)
+        .await?;
+        if let Some(id) = existing {
+            return Ok(Some((kind, id)));
+        }
+    }
+    Ok(None)
+}
+
+/// 入库一条鱼：解码图片 → 哈希去重 → 写入 ImageStore → 插入记录
+pub async fn insert_library_fish(
+    state: &AppState,
+    kind: FishKind,
+    fish: NewLibraryFish,
+) -> Result<InsertOutcome, ApiError> {
+    validate_library_fields(fish.difficulty_level, fish.weight, &fish.metadata)?;
+
+    let decoded = decode_image_data(&fish.image_data)?;
+    if decoded.bytes.is_empty() {
+        return Err(ApiError::BadRequest("Invalid image_data".to_string()));
+    }
+    let hash = content_hash(&decoded.bytes);
+
+    if let Some((kind, id)) = find_by_hash(state, &hash).await? {
+        return Ok(InsertOutcome::Duplicate { kind, id });
+    }
+
+    let fish_id = Uuid::new_v4();
+    let stored_image_data = state
+        .image_store
+        .prepare_fish_image_data(kind.as_str(), fish_id, &fish.image_data)
+        .await?;
+
+    let inserted: Option<LibraryFish> = sqlx::query_as(&format!(
+        r#"
+        INSERT INTO {} (
+            id, image_data, difficulty_level, metadata, weight, is_active,
+            content_hash, source_drawing_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (content_hash) DO NOTHING
+        RETURNING *
+        "#,
+        kind.table()
+    ))
+    .bind(fish_id)
+    .bind(&stored_image_data)
+    .bind(fish.difficulty_level)
+    .bind(&fish.metadata)
+    .bind(fish.weight)
+    .bind(fish.is_active)
+    .bind(&hash)
+    .bind(fish.source_drawing_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match inserted {
+        Some(row) => {
+            tracing::info!("Library fish {} added to {}", row.id, kind.table());
+            Ok(InsertOutcome::Inserted(row))
+        }
+        // 并发导入同一张图：以先入库者为准
+        None => {
+            let (kind, id) = find_by_hash(state, &hash)
+                .await?
+                .ok_or(ApiError::Internal("Library insert failed".to_string()))?;
+            Ok(InsertOutcome::Duplicate { kind, id })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_is_sha256_hex() {
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(content_hash(b"fish"), content_hash(b"fish2"));
+    }
+
+    #[test]
+    fn validate_library_fields_rejects_bad_values() {
+        let meta = serde_json::json!({});
+        assert!(validate_library_fields(1, 1, &meta).is_ok());
+        assert!(validate_library_fields(0, 1, &meta).is_err());
+        assert!(validate_library_fields(1, 0, &meta).is_err());
+        assert!(validate_library_fields(1, 1, &serde_json::json!([])).is_err());
+    }
+}
@@ -24,6 +24,17 @@ pub trait ImageStore: Send + Sync {
         db: &PgPool,
         drawing_id: Uuid,
     ) -> Result<ImageBytes, ApiError>;
+
+    /// 单人模式鱼库图片（`human_fish` / `ai_fish`），`kind` 为 `human` 或 `ai`
+    async fn prepare_fish_image_data(
+        &self,
+        kind: &str,
+        fish_id: Uuid,
+        image_data: &str,
+    ) -> Result<String, ApiError>;
+
+    /// 读取已落库的 image_data（data URL 或存储标记）
+    async fn read_image_data(&self, image_data: &str) -> Result<ImageBytes, ApiError>;
 }
 
 pub struct DbDataUrlImageStore;
@@ -171,6 +182,19 @@ impl ImageStore for DbDataUrlImageStore {
 
         decode_image_data(&image_data)
     }
+
+    async fn prepare_fish_image_data(
+        &self,
+        _kind: &str,
+        _fish_id: Uuid,
+        image_data: &str,
+    ) -> Result<String, ApiError> {
+        Ok(image_data.to_string())
+    }
+
+    async fn read_image_data(&self, image_data: &str) -> Result<ImageBytes, ApiError> {
+        decode_image_data(image_data)
+    }
 }
 
 #[async_trait::async_trait]
@@ -209,7 +233,29 @@ impl ImageStore for OpendalS3ImageStore {
             return Err(ApiError::NotFound("Drawing not found".to_string()));
         };
 
-        if let Some((content_type, key)) = parse_od_s3_marker(&image_data) {
+        self.read_image_data(&image_data).await
+    }
+
+    async fn prepare_fish_image_data(
+        &self,
+        kind: &str,
+        fish_id: Uuid,
+        image_data: &str,
+    ) -> Result<String, ApiError> {
+        let decoded = decode_image_data(image_data)?;
+        let ext = ext_from_content_type(decoded.content_type)?;
+        let key = format!("fish/{}/{}.{}", kind, fish_id, ext);
+
+        self.op.write(&key, decoded.bytes).await.map_err(|e| {
+            tracing::error!("Failed to write storage object {}: {}", key, e);
+            ApiError::Internal("Storage write failed".to_string())
+        })?;
+
+        Ok(format!("od:s3|{}|{}", decoded.content_type, key))
+    }
+
+    async fn read_image_data(&self, image_data: &str) -> Result<ImageBytes, ApiError> {
+        if let Some((content_type, key)) = parse_od_s3_marker(image_data) {
             let buf = self.op.read(key).await.map_err(|e| {
                 tracing::error!("Failed to read storage object {}: {}", key, e);
                 ApiError::Internal("Storage read failed".to_string())
@@ -220,10 +266,19 @@ impl ImageStore for OpendalS3ImageStore {
             });
         }
 
-        decode_image_data(&image_data)
+        decode_image_data(image_data)
     }
 }
 
+/// 将原始图片字节编码为 data URL（用于在不同存储键之间转存）
+pub(crate) fn encode_data_url(img: &ImageBytes) -> String {
+    format!(
+        "data:{};base64,{}",
+        img.content_type,
+        base64::engine::general_purpose::STANDARD.encode(&img.bytes)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,6 +312,17 @@ mod tests {
         assert_eq!(decoded.bytes, png_bytes);
     }
 
+    #[test]
+    fn encode_data_url_round_trips() {
+        let img = ImageBytes {
+            content_type: "image/webp",
+            bytes: b"webp".to_vec(),
+        };
+        let decoded = decode_image_data(&encode_data_url(&img)).unwrap();
+        assert_eq!(decoded.content_type, "image/webp");
+        assert_eq!(decoded.bytes, b"webp");
+    }
+
     #[test]
     fn parse_s3_marker() {
         let image_data = "od:s3|image/png|drawings/abc.png";
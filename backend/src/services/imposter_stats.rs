@@ -0,0 +1,190 @@
+//! AI 鱼（卧底）欺骗效果统计
+//!
+//! 每条 AI drawing 记录存活时长、跨轮得票（`drawings.votes_received`，不随每轮投票清零，
+//! 撤票时扣回，即各轮净得票之和）和结局（被淘汰 / 活到终局 / 仍在场），并按来源聚合：
+//! 预置鱼 id、生成任务、提示词关键词。欺骗分是平滑后的终局存活率，预置鱼出鱼可以按它加权挑选
+//! （`PRESET_FISH_SELECTION=deceptive`），出鱼路径只读 Redis 里的缓存，见 `cached_preset_scores`。
+
+use deadpool_redis::redis;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::services::{ApiError, AppState};
+
+const PRESET_SCORES_CACHE_KEY: &str = "imposter_stats:preset_scores";
+
+/// 出场存活秒数：淘汰时刻 / 房间结束时刻 / 现在
+pub const SURVIVAL_SECONDS_SQL: &str =
+    "EXTRACT(EPOCH FROM COALESCE(d.eliminated_at, r.ended_at, NOW()) - d.created_at)::float8";
+
+pub const OUTCOME_SQL: &str = "CASE WHEN d.is_eliminated THEN 'eliminated' \
+     WHEN r.status = 'gameover' THEN 'survived' ELSE 'alive' END";
+
+/// 欺骗分的平滑先验：相当于预先记 1 次存活、1 次淘汰，避免新鱼一局定生死
+const PRIOR_SURVIVED: f64 = 1.0;
+const PRIOR_FINISHED: f64 = 2.0;
+
+/// 加权挑选时对欺骗分取幂，拉开高分与低分鱼的出场概率
+const SELECTION_SHARPNESS: i32 = 3;
+
+/// 聚合维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsGroup {
+    Preset,
+    Task,
+    Keyword,
+}
+
+impl StatsGroup {
+    fn key_sql(self) -> &'static str {
+        match self {
+            StatsGroup::Preset => "d.preset_fish_id::text",
+            StatsGroup::Task => "t.id::text",
+            StatsGroup::Keyword => "t.keyword",
+        }
+    }
+}
+
+/// 按来源聚合的效果统计
+#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStats {
+    pub source_id: String,
+    pub spawned: i64,
+    pub eliminated: i64,
+    /// 活到终局
+    pub survived: i64,
+    pub avg_survival_seconds: f64,
+    pub avg_votes: f64,
+    #[sqlx(skip)]
+    pub deception_score: f64,
+}
+
+/// 平滑后的终局存活率（仍在场的不计）
+pub fn deception_score(survived: i64, eliminated: i64) -> f64 {
+    (survived as f64 + PRIOR_SURVIVED) / ((survived + eliminated) as f64 + PRIOR_FINISHED)
+}
+
+/// 按来源聚合，欺骗分从高到低
+pub async fn summarize(db: &sqlx::PgPool, group: StatsGroup) -> Result<Vec<SourceStats>, ApiError> {
+    let key = group.key_sql();
+    let mut rows: Vec<SourceStats> = sqlx::query_as(&format!(
+        r#"
+        SELECT
+            {key} AS source_id,
+            COUNT(*) AS spawned,
+            COUNT(*) FILTER (WHERE d.is_eliminated) AS eliminated,
+            COUNT(*) FILTER (WHERE NOT d.is_eliminated AND r.status = 'gameover') AS survived,
+            COALESCE(AVG({survival}), 0)::float8 AS avg_survival_seconds,
+            COALESCE(AVG(d.votes_received), 0)::float8 AS avg_votes
+        FROM drawings d
+        JOIN rooms r ON r.id = d.room_id
+        LEFT JOIN ai_tasks t ON t.drawing_id = d.id
+        WHERE d.is_ai = TRUE AND {key} IS NOT NULL
+        GROUP BY 1
+        "#,
+        key = key,
+        survival = SURVIVAL_SECONDS_SQL,
+    ))
+    .fetch_all(db)
+    .await?;
+
+    for row in &mut rows {
+        row.deception_score = deception_score(row.survived, row.eliminated);
+    }
+    rows.sort_by(|a, b| b.deception_score.total_cmp(&a.deception_score));
+    Ok(rows)
+}
+
+/// 各预置鱼的欺骗分
+pub async fn preset_scores(db: &sqlx::PgPool) -> Result<HashMap<u8, f64>, ApiError> {
+    Ok(summarize(db, StatsGroup::Preset)
+        .await?
+        .into_iter()
+        .filter_map(|s| {
+            s.source_id
+                .parse::<u8>()
+                .ok()
+                .map(|id| (id, s.deception_score))
+        })
+        .collect())
+}
+
+/// 带缓存的 `preset_scores`：聚合要扫全部 AI drawing，不能每次出鱼都跑一遍。
+/// 命中直接用，未命中重算后写回并按 `PRESET_SCORES_CACHE_SECONDS` 过期；Redis 不可用时直接查库
+pub async fn cached_preset_scores(state: &AppState) -> Result<HashMap<u8, f64>, ApiError> {
+    let mut conn = match state.redis.get().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!("[ImposterStats] Redis unavailable, skipping cache: {}", e);
+            return preset_scores(&state.db).await;
+        }
+    };
+
+    let cached: Option<String> = redis::cmd("GET")
+        .arg(PRESET_SCORES_CACHE_KEY)
+        .query_async(&mut conn)
+        .await
+        .unwrap_or(None);
+    if let Some(scores) = cached.and_then(|json| serde_json::from_str(&json).ok()) {
+        return Ok(scores);
+    }
+
+    let scores = preset_scores(&state.db).await?;
+    if let Ok(json) = serde_json::to_string(&scores) {
+        let _: Result<(), _> = redis::cmd("SET")
+            .arg(PRESET_SCORES_CACHE_KEY)
+            .arg(json)
+            .arg("EX")
+            .arg(state.config.preset_scores_cache_seconds.max(1))
+            .query_async(&mut conn)
+            .await;
+    }
+    Ok(scores)
+}
+
+/// 按欺骗分加权随机挑一条预置鱼，没有数据的鱼按先验分参与
+pub fn pick_deceptive<R: Rng>(ids: &[u8], scores: &HashMap<u8, f64>, rng: &mut R) -> Option<u8> {
+    let prior = deception_score(0, 0);
+    let weights = ids.iter().map(|id| {
+        scores
+            .get(id)
+            .copied()
+            .unwrap_or(prior)
+            .powi(SELECTION_SHARPNESS)
+    });
+    let index = WeightedIndex::new(weights).ok()?;
+    ids.get(index.sample(rng)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn score_is_smoothed_survival_rate() {
+        assert_eq!(deception_score(0, 0), 0.5);
+        assert!(deception_score(1, 0) > deception_score(0, 0));
+        assert!(deception_score(9, 1) > deception_score(1, 0));
+        assert!(deception_score(0, 5) < 0.2);
+    }
+
+    #[test]
+    fn selection_favours_deceptive_fish() {
+        let ids = [0u8, 1, 2];
+        let scores = HashMap::from([(0u8, 0.9), (1u8, 0.1)]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut picks = HashMap::new();
+        for _ in 0..1000 {
+            *picks
+                .entry(pick_deceptive(&ids, &scores, &mut rng).unwrap())
+                .or_insert(0) += 1;
+        }
+        assert!(picks[&0] > picks[&2]);
+        assert!(picks[&2] > picks.get(&1).copied().unwrap_or(0));
+        assert_eq!(pick_deceptive(&[], &scores, &mut rng), None);
+    }
+}
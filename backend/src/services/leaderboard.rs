@@ -0,0 +1,318 @@
+//! 单人模式计分与排行榜
+//!
+//! 分数在提交时计算并写入 `single_player_runs.score`；排行榜用 Redis 有序集合存储，
+//! 每个用户在每个榜单只保留最好成绩（`ZADD GT`）。Redis 丢数据时可从数据库重建。
+//! 周榜、每日挑战榜的过期时间按所属周/日期计算（`EXPIREAT`），重建时不会延长已过期或将过期的旧榜。
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
+use deadpool_redis::redis::{self, AsyncCommands};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::SinglePlayerRun;
+use crate::services::{ApiError, AppState};
+
+const KEY_PREFIX: &str = "leaderboard:sp";
+/// 周榜从该周周一起保留两周，方便查询上一周
+const WEEKLY_TTL_SECONDS: i64 = 14 * 24 * 3600;
+/// 每日挑战榜从当天起保留一个月
+const DAILY_TTL_SECONDS: i64 = 31 * 24 * 3600;
+
+/// 每个已找到的目标得分（乘以关卡号）
+const SCORE_PER_TARGET: i32 = 100;
+/// 胜利奖励（乘以关卡号）
+const VICTORY_BONUS: i32 = 500;
+/// 胜利时每剩余一秒的得分
+const SCORE_PER_SECOND_LEFT: i32 = 10;
+/// 每次误抓扣分
+const MISTAKE_PENALTY: i32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Board {
+    All,
+    Weekly,
+    Level,
+    Theme,
+    Daily,
+    Endless,
+}
+
+/// 计算单局得分：找到的目标 × 关卡 + 胜利奖励 + 剩余时间 − 误抓，最低 0
+pub fn compute_score(
+    level: i32,
+    status: &str,
+    targets_found: i32,
+    mistakes: i32,
+    seconds_left: i64,
+) -> i32 {
+    let level = level.max(1);
+    let mut score = targets_found.max(0) * SCORE_PER_TARGET * level;
+    if status == "victory" {
+        score += VICTORY_BONUS * level;
+        score += seconds_left.clamp(0, i32::MAX as i64) as i32 * SCORE_PER_SECOND_LEFT;
+    }
+    score -= mistakes.max(0) * MISTAKE_PENALTY;
+    score.max(0)
+}
+
+/// ISO 周标识，例如 `2026-W07`
+pub fn week_id(at: DateTime<Utc>) -> String {
+    let iso = at.iso_week();
+    format!("{}-W{:02}", iso.year(), iso.week())
+}
+
+pub fn all_time_key() -> String {
+    format!("{}:all", KEY_PREFIX)
+}
+
+pub fn weekly_key(week: &str) -> String {
+    format!("{}:week:{}", KEY_PREFIX, week)
+}
+
+/// 关卡榜；主题专属关卡与通用关卡分开排名
+pub fn level_key(level: i32, theme_id: Option<Uuid>) -> String {
+    match theme_id {
+        Some(theme_id) => format!("{}:level:{}:theme:{}", KEY_PREFIX, level, theme_id),
+        None => format!("{}:level:{}", KEY_PREFIX, level),
+    }
+}
+
+pub fn theme_key(theme_id: Uuid) -> String {
+    format!("{}:theme:{}", KEY_PREFIX, theme_id)
+}
+
+pub fn daily_key(date: NaiveDate) -> String {
+    format!("{}:daily:{}", KEY_PREFIX, date)
+}
+
+pub fn endless_key() -> String {
+    format!("{}:endless", KEY_PREFIX)
+}
+
+/// 周榜过期的时间点（Unix 秒）：该 ISO 周周一 0 点起 `WEEKLY_TTL_SECONDS`
+fn weekly_expire_at(at: DateTime<Utc>) -> i64 {
+    let iso = at.iso_week();
+    let monday = NaiveDate::from_isoywd_opt(iso.year(), iso.week(), Weekday::Mon)
+        .unwrap_or_else(|| at.date_naive());
+    day_start(monday) + WEEKLY_TTL_SECONDS
+}
+
+fn day_start(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|t| t.and_utc().timestamp())
+        .unwrap_or_default()
+}
+
+/// 一局结束后应写入的全部榜单 key 及其过期时间点（Unix 秒）；每日挑战、无尽模式各自单独成榜
+fn keys_for_run(run: &SinglePlayerRun, submitted_at: DateTime<Utc>) -> Vec<(String, Option<i64>)> {
+    if let Some(date) = run.challenge_date {
+        return vec![(daily_key(date), Some(day_start(date) + DAILY_TTL_SECONDS))];
+    }
+    if run.mode == "endless" {
+        return vec![(endless_key(), None)];
+    }
+
+    let mut keys = vec![
+        (all_time_key(), None),
+        (
+            weekly_key(&week_id(submitted_at)),
+            Some(weekly_expire_at(submitted_at)),
+        ),
+        (level_key(run.level, run.theme_id), None),
+    ];
+    if let Some(theme_id) = run.theme_id {
+        keys.push((theme_key(theme_id), None));
+    }
+    keys
+}
+
+/// 把一局成绩写入各榜单（仅保留更高分；被标记作弊的对局跳过）
+pub async fn record_run(state: &AppState, run: &SinglePlayerRun) -> Result<(), ApiError> {
+    if run.flagged {
+        return Ok(());
+    }
+    let (Some(user_id), Some(score), Some(submitted_at)) =
+        (run.user_id, run.score, run.submitted_at)
+    else {
+        return Ok(());
+    };
+
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+
+    let now = Utc::now().timestamp();
+    let mut pipe = redis::pipe();
+    for (key, expire_at) in keys_for_run(run, submitted_at) {
+        // 已过保留期的旧榜不再写入（重建时会遇到）
+        if expire_at.is_some_and(|at| at <= now) {
+            continue;
+        }
+        pipe.cmd("ZADD")
+            .arg(&key)
+            .arg("GT")
+            .arg(score)
+            .arg(user_id.to_string())
+            .ignore();
+        if let Some(at) = expire_at {
+            pipe.expire_at(&key, at).ignore();
+        }
+    }
+    pipe.query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    Ok(())
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub score: i32,
+}
+
+/// 读取榜单前 `limit` 名
+pub async fn top(
+    state: &AppState,
+    key: &str,
+    limit: i64,
+) -> Result<Vec<LeaderboardEntry>, ApiError> {
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+
+    let rows: Vec<(String, f64)> = conn
+        .zrevrange_withscores(key, 0, (limit - 1) as isize)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(member, score)| Some((Uuid::parse_str(&member).ok()?, score)))
+        .enumerate()
+        .map(|(i, (user_id, score))| LeaderboardEntry {
+            rank: i as i64 + 1,
+            user_id,
+            score: score as i32,
+        })
+        .collect())
+}
+
+/// 查询某用户在榜单中的名次与分数
+pub async fn rank_of(
+    state: &AppState,
+    key: &str,
+    user_id: Uuid,
+) -> Result<Option<LeaderboardEntry>, ApiError> {
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+
+    let member = user_id.to_string();
+    let rank: Option<i64> = conn
+        .zrevrank(key, &member)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    let score: Option<f64> = conn
+        .zscore(key, &member)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+
+    Ok(match (rank, score) {
+        (Some(rank), Some(score)) => Some(LeaderboardEntry {
+            rank: rank + 1,
+            user_id,
+            score: score as i32,
+        }),
+        _ => None,
+    })
+}
+
+/// 启动时若总榜不存在（Redis 被清空），从数据库重建全部榜单
+pub async fn ensure_leaderboards(state: Arc<AppState>) {
+    let exists: Result<bool, _> = match state.redis.get().await {
+        Ok(mut conn) => conn.exists(all_time_key()).await,
+        Err(e) => {
+            tracing::warn!("[Leaderboard] redis unavailable, skip rebuild: {}", e);
+            return;
+        }
+    };
+    if matches!(exists, Ok(true)) {
+        return;
+    }
+
+    let runs: Vec<SinglePlayerRun> = match sqlx::query_as(
+        "SELECT * FROM single_player_runs WHERE user_id IS NOT NULL AND score IS NOT NULL AND flagged = FALSE",
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(runs) => runs,
+        Err(e) => {
+            tracing::warn!("[Leaderboard] rebuild query failed: {}", e);
+            return;
+        }
+    };
+
+    let mut restored = 0usize;
+    for run in &runs {
+        match record_run(&state, run).await {
+            Ok(()) => restored += 1,
+            Err(e) => {
+                tracing::warn!("[Leaderboard] rebuild aborted: {:?}", e);
+                return;
+            }
+        }
+    }
+    tracing::info!("[Leaderboard] rebuilt from {} runs", restored);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn score_rewards_victory_and_time_left() {
+        assert_eq!(compute_score(1, "victory", 3, 0, 20), 300 + 500 + 200);
+        assert_eq!(compute_score(2, "victory", 3, 1, 0), 600 + 1000 - 50);
+        assert_eq!(compute_score(2, "expired", 2, 0, 30), 400);
+    }
+
+    #[test]
+    fn score_never_negative() {
+        assert_eq!(compute_score(1, "defeat", 0, 3, 0), 0);
+    }
+
+    #[test]
+    fn weekly_boards_expire_relative_to_their_week() {
+        // 2026-02-12 是周四，所在周的周一为 2026-02-09
+        let thursday = Utc.with_ymd_and_hms(2026, 2, 12, 18, 0, 0).unwrap();
+        let monday = Utc.with_ymd_and_hms(2026, 2, 9, 0, 0, 0).unwrap();
+        assert_eq!(
+            weekly_expire_at(thursday),
+            monday.timestamp() + WEEKLY_TTL_SECONDS
+        );
+        assert_eq!(weekly_expire_at(monday), weekly_expire_at(thursday));
+        assert_eq!(
+            level_key(2, Some(Uuid::nil())),
+            "leaderboard:sp:level:2:theme:00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(level_key(2, None), "leaderboard:sp:level:2");
+    }
+
+    #[test]
+    fn week_id_uses_iso_week() {
+        // 2027-01-01 属于 2026 年第 53 周
+        let at = Utc.with_ymd_and_hms(2027, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(week_id(at), "2026-W53");
+    }
+}
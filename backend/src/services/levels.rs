@@ -0,0 +1,187 @@
+//! 单人模式关卡：按主题解析关卡定义，并校验鱼库容量
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::SinglePlayerLevel;
+use crate::services::ApiError;
+
+/// 某难度等级下可用（`is_active`）的鱼数量
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryCapacity {
+    pub human_available: i64,
+    pub ai_available: i64,
+}
+
+impl LibraryCapacity {
+    /// 鱼库是否足以开一局；允许重复时只要求每类至少一条
+    pub fn check(
+        &self,
+        level: &SinglePlayerLevel,
+        allow_duplicates: bool,
+    ) -> Result<(), &'static str> {
+        let human_needed = level.human_count() as i64;
+        let ai_needed = level.ai_count as i64;
+
+        if human_needed > 0 && self.human_available == 0 {
+            return Err("Not enough human fish library data");
+        }
+        if ai_needed > 0 && self.ai_available == 0 {
+            return Err("Not enough ai fish library data");
+        }
+        if !allow_duplicates
+            && (self.human_available < human_needed || self.ai_available < ai_needed)
+        {
+            return Err("Not enough fish library data");
+        }
+        Ok(())
+    }
+}
+
+/// 解析关卡：主题专属关卡优先，其次通用关卡
+pub async fn resolve_level(
+    conn: &mut PgConnection,
+    level: i32,
+    theme_id: Option<Uuid>,
+) -> Result<Option<SinglePlayerLevel>, ApiError> {
+    let row: Option<SinglePlayerLevel> = sqlx::query_as(
+        r#"
+        SELECT * FROM single_player_levels
+        WHERE level = $1 AND is_active = TRUE AND (theme_id = $2 OR theme_id IS NULL)
+        ORDER BY theme_id NULLS LAST
+        LIMIT 1
+        "#,
+    )
+    .bind(level)
+    .bind(theme_id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(row)
+}
+
+/// 列出某主题下可玩的关卡（同一关卡号主题专属覆盖通用）
+pub async fn list_levels(
+    conn: &mut PgConnection,
+    theme_id: Option<Uuid>,
+) -> Result<Vec<SinglePlayerLevel>, ApiError> {
+    let rows: Vec<SinglePlayerLevel> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT ON (level) * FROM single_player_levels
+        WHERE is_active = TRUE AND (theme_id = $1 OR theme_id IS NULL)
+        ORDER BY level, theme_id NULLS LAST
+        "#,
+    )
+    .bind(theme_id)
+    .fetch_all(conn)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn library_capacity(
+    conn: &mut PgConnection,
+    difficulty_level: i32,
+) -> Result<LibraryCapacity, ApiError> {
+    let (human_available, ai_available): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM human_fish WHERE is_active = TRUE AND difficulty_level = $1),
+            (SELECT COUNT(*) FROM ai_fish WHERE is_active = TRUE AND difficulty_level = $1)
+        "#,
+    )
+    .bind(difficulty_level)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(LibraryCapacity {
+        human_available,
+        ai_available,
+    })
+}
+
+/// 关卡参数合法性校验
+pub fn validate_level(level: &SinglePlayerLevel) -> Result<(), ApiError> {
+    let bad = |msg: &str| Err(ApiError::BadRequest(msg.to_string()));
+
+    if level.level < 1 {
+        return bad("level must be >= 1");
+    }
+    if level.total_fish < 1 {
+        return bad("totalFish must be >= 1");
+    }
+    if level.ai_count < 1 || level.ai_count > level.total_fish {
+        return bad("aiCount must be between 1 and totalFish");
+    }
+    if level.target_total < 1 || level.target_total > level.ai_count {
+        return bad("targetTotal must be between 1 and aiCount");
+    }
+    if level.seconds < 1 {
+        return bad("seconds must be >= 1");
+    }
+    if level.max_mistakes < 1 {
+        return bad("maxMistakes must be >= 1");
+    }
+    if level.difficulty_level < 1 {
+        return bad("difficultyLevel must be >= 1");
+    }
+    if level.time_bonus_seconds < 0 || level.mistake_penalty_seconds < 0 {
+        return bad("timeBonusSeconds and mistakePenaltySeconds must be >= 0");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn level(total_fish: i32, ai_count: i32, target_total: i32) -> SinglePlayerLevel {
+        SinglePlayerLevel {
+            id: Uuid::new_v4(),
+            level: 1,
+            theme_id: None,
+            total_fish,
+            ai_count,
+            seconds: 60,
+            max_mistakes: 3,
+            target_total,
+            difficulty_level: 1,
+            time_bonus_seconds: 0,
+            mistake_penalty_seconds: 0,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn capacity_requires_full_set_without_duplicates() {
+        let lvl = level(10, 3, 3);
+        let cap = LibraryCapacity {
+            human_available: 5,
+            ai_available: 3,
+        };
+        assert!(cap.check(&lvl, true).is_ok());
+        assert_eq!(cap.check(&lvl, false), Err("Not enough fish library data"));
+    }
+
+    #[test]
+    fn capacity_requires_at_least_one_of_each_kind() {
+        let lvl = level(10, 3, 3);
+        let cap = LibraryCapacity {
+            human_available: 0,
+            ai_available: 3,
+        };
+        assert_eq!(
+            cap.check(&lvl, true),
+            Err("Not enough human fish library data")
+        );
+    }
+
+    #[test]
+    fn validate_level_rejects_unwinnable_targets() {
+        assert!(validate_level(&level(10, 3, 3)).is_ok());
+        assert!(validate_level(&level(10, 3, 4)).is_err());
+        assert!(validate_level(&level(3, 4, 1)).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+//! 拟态：参考本房间最近的人类画作生成 AI 鱼
+//!
+//! 开启 `AI_MIMICRY_ENABLED` 后，人类提交画作时为房间准备一条拟态任务：取最近
+//! `AI_MIMICRY_REFERENCES` 幅人类画作，图片地址放进生成请求的 `style_references`，
+//! 从图片里提取的主色放进 `style_palette`，参考的画作 id 记在 `ai_tasks.style_reference_ids`。
+//! 拟态任务带 `room_id`，结果进入房间队列，出鱼时优先于主题共享池。
+//! 玩家写的名字和描述未经审核，不拼进付费生成的提示词，提示词只追加固定的拟态要求。
+
+use deadpool_redis::redis::AsyncCommands;
+use image::{imageops::FilterType, RgbaImage};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{Room, StyleReference, Theme};
+use crate::services::image_store::ImageBytes;
+use crate::services::{ai_fish_buffer, n8n_jobs, ApiError, AppState};
+
+/// 提取主色前先缩到这个最长边，大图不必逐像素统计
+const THUMBNAIL_SIZE: u32 = 128;
+/// 从参考画作提取的调色板大小
+const PALETTE_SIZE: usize = 5;
+
+/// 拟态任务的参考画作与其主色（普通主题池任务为空）
+#[derive(Debug, Clone, Default)]
+pub struct MimicStyle {
+    pub references: Vec<StyleReference>,
+    pub palette: Vec<String>,
+}
+
+/// 在原提示词后追加拟态要求（不含玩家文本）
+pub fn mimic_prompt(prompt: &str, references: &[StyleReference]) -> String {
+    if references.is_empty() {
+        return prompt.to_string();
+    }
+    format!(
+        "{} It must blend in with {} amateur player doodles: match their line quality, coloring and level of skill.",
+        prompt,
+        references.len()
+    )
+}
+
+/// 统计不透明、非近白像素的主色（每通道量化到 16 级），按出现次数取前 `k` 个
+pub fn dominant_colors(images: &[RgbaImage], k: usize) -> Vec<String> {
+    let mut counts: HashMap<[u8; 3], u32> = HashMap::new();
+    for img in images {
+        for px in img.pixels() {
+            let [r, g, b, a] = px.0;
+            if a < 128 || (r > 235 && g > 235 && b > 235) {
+                continue;
+            }
+            *counts.entry([r >> 4, g >> 4, b >> 4]).or_insert(0) += 1;
+        }
+    }
+
+    let mut buckets: Vec<([u8; 3], u32)> = counts.into_iter().collect();
+    buckets.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    buckets
+        .into_iter()
+        .take(k)
+        .map(|([r, g, b], _)| format!("#{:02X}{:02X}{:02X}", r * 17, g * 17, b * 17))
+        .collect()
+}
+
+/// 参考画作的调色板；解码失败的参考跳过。解码和缩放是 CPU 密集的，须在 `spawn_blocking` 里调用
+pub fn reference_palette(images: &[ImageBytes]) -> Vec<String> {
+    let thumbnails: Vec<RgbaImage> = images
+        .iter()
+        .filter_map(|img| image::load_from_memory(&img.bytes).ok())
+        .map(|img| {
+            img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+                .to_rgba8()
+        })
+        .collect();
+    dominant_colors(&thumbnails, PALETTE_SIZE)
+}
+
+/// 取房间最近的人类画作作为参考，并在阻塞线程池里提取主色
+async fn load_references(
+    state: &AppState,
+    room_id: Uuid,
+    limit: i64,
+) -> Result<MimicStyle, ApiError> {
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM drawings
+        WHERE room_id = $1 AND is_ai = FALSE AND is_hidden = FALSE
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(room_id)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut references = Vec::with_capacity(ids.len());
+    let mut images = Vec::with_capacity(ids.len());
+    for drawing_id in ids {
+        match state
+            .image_store
+            .get_drawing_image(&state.db, drawing_id)
+            .await
+        {
+            Ok(image) => images.push(image),
+            Err(e) => {
+                tracing::warn!("[Mimicry] skip reference {}: {:?}", drawing_id, e);
+                continue;
+            }
+        }
+        references.push(StyleReference {
+            drawing_id,
+            image_url: format!(
+                "{}/api/drawings/{}/image",
+                state.config.callback_base_url, drawing_id
+            ),
+        });
+    }
+
+    let palette = tokio::task::spawn_blocking(move || reference_palette(&images))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Palette task failed: {}", e)))?;
+    Ok(MimicStyle {
+        references,
+        palette,
+    })
+}
+
+/// 房间是否还能新建拟态任务：没有在生成中的拟态任务，且主题当天预算未用完
+async fn mimic_slot_available(
+    conn: &mut sqlx::PgConnection,
+    state: &AppState,
+    room: &Room,
+    theme: &Theme,
+) -> Result<bool, ApiError> {
+    let (in_flight, used_today): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE room_id = $1 AND status IN ('pending', 'dispatched')),
+            COUNT(*) FILTER (WHERE theme_id = $2 AND created_at >= date_trunc('day', NOW()))
+        FROM ai_tasks
+        WHERE room_id = $1 OR theme_id = $2
+        "#,
+    )
+    .bind(room.id)
+    .bind(theme.id)
+    .fetch_one(conn)
+    .await?;
+    let budget = state.config.ai_fish_buffer_daily_budget;
+    Ok(in_flight == 0 && (budget <= 0 || used_today < budget))
+}
+
+/// 房间没有在生成或待出场的拟态鱼时新建一条拟态任务，返回是否新建
+pub async fn ensure_room_mimic(
+    state: &Arc<AppState>,
+    room: &Room,
+    theme: &Theme,
+) -> Result<bool, ApiError> {
+    if !state.config.ai_generation_enabled || !state.config.ai_mimicry_enabled {
+        return Ok(false);
+    }
+    if theme.max_imposters > 0 && room.ai_count >= theme.max_imposters {
+        return Ok(false);
+    }
+
+    let queued: i64 = {
+        let mut conn = state
+            .redis
+            .get()
+            .await
+            .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+        conn.llen(format!("room:{}:ai_fish_queue", room.id))
+            .await
+            .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?
+    };
+    if queued > 0 {
+        return Ok(false);
+    }
+
+    // 先不加锁粗查一次，再在锁外读参考图、提色，持锁事务里只做复查和插入
+    {
+        let mut conn = state.db.acquire().await?;
+        if !mimic_slot_available(&mut conn, state, room, theme).await? {
+            return Ok(false);
+        }
+    }
+    let style = load_references(state, room.id, state.config.ai_mimicry_references).await?;
+    if style.references.is_empty() {
+        return Ok(false);
+    }
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("ai_mimic:{}", room.id))
+        .execute(&mut *tx)
+        .await?;
+    if !mimic_slot_available(&mut tx, state, room, theme).await? {
+        return Ok(false);
+    }
+
+    let request = ai_fish_buffer::insert_task(&mut tx, state, theme, Some(room.id), style).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "[Mimicry] task {} for room {} with {} references",
+        request.task_id,
+        room.room_code,
+        request.style_references.len()
+    );
+    tokio::spawn(n8n_jobs::dispatch(state.clone(), request));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn dominant_colors_skip_background() {
+        let mut img = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        for x in 0..10 {
+            for y in 0..6 {
+                img.put_pixel(x, y, Rgba([0xE0, 0x30, 0x30, 255]));
+            }
+            img.put_pixel(x, 9, Rgba([0x10, 0x10, 0xF0, 255]));
+        }
+        img.put_pixel(0, 8, Rgba([0, 255, 0, 0]));
+
+        let colors = dominant_colors(&[img], 5);
+        assert_eq!(colors, vec!["#EE3333".to_string(), "#1111FF".to_string()]);
+    }
+
+    #[test]
+    fn mimic_prompt_only_counts_references() {
+        let refs = vec![
+            StyleReference {
+                drawing_id: Uuid::nil(),
+                image_url: "http://backend:3001/api/drawings/a/image".to_string(),
+            },
+            StyleReference {
+                drawing_id: Uuid::nil(),
+                image_url: "http://backend:3001/api/drawings/b/image".to_string(),
+            },
+        ];
+        let prompt = mimic_prompt("A doodle of a crab.", &refs);
+        assert!(prompt.starts_with("A doodle of a crab. "));
+        assert!(prompt.contains("2 amateur player doodles"));
+        assert_eq!(mimic_prompt("x", &[]), "x");
+    }
+}
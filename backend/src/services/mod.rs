@@ -1,9 +1,30 @@
+pub mod ai_fish_buffer;
+pub mod ai_generator;
+pub mod ai_prompt;
+pub mod anti_cheat;
 pub mod auth;
+pub mod author_names;
+pub mod content_filter;
+pub mod daily_challenge;
+pub mod endless;
+pub mod fish_calibration;
+pub mod fish_library;
 pub mod game_logic;
 pub mod image_store;
+pub mod imposter_stats;
+pub mod leaderboard;
+pub mod levels;
+pub mod mimicry;
 pub mod n8n_client;
+pub mod n8n_jobs;
+pub mod n8n_signature;
 pub mod preset_fish;
+pub mod procedural_fish;
+pub mod rate_limit;
+pub mod redis_script;
 pub mod room_manager;
+pub mod run_sweeper;
+pub mod spawn_policy;
 
 use axum::{
     http::StatusCode,
@@ -13,29 +34,21 @@ use axum::{
 use deadpool_redis::{redis::AsyncCommands, Pool as RedisPool};
 use rand::{seq::SliceRandom, thread_rng};
 use sqlx::PgPool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::models::{Drawing, Room, Theme, TriggerN8nRequest, TriggerN8nTheme};
+use crate::models::{Drawing, Room};
+use ai_generator::{build_ai_generator, AiGenerator};
+use author_names::AuthorCorpus;
+use content_filter::ContentFilter;
 use image_store::{build_image_store, ImageStore};
 
-pub use n8n_client::*;
 pub use preset_fish::*;
 
-/// 随机作者名列表（避免 AI 相关字眼）
-const RANDOM_AUTHORS: &[&str] = &[
-    "小明", "阿强", "花花", "大毛", "翠花", "老王", "小李", "阿珍", "铁柱", "建国", "美丽", "胖虎",
-    "小新", "大雄", "静香", "小红", "阿华", "小刚", "丽丽", "小芳",
-];
-
-/// 获取随机作者名
-fn get_random_author() -> &'static str {
-    RANDOM_AUTHORS
-        .choose(&mut thread_rng())
-        .unwrap_or(&"匿名画家")
-}
+/// 挑 AI 作者名时参考的最近人类作者数
+const RECENT_HUMAN_AUTHORS: usize = 20;
 
 /// 应用共享状态
 pub struct AppState {
@@ -43,72 +56,26 @@ pub struct AppState {
     pub redis: RedisPool,
     pub config: Config,
     pub image_store: Arc<dyn ImageStore>,
+    pub ai_generator: Arc<dyn AiGenerator>,
+    pub content_filter: ContentFilter,
 }
 
 impl AppState {
     pub fn new(db: PgPool, redis: RedisPool, config: Config) -> Result<Self, ApiError> {
         let image_store = build_image_store(&config)?;
+        let ai_generator = build_ai_generator(&config)?;
+        let content_filter = ContentFilter::from_config(&config)?;
 
         Ok(Self {
             db,
             redis,
             config,
             image_store,
+            ai_generator,
+            content_filter,
         })
     }
 
-    /// 触发 AI 生成
-    pub async fn trigger_ai_generation(&self, room_id: Uuid, theme: &Theme) {
-        // 检查是否启用 AI 生成
-        if !self.config.ai_generation_enabled {
-            tracing::info!("AI generation is disabled, skipping for room {}", room_id);
-            return;
-        }
-
-        let task_id = Uuid::new_v4();
-
-        // 创建任务记录
-        if let Err(e) =
-            sqlx::query("INSERT INTO ai_tasks (id, room_id, status) VALUES ($1, $2, 'pending')")
-                .bind(task_id)
-                .bind(room_id)
-                .execute(&self.db)
-                .await
-        {
-            tracing::error!("Failed to create AI task: {}", e);
-            return;
-        }
-
-        // 构造请求
-        let palette: Vec<String> =
-            serde_json::from_value(theme.palette.clone()).unwrap_or_default();
-        let keywords: Vec<String> =
-            serde_json::from_value(theme.ai_keywords.clone()).unwrap_or_default();
-
-        let callback_url = format!("{}/api/n8n/callback", self.config.callback_base_url);
-
-        let request = TriggerN8nRequest {
-            room_id,
-            task_id,
-            theme: TriggerN8nTheme {
-                palette,
-                keywords,
-                prompt_style: theme.ai_prompt_style.clone(),
-            },
-            callback_url,
-        };
-
-        // 异步触发 n8n
-        let webhook_url = self.config.n8n_webhook_url.clone();
-        tokio::spawn(async move {
-            if let Err(e) = trigger_n8n(&webhook_url, &request).await {
-                tracing::error!("Failed to trigger n8n: {}", e);
-            }
-        });
-
-        tracing::info!("AI generation triggered for room {}", room_id);
-    }
-
     /// 淘汰绘画
     pub async fn eliminate_drawing(
         &self,
@@ -196,6 +163,38 @@ impl AppState {
         }
     }
 
+    /// 推入主题共享池
+    pub async fn push_theme_ai_fish(&self, theme_id: Uuid, fish_data: &serde_json::Value) {
+        let key = ai_fish_buffer::theme_pool_key(theme_id);
+        match self.redis.get().await {
+            Ok(mut conn) => {
+                let data = serde_json::to_string(fish_data).unwrap_or_default();
+                let _: Result<(), _> = conn.rpush(&key, data).await;
+            }
+            Err(e) => {
+                tracing::error!("Redis push_theme_ai_fish error: {}", e);
+            }
+        }
+    }
+
+    /// 从主题共享池取出一条
+    pub async fn pop_theme_ai_fish(&self, theme_id: Uuid) -> Option<serde_json::Value> {
+        let key = ai_fish_buffer::theme_pool_key(theme_id);
+        match self.redis.get().await {
+            Ok(mut conn) => {
+                let result: Result<Option<String>, _> = conn.lpop(&key, None).await;
+                match result {
+                    Ok(Some(data)) => serde_json::from_str(&data).ok(),
+                    _ => None,
+                }
+            }
+            Err(e) => {
+                tracing::error!("Redis pop_theme_ai_fish error: {}", e);
+                None
+            }
+        }
+    }
+
     /// 从队列取出一条 n8n 生成的 AI 鱼
     pub async fn pop_ai_fish_from_queue(&self, room_id: Uuid) -> Option<serde_json::Value> {
         let key = format!("room:{}:ai_fish_queue", room_id);
@@ -214,10 +213,44 @@ impl AppState {
         }
     }
 
+    // ============ AI 鱼作者名 ============
+
+    /// 为房间里新出场的 AI 鱼挑一个作者名（参考本房间人类作者名，且不与已有作者重名）
+    async fn pick_ai_author(&self, room_id: Uuid) -> String {
+        let corpus: Option<serde_json::Value> = sqlx::query_scalar(
+            "SELECT t.author_corpus FROM rooms r JOIN themes t ON t.id = r.theme_id WHERE r.id = $1",
+        )
+        .bind(room_id)
+        .fetch_optional(&self.db)
+        .await
+        .ok()
+        .flatten();
+        let corpus = AuthorCorpus::from_theme_value(&corpus.unwrap_or_default());
+
+        let authors: Vec<(String, bool)> = sqlx::query_as(
+            "SELECT author_name, is_ai FROM drawings WHERE room_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(room_id)
+        .fetch_all(&self.db)
+        .await
+        .unwrap_or_default();
+
+        let human_names: Vec<String> = authors
+            .iter()
+            .filter(|(_, is_ai)| !is_ai)
+            .take(RECENT_HUMAN_AUTHORS)
+            .map(|(name, _)| name.clone())
+            .collect();
+        let used: HashSet<String> = authors.into_iter().map(|(name, _)| name).collect();
+
+        corpus.pick(&human_names, &used, &mut thread_rng())
+    }
+
     // ============ 预置 AI 鱼生成 ============
 
     /// 从预置池生成 AI 鱼（返回 Drawing，需要后续广播）
-    /// 优先选择全局使用次数最少的鱼，确保均匀分布
+    /// 默认优先选择全局使用次数最少的鱼，确保均匀分布；
+    /// `PRESET_FISH_SELECTION=deceptive` 时按欺骗分加权挑选
     pub async fn spawn_preset_ai_fish(&self, room_id: Uuid) -> Option<Drawing> {
         // 1. 获取所有预置鱼的全局使用次数
         let usage_counts = self.get_fish_usage_counts().await;
@@ -239,10 +272,27 @@ impl AppState {
             return None;
         }
 
-        // 4. 从使用次数最少的鱼中随机选择一条
-        let fish_id = {
-            let mut rng = thread_rng();
-            *least_used.choose(&mut rng)?
+        // 4. 按欺骗分加权挑选（统计不可用时退回），否则从使用次数最少的鱼中随机选择一条
+        let deceptive = if self.config.preset_fish_selection == "deceptive" {
+            match imposter_stats::cached_preset_scores(self).await {
+                Ok(scores) => {
+                    let ids: Vec<u8> = (0..fish_count).collect();
+                    imposter_stats::pick_deceptive(&ids, &scores, &mut thread_rng())
+                }
+                Err(e) => {
+                    tracing::warn!("Preset deception scores unavailable: {:?}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let fish_id = match deceptive {
+            Some(id) => id,
+            None => {
+                let mut rng = thread_rng();
+                *least_used.choose(&mut rng)?
+            }
         };
 
         let fish = get_preset_fish(fish_id)?;
@@ -268,7 +318,7 @@ impl AppState {
             "Preset AI fish {} spawned for room {} (global usage: {})",
             fish_id,
             room_id,
-            min_count + 1
+            usage_counts.get(&fish_id).copied().unwrap_or(0) + 1
         );
         Some(drawing)
     }
@@ -296,16 +346,15 @@ impl AppState {
             .prepare_drawing_image_data(drawing_id, &image_data)
             .await?;
 
-        // 随机选择作者名
-        let author_name = get_random_author();
+        let author_name = self.pick_ai_author(room_id).await;
 
         let drawing: Drawing = sqlx::query_as(
             r#"
             INSERT INTO drawings (
                 id, room_id, is_ai, image_data, name, description, author_name,
-                position_x, position_y, velocity_x, velocity_y, flip_x
+                position_x, position_y, velocity_x, velocity_y, flip_x, preset_fish_id
             )
-            VALUES ($1, $2, TRUE, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, TRUE, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING *
             "#,
         )
@@ -320,15 +369,19 @@ impl AppState {
         .bind(velocity_x)
         .bind(velocity_y)
         .bind(flip_x)
+        .bind(fish.id as i16)
         .fetch_one(&self.db)
         .await?;
 
         Ok(drawing)
     }
 
-    /// 尝试从 n8n 队列生成 AI 鱼
-    pub async fn try_spawn_from_n8n_queue(&self, room_id: Uuid) -> Option<Drawing> {
-        let fish_data = self.pop_ai_fish_from_queue(room_id).await?;
+    /// 尝试从生成队列取 AI 鱼：先取房间遗留队列，再取主题共享池
+    pub async fn try_spawn_from_n8n_queue(&self, room_id: Uuid, theme_id: Uuid) -> Option<Drawing> {
+        let fish_data = match self.pop_ai_fish_from_queue(room_id).await {
+            Some(data) => data,
+            None => self.pop_theme_ai_fish(theme_id).await?,
+        };
         let task_id = fish_data.get("task_id")?.as_str()?;
         let task_id = Uuid::parse_str(task_id).ok()?;
 
@@ -369,8 +422,7 @@ impl AppState {
             .await
             .ok()?;
 
-        // 随机选择作者名
-        let author_name = get_random_author();
+        let author_name = self.pick_ai_author(room_id).await;
 
         let drawing: Drawing = sqlx::query_as(
             r#"
@@ -397,6 +449,13 @@ impl AppState {
         .await
         .ok()?;
 
+        // 记录生成任务产出的 drawing，便于按提示词统计效果
+        let _ = sqlx::query("UPDATE ai_tasks SET drawing_id = $1 WHERE id = $2")
+            .bind(drawing_id)
+            .bind(task_id)
+            .execute(&self.db)
+            .await;
+
         // 更新 room 的 ai_count 和 total_items
         let _ = sqlx::query(
             "UPDATE rooms SET ai_count = ai_count + 1, total_items = total_items + 1, updated_at = NOW() WHERE id = $1"
@@ -416,6 +475,8 @@ pub enum ApiError {
     NotFound(String),
     BadRequest(String),
     Unauthorized(String),
+    Conflict(String),
+    TooManyRequests(String),
     Internal(String),
 }
 
@@ -432,6 +493,8 @@ impl IntoResponse for ApiError {
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            ApiError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
 
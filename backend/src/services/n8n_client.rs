@@ -1,8 +1,11 @@
-use crate::models::TriggerN8nRequest;
+use crate::models::GenerationRequest;
 use anyhow::Result;
 
-/// 触发 n8n webhook
-pub async fn trigger_n8n(webhook_url: &str, request: &TriggerN8nRequest) -> Result<()> {
+/// 触发 n8n webhook，请求体就是生成请求本身（含回调地址与 nonce）
+pub async fn trigger_n8n(webhook_url: &str, request: &GenerationRequest) -> Result<()> {
+    if request.callback.is_none() {
+        anyhow::bail!("n8n request for task {} has no callback", request.task_id);
+    }
     let client = reqwest::Client::new();
 
     let response = client
@@ -0,0 +1,309 @@
+//! AI 生成任务队列（基于 `ai_tasks` 表）
+//!
+//! 状态流转：`pending`（等待派发）→ `dispatched`（已交给生成后端，等待回调）→ `completed`。
+//! 同步返回结果的后端（见 `ai_generator`）派发后直接完成。
+//! 派发失败、回调报错或超时未回调都会记一次失败：未超过重试上限则按指数退避回到
+//! `pending`，否则进入死信 `dead`，由管理员排查后手动重试。
+
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::GenerationRequest;
+use crate::services::ai_generator::{GeneratedFish, Generation};
+use crate::services::{ApiError, AppState};
+
+/// 每轮最多派发的任务数
+const DISPATCH_BATCH: i64 = 20;
+
+/// 重试策略
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 最多失败次数，达到后进入死信
+    pub max_attempts: i32,
+    pub base_delay_secs: u64,
+    pub max_delay_secs: u64,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_attempts: config.n8n_max_attempts,
+            base_delay_secs: config.n8n_retry_base_seconds,
+            max_delay_secs: config.n8n_retry_max_seconds,
+        }
+    }
+
+    /// 第 `failures` 次失败后的重试等待秒数；`None` 表示进入死信
+    pub fn next_delay(&self, failures: i32) -> Option<u64> {
+        if failures >= self.max_attempts {
+            return None;
+        }
+        let exp = (failures.max(1) - 1).min(32) as u32;
+        Some(
+            self.base_delay_secs
+                .saturating_mul(1u64 << exp)
+                .min(self.max_delay_secs),
+        )
+    }
+}
+
+/// 记一次失败：退避重试或进入死信（已完成/已死信的任务不受影响）
+pub async fn record_failure(
+    db: &PgPool,
+    policy: RetryPolicy,
+    task_id: Uuid,
+    error: &str,
+) -> Result<(), ApiError> {
+    let mut tx = db.begin().await?;
+
+    let retry_count: Option<i32> = sqlx::query_scalar(
+        "SELECT retry_count FROM ai_tasks WHERE id = $1 AND status IN ('pending', 'dispatched') FOR UPDATE",
+    )
+    .bind(task_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(retry_count) = retry_count else {
+        return Ok(());
+    };
+
+    let failures = retry_count + 1;
+    match policy.next_delay(failures) {
+        Some(delay) => {
+            sqlx::query(
+                r#"
+                UPDATE ai_tasks
+                SET status = 'pending', retry_count = $2, error_message = $3,
+                    dispatched_at = NULL, next_attempt_at = NOW() + make_interval(secs => $4)
+                WHERE id = $1
+                "#,
+            )
+            .bind(task_id)
+            .bind(failures)
+            .bind(error)
+            .bind(delay as f64)
+            .execute(&mut *tx)
+            .await?;
+            tracing::warn!(
+                "[N8nJobs] task {} failed ({}), retry #{} in {}s",
+                task_id,
+                error,
+                failures,
+                delay
+            );
+        }
+        None => {
+            sqlx::query(
+                r#"
+                UPDATE ai_tasks
+                SET status = 'dead', retry_count = $2, error_message = $3, dispatched_at = NULL
+                WHERE id = $1
+                "#,
+            )
+            .bind(task_id)
+            .bind(failures)
+            .bind(error)
+            .execute(&mut *tx)
+            .await?;
+            tracing::error!(
+                "[N8nJobs] task {} dead-lettered after {} failures: {}",
+                task_id,
+                failures,
+                error
+            );
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 任务完成：写入生成结果并推入主题池（旧的房间任务推入房间队列）；重复完成返回 `false`
+pub async fn complete_task(
+    state: &AppState,
+    task_id: Uuid,
+    fish: &GeneratedFish,
+) -> Result<bool, ApiError> {
+    let target: Option<(Option<Uuid>, Option<Uuid>)> = sqlx::query_as(
+        r#"
+        UPDATE ai_tasks
+        SET status = 'completed', image_data = $1, generated_name = $2,
+            generated_description = $3, completed_at = $4
+        WHERE id = $5 AND status != 'completed'
+        RETURNING room_id, theme_id
+        "#,
+    )
+    .bind(&fish.image_data)
+    .bind(&fish.name)
+    .bind(&fish.description)
+    .bind(Utc::now())
+    .bind(task_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let Some((room_id, theme_id)) = target else {
+        return Ok(false);
+    };
+    let fish_data = serde_json::json!({
+        "task_id": task_id
+    });
+    match (room_id, theme_id) {
+        (Some(room_id), _) => state.push_ai_fish_to_queue(room_id, &fish_data).await,
+        (None, Some(theme_id)) => state.push_theme_ai_fish(theme_id, &fish_data).await,
+        (None, None) => tracing::warn!("[N8nJobs] task {} has no room or theme", task_id),
+    }
+    Ok(true)
+}
+
+/// 交给生成后端；同步出图的直接完成，失败时记入重试
+pub async fn dispatch(state: Arc<AppState>, request: GenerationRequest) {
+    let task_id = request.task_id;
+    let result = match state.ai_generator.generate(&request).await {
+        Ok(Generation::Pending) => Ok(()),
+        Ok(Generation::Completed(fish)) => complete_task(&state, task_id, &fish).await.map(|_| ()),
+        Err(e) => {
+            let policy = RetryPolicy::from_config(&state.config);
+            let error = format!("Generation failed: {}", e);
+            record_failure(&state.db, policy, task_id, &error).await
+        }
+    };
+    if let Err(err) = result {
+        tracing::error!("[N8nJobs] dispatch of task {} failed: {:?}", task_id, err);
+    }
+}
+
+/// 后台任务：按 `N8N_JOB_POLL_INTERVAL_SECONDS` 周期处理超时与待重试任务，0 表示关闭
+pub async fn start_n8n_job_runner(state: Arc<AppState>) {
+    let interval_secs = state.config.n8n_job_poll_interval_seconds;
+    if interval_secs == 0 {
+        tracing::info!("[N8nJobs] runner disabled");
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    loop {
+        ticker.tick().await;
+        if let Err(err) = expire_timed_out(&state).await {
+            tracing::warn!("[N8nJobs] timeout check failed: {:?}", err);
+        }
+        if let Err(err) = dispatch_due(&state).await {
+            tracing::warn!("[N8nJobs] dispatch failed: {:?}", err);
+        }
+    }
+}
+
+/// 派发后超时仍未回调的任务记一次失败
+async fn expire_timed_out(state: &AppState) -> Result<(), ApiError> {
+    let policy = RetryPolicy::from_config(&state.config);
+    let timed_out: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM ai_tasks
+        WHERE status = 'dispatched'
+          AND dispatched_at < NOW() - make_interval(secs => $1)
+        "#,
+    )
+    .bind(state.config.n8n_task_timeout_seconds as f64)
+    .fetch_all(&state.db)
+    .await?;
+
+    for task_id in timed_out {
+        record_failure(&state.db, policy, task_id, "Callback timed out").await?;
+    }
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct ClaimedTask {
+    id: Uuid,
+    request_payload: Option<serde_json::Value>,
+}
+
+/// 认领到期的 `pending` 任务并重新派发
+async fn dispatch_due(state: &Arc<AppState>) -> Result<(), ApiError> {
+    let claimed: Vec<ClaimedTask> = sqlx::query_as(
+        r#"
+        UPDATE ai_tasks SET status = 'dispatched', dispatched_at = $2
+        WHERE id IN (
+            SELECT id FROM ai_tasks
+            WHERE status = 'pending' AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at ASC
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, request_payload
+        "#,
+    )
+    .bind(DISPATCH_BATCH)
+    .bind(Utc::now())
+    .fetch_all(&state.db)
+    .await?;
+
+    for task in claimed {
+        let request = task
+            .request_payload
+            .and_then(|p| serde_json::from_value::<GenerationRequest>(p).ok());
+        let Some(request) = request else {
+            // 旧数据没有保存请求体，无法重发
+            sqlx::query(
+                "UPDATE ai_tasks SET status = 'dead', error_message = 'Missing request payload' WHERE id = $1",
+            )
+            .bind(task.id)
+            .execute(&state.db)
+            .await?;
+            continue;
+        };
+
+        tokio::spawn(dispatch(state.clone(), request));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay_secs: 10,
+            max_delay_secs: 100,
+        };
+        assert_eq!(policy.next_delay(1), Some(10));
+        assert_eq!(policy.next_delay(2), Some(20));
+        assert_eq!(policy.next_delay(3), Some(40));
+        assert_eq!(policy.next_delay(5), Some(100));
+    }
+
+    #[test]
+    fn dead_letter_after_max_attempts() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay_secs: 10,
+            max_delay_secs: 600,
+        };
+        assert!(policy.next_delay(2).is_some());
+        assert_eq!(policy.next_delay(3), None);
+    }
+
+    #[test]
+    fn stored_payloads_keep_callback_fields_at_top_level() {
+        let payload = serde_json::json!({
+            "task_id": Uuid::nil(),
+            "theme": { "palette": [], "keywords": ["fish"], "prompt_style": "doodle" },
+            "callback_url": "http://backend:3001/api/n8n/callback",
+            "nonce": "abc",
+        });
+        let request: GenerationRequest = serde_json::from_value(payload.clone()).unwrap();
+        assert_eq!(
+            request.callback.as_ref().map(|c| c.nonce.as_str()),
+            Some("abc")
+        );
+        assert_eq!(
+            serde_json::to_value(&request).unwrap()["callback_url"],
+            payload["callback_url"]
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! n8n 回调签名
+//!
+//! n8n 用共享密钥对 `"{timestamp}.{原始请求体}"` 做 HMAC-SHA256，放在
+//! `X-Mimic-Timestamp` / `X-Mimic-Signature`（十六进制）请求头中。时间戳超出允许偏差的
+//! 回调直接拒绝，窗口内同一签名只接受一次，防止重放。
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const TIMESTAMP_HEADER: &str = "x-mimic-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-mimic-signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// 校验时间戳与签名，失败返回原因
+pub fn verify(
+    secret: &str,
+    timestamp: i64,
+    body: &[u8],
+    signature_hex: &str,
+    now: i64,
+    max_skew_seconds: i64,
+) -> Result<(), &'static str> {
+    if (now - timestamp).abs() > max_skew_seconds {
+        return Err("Callback timestamp out of range");
+    }
+    let signature = hex::decode(signature_hex.trim()).map_err(|_| "Invalid signature")?;
+    mac(secret, timestamp, body)
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid signature")
+}
+
+/// 每个任务的回调 nonce，随触发请求下发，回调必须原样带回
+pub fn new_nonce() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+        hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+    }
+
+    #[test]
+    fn signature_roundtrip() {
+        let body = br#"{"task_id":"x","status":"completed"}"#;
+        let sig = sign("secret", 1_700_000_000, body);
+        assert!(verify("secret", 1_700_000_000, body, &sig, 1_700_000_010, 300).is_ok());
+        assert!(verify("other", 1_700_000_000, body, &sig, 1_700_000_010, 300).is_err());
+        assert!(verify("secret", 1_700_000_000, b"{}", &sig, 1_700_000_010, 300).is_err());
+    }
+
+    #[test]
+    fn stale_timestamp_is_rejected() {
+        let body = b"{}";
+        let sig = sign("secret", 1_700_000_000, body);
+        assert_eq!(
+            verify("secret", 1_700_000_000, body, &sig, 1_700_000_301, 300),
+            Err("Callback timestamp out of range")
+        );
+    }
+}
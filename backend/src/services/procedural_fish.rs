@@ -0,0 +1,635 @@
+//! 程序化涂鸦鱼
+//!
+//! 不依赖任何外部服务，按主题的 `palette` / `ai_keywords` 画出儿童涂鸦风格的 PNG：
+//! 关键词决定造型（鱼、鲸、鲨鱼、章鱼、水母、螃蟹，其余按鱼处理），
+//! 颜色取自调色板，描边带手抖和错位填色。同一个种子总是得到同一张图和同一个名字。
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::f32::consts::PI;
+use std::io::Cursor;
+
+use crate::services::ApiError;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+/// 与 `ai_tasks.generated_name` / `generated_description` 列宽一致
+const NAME_MAX_CHARS: usize = 24;
+const DESCRIPTION_MAX_CHARS: usize = 60;
+
+const FALLBACK_COLOR: Rgba<u8> = Rgba([255, 140, 60, 255]);
+const INK: Rgba<u8> = Rgba([34, 34, 34, 255]);
+
+const NAME_PREFIXES: &[&str] = &[
+    "胖胖",
+    "小",
+    "圆滚滚",
+    "歪歪",
+    "迷糊",
+    "大眼",
+    "害羞",
+    "快乐",
+    "懒懒",
+    "呆呆",
+];
+const TRAITS: &[&str] = &[
+    "正在吐泡泡",
+    "喜欢绕着水草转圈",
+    "刚刚睡醒",
+    "总是游得歪歪扭扭",
+    "在找丢掉的贝壳",
+    "对谁都很友好",
+    "有点怕黑",
+    "梦想游到大海",
+];
+
+pub struct ProceduralFish {
+    pub png: Vec<u8>,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Species {
+    Fish,
+    Whale,
+    Shark,
+    Octopus,
+    Jellyfish,
+    Crab,
+}
+
+impl Species {
+    fn from_keyword(keyword: &str) -> Self {
+        let k = keyword.to_lowercase();
+        if k.contains("whale") || k.contains('鲸') {
+            Self::Whale
+        } else if k.contains("shark") || k.contains('鲨') {
+            Self::Shark
+        } else if k.contains("octopus") || k.contains("章鱼") {
+            Self::Octopus
+        } else if k.contains("jelly") || k.contains("水母") {
+            Self::Jellyfish
+        } else if k.contains("crab") || k.contains("螃蟹") {
+            Self::Crab
+        } else {
+            Self::Fish
+        }
+    }
+
+    fn noun(&self) -> &'static str {
+        match self {
+            Self::Fish => "鱼",
+            Self::Whale => "鲸",
+            Self::Shark => "鲨鱼",
+            Self::Octopus => "章鱼",
+            Self::Jellyfish => "水母",
+            Self::Crab => "螃蟹",
+        }
+    }
+}
+
+type Pt = (f32, f32);
+
+/// 一张涂鸦：填色区域（同时描边）、身体下层的线条（触手、腿）、
+/// 身体上层的细节线（鳞片、嘴）和实心圆点
+#[derive(Default)]
+struct Doodle {
+    shapes: Vec<(Vec<Pt>, Rgba<u8>)>,
+    lines: Vec<Vec<Pt>>,
+    details: Vec<Vec<Pt>>,
+    dots: Vec<(Pt, f32, Rgba<u8>)>,
+}
+
+/// 渲染一条涂鸦鱼
+pub fn render(
+    palette: &[String],
+    keywords: &[String],
+    seed: u64,
+) -> Result<ProceduralFish, ApiError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut colors: Vec<Rgba<u8>> = palette.iter().filter_map(|c| parse_hex_color(c)).collect();
+    if colors.is_empty() {
+        colors.push(FALLBACK_COLOR);
+    }
+    let keyword = keywords
+        .choose(&mut rng)
+        .map(String::as_str)
+        .unwrap_or("fish");
+    let species = Species::from_keyword(keyword);
+    let body = *colors.choose(&mut rng).unwrap_or(&FALLBACK_COLOR);
+    let accent = *colors.choose(&mut rng).unwrap_or(&body);
+
+    let doodle = match species {
+        Species::Fish => fish(&mut rng, body, accent),
+        Species::Whale => whale(&mut rng, body, accent),
+        Species::Shark => shark(&mut rng, body, accent),
+        Species::Octopus => octopus(&mut rng, body, accent),
+        Species::Jellyfish => jellyfish(&mut rng, body, accent),
+        Species::Crab => crab(&mut rng, body, accent),
+    };
+    let img = paint(&doodle, &mut rng);
+
+    let mut out = Cursor::new(Vec::new());
+    img.write_to(&mut out, ImageFormat::Png)
+        .map_err(|e| ApiError::Internal(format!("Image encode failed: {}", e)))?;
+
+    let (name, description) = describe(species, body, &mut rng);
+    Ok(ProceduralFish {
+        png: out.into_inner(),
+        name,
+        description,
+    })
+}
+
+fn describe(species: Species, color: Rgba<u8>, rng: &mut StdRng) -> (String, String) {
+    let color_name = color_name(color);
+    let prefix = NAME_PREFIXES.choose(rng).copied().unwrap_or("小");
+    let trait_text = TRAITS.choose(rng).copied().unwrap_or("正在吐泡泡");
+
+    let name: String = format!("{}{}{}", prefix, color_name, species.noun())
+        .chars()
+        .take(NAME_MAX_CHARS)
+        .collect();
+    let description: String = format!("一条{}色的{}，{}", color_name, species.noun(), trait_text)
+        .chars()
+        .take(DESCRIPTION_MAX_CHARS)
+        .collect();
+    (name, description)
+}
+
+fn parse_hex_color(s: &str) -> Option<Rgba<u8>> {
+    let hex = s.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let v = u32::from_str_radix(hex, 16).ok()?;
+    Some(Rgba([(v >> 16) as u8, (v >> 8) as u8, v as u8, 255]))
+}
+
+/// 按色相给颜色起个中文名
+fn color_name(c: Rgba<u8>) -> &'static str {
+    let (r, g, b) = (
+        c[0] as f32 / 255.0,
+        c[1] as f32 / 255.0,
+        c[2] as f32 / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if max < 0.2 {
+        return "黑";
+    }
+    if delta < 0.12 {
+        return if max > 0.85 { "白" } else { "灰" };
+    }
+
+    let hue = if max == r {
+        60.0 * (((g - b) / delta).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    match hue {
+        h if !(15.0..345.0).contains(&h) => {
+            if min > 0.55 {
+                "粉"
+            } else {
+                "红"
+            }
+        }
+        h if h < 40.0 => "橙",
+        h if h < 70.0 => "黄",
+        h if h < 160.0 => "绿",
+        h if h < 200.0 => "青",
+        h if h < 260.0 => "蓝",
+        h if h < 300.0 => "紫",
+        _ => "粉",
+    }
+}
+
+// ============ 造型 ============
+
+/// 带随机起伏的椭圆轮廓
+fn blob(rng: &mut StdRng, c: Pt, rx: f32, ry: f32, wobble: f32) -> Vec<Pt> {
+    let n = 48;
+    let phase: f32 = rng.gen_range(0.0..(2.0 * PI));
+    let waves = rng.gen_range(2..5) as f32;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / n as f32 * 2.0 * PI;
+            let k = 1.0 + wobble * (waves * t + phase).sin();
+            (c.0 + rx * k * t.cos(), c.1 + ry * k * t.sin())
+        })
+        .collect()
+}
+
+/// 从 `start` 出发朝 `angle` 方向的波浪线
+fn wavy(start: Pt, len: f32, angle: f32, amp: f32, waves: f32, phase: f32) -> Vec<Pt> {
+    let n = 24;
+    let (dx, dy) = (angle.cos(), angle.sin());
+    (0..=n)
+        .map(|i| {
+            let s = i as f32 / n as f32;
+            let off = amp * (s * waves * 2.0 * PI + phase).sin() * s;
+            (
+                start.0 + dx * len * s - dy * off,
+                start.1 + dy * len * s + dx * off,
+            )
+        })
+        .collect()
+}
+
+fn eye(doodle: &mut Doodle, at: Pt, r: f32) {
+    doodle.dots.push((at, r + 3.0, Rgba([255, 255, 255, 255])));
+    doodle.dots.push((at, r, INK));
+}
+
+fn fish(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let c = (180.0, 120.0);
+    let rx: f32 = rng.gen_range(70.0..95.0);
+    let ry: f32 = rng.gen_range(40.0..65.0);
+    let tail_len: f32 = rng.gen_range(45.0..70.0);
+    let tail_x = c.0 - rx + 10.0;
+
+    d.shapes.push((
+        vec![
+            (tail_x, c.1),
+            (tail_x - tail_len, c.1 - tail_len * 0.8),
+            (tail_x - tail_len * 0.7, c.1),
+            (tail_x - tail_len, c.1 + tail_len * 0.8),
+        ],
+        accent,
+    ));
+    d.shapes.push((blob(rng, c, rx, ry, 0.04), body));
+    // 背鳍
+    d.shapes.push((
+        vec![
+            (c.0 - rx * 0.3, c.1 - ry * 0.9),
+            (c.0 - rx * 0.1, c.1 - ry - 25.0),
+            (c.0 + rx * 0.25, c.1 - ry * 0.95),
+        ],
+        accent,
+    ));
+    // 鳞片
+    for i in 0..rng.gen_range(2..5) {
+        let x = c.0 - rx * 0.2 + i as f32 * 18.0;
+        d.details
+            .push(wavy((x, c.1 - 12.0), 24.0, PI / 2.0, 6.0, 0.5, 0.0));
+    }
+    d.details.push(vec![
+        (c.0 + rx * 0.75, c.1 + ry * 0.2),
+        (c.0 + rx * 0.88, c.1 + ry * 0.28),
+    ]);
+    eye(&mut d, (c.0 + rx * 0.55, c.1 - ry * 0.25), 6.0);
+    d
+}
+
+fn whale(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let c = (170.0, 135.0);
+    let rx: f32 = rng.gen_range(100.0..120.0);
+    let ry: f32 = rng.gen_range(50.0..65.0);
+    let tail_x = c.0 - rx + 12.0;
+
+    d.shapes.push((
+        vec![
+            (tail_x, c.1),
+            (tail_x - 40.0, c.1 - 45.0),
+            (tail_x - 20.0, c.1 - 10.0),
+            (tail_x - 45.0, c.1 + 5.0),
+        ],
+        accent,
+    ));
+    d.shapes.push((blob(rng, c, rx, ry, 0.03), body));
+    // 肚皮
+    d.shapes.push((
+        blob(rng, (c.0 + 15.0, c.1 + ry * 0.55), rx * 0.7, ry * 0.3, 0.02),
+        accent,
+    ));
+    // 喷水
+    let top = (c.0 + rx * 0.35, c.1 - ry - 4.0);
+    for angle in [-0.6f32, -0.15, 0.3] {
+        d.lines
+            .push(wavy(top, 40.0, -PI / 2.0 + angle, 4.0, 1.0, 0.0));
+    }
+    eye(&mut d, (c.0 + rx * 0.6, c.1 - ry * 0.1), 5.0);
+    d
+}
+
+fn shark(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let (cx, cy) = (170.0, 130.0);
+    let len: f32 = rng.gen_range(200.0..240.0);
+    let h: f32 = rng.gen_range(38.0..50.0);
+    let (x0, x1) = (cx - len / 2.0, cx + len / 2.0);
+
+    // 梭形身体：上下两条正弦弧
+    let n = 32;
+    let mut outline: Vec<Pt> = (0..=n)
+        .map(|i| {
+            let s = i as f32 / n as f32;
+            (x0 + len * s, cy - h * (s * PI).sin().max(0.0).powf(0.8))
+        })
+        .collect();
+    outline.extend((0..=n).rev().map(|i| {
+        let s = i as f32 / n as f32;
+        (
+            x0 + len * s,
+            cy + h * 0.8 * (s * PI).sin().max(0.0).powf(0.8),
+        )
+    }));
+
+    d.shapes.push((
+        vec![
+            (x0 + 8.0, cy),
+            (x0 - 35.0, cy - 55.0),
+            (x0 - 15.0, cy),
+            (x0 - 30.0, cy + 40.0),
+        ],
+        accent,
+    ));
+    d.shapes.push((outline, body));
+    d.shapes.push((
+        vec![
+            (cx - 30.0, cy - h * 0.9),
+            (cx - 5.0, cy - h - 45.0),
+            (cx + 25.0, cy - h * 0.95),
+        ],
+        body,
+    ));
+    // 鳃和牙
+    for i in 0..3 {
+        let x = cx + len * 0.22 + i as f32 * 8.0;
+        d.details.push(vec![(x, cy - 14.0), (x - 4.0, cy + 10.0)]);
+    }
+    let mouth_y = cy + h * 0.35;
+    let mouth: Vec<Pt> = (0..7)
+        .map(|i| {
+            let x = x1 - 45.0 + i as f32 * 6.0;
+            (x, if i % 2 == 0 { mouth_y } else { mouth_y + 6.0 })
+        })
+        .collect();
+    d.details.push(mouth);
+    eye(&mut d, (x1 - 40.0, cy - h * 0.35), 5.0);
+    d
+}
+
+fn octopus(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let c = (160.0, 95.0);
+    let r: f32 = rng.gen_range(55.0..70.0);
+
+    let legs = 6;
+    for i in 0..legs {
+        let s = i as f32 / (legs - 1) as f32;
+        let start = (c.0 - r * 0.8 + s * r * 1.6, c.1 + r * 0.6);
+        let angle = PI / 2.0 + (s - 0.5) * 2.2;
+        let phase = rng.gen_range(0.0..(2.0 * PI));
+        d.lines.push(wavy(
+            start,
+            rng.gen_range(70.0..95.0),
+            angle,
+            10.0,
+            1.5,
+            phase,
+        ));
+    }
+    d.shapes.push((blob(rng, c, r, r * 0.9, 0.05), body));
+    for _ in 0..4 {
+        let at = (
+            c.0 + rng.gen_range(-r * 0.5..r * 0.5),
+            c.1 - rng.gen_range(r * 0.2..r * 0.6),
+        );
+        d.dots.push((at, rng.gen_range(4.0..8.0), accent));
+    }
+    eye(&mut d, (c.0 - r * 0.3, c.1 + r * 0.1), 6.0);
+    eye(&mut d, (c.0 + r * 0.3, c.1 + r * 0.1), 6.0);
+    d
+}
+
+fn jellyfish(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let c = (160.0, 100.0);
+    let rx: f32 = rng.gen_range(70.0..90.0);
+    let ry: f32 = rng.gen_range(50.0..65.0);
+
+    // 伞盖：上半椭圆 + 波浪下沿
+    let n = 32;
+    let mut dome: Vec<Pt> = (0..=n)
+        .map(|i| {
+            let t = PI + i as f32 / n as f32 * PI;
+            (c.0 + rx * t.cos(), c.1 + ry * t.sin())
+        })
+        .collect();
+    dome.extend((0..=n).rev().map(|i| {
+        let s = i as f32 / n as f32;
+        (
+            c.0 - rx + 2.0 * rx * s,
+            c.1 + 8.0 * (s * 5.0 * PI).sin().abs(),
+        )
+    }));
+
+    for i in 0..5 {
+        let x = c.0 - rx * 0.7 + i as f32 * rx * 0.35;
+        let phase = rng.gen_range(0.0..(2.0 * PI));
+        d.lines.push(wavy(
+            (x, c.1 + 6.0),
+            rng.gen_range(70.0..110.0),
+            PI / 2.0,
+            8.0,
+            2.0,
+            phase,
+        ));
+    }
+    d.shapes.push((dome, body));
+    d.shapes.push((
+        blob(rng, (c.0, c.1 - ry * 0.6), rx * 0.35, ry * 0.18, 0.05),
+        accent,
+    ));
+    eye(&mut d, (c.0 - 20.0, c.1 - 12.0), 5.0);
+    eye(&mut d, (c.0 + 20.0, c.1 - 12.0), 5.0);
+    d
+}
+
+fn crab(rng: &mut StdRng, body: Rgba<u8>, accent: Rgba<u8>) -> Doodle {
+    let mut d = Doodle::default();
+    let c = (160.0, 140.0);
+    let rx: f32 = rng.gen_range(70.0..85.0);
+    let ry: f32 = rng.gen_range(38.0..48.0);
+
+    for side in [-1.0f32, 1.0] {
+        for i in 0..3 {
+            let start = (c.0 + side * rx * 0.7, c.1 + 5.0 + i as f32 * 12.0);
+            let angle = if side > 0.0 { 0.5 } else { PI - 0.5 } + i as f32 * 0.25 * side;
+            d.lines.push(wavy(start, 50.0, angle, 5.0, 0.5, 0.0));
+        }
+        // 钳子
+        let arm_start = (c.0 + side * rx * 0.6, c.1 - ry * 0.6);
+        let claw = (c.0 + side * (rx + 15.0), c.1 - ry - 40.0);
+        d.lines.push(vec![arm_start, claw]);
+        d.shapes.push((blob(rng, claw, 22.0, 16.0, 0.08), accent));
+        // 眼柄
+        let stalk_top = (c.0 + side * 18.0, c.1 - ry - 25.0);
+        d.lines
+            .push(vec![(c.0 + side * 15.0, c.1 - ry + 5.0), stalk_top]);
+        eye(&mut d, stalk_top, 5.0);
+    }
+    d.shapes.push((blob(rng, c, rx, ry, 0.04), body));
+    d.details
+        .push(wavy((c.0 - 20.0, c.1 + ry * 0.3), 40.0, 0.0, 5.0, 0.5, PI));
+    d
+}
+
+// ============ 绘制 ============
+
+fn paint(doodle: &Doodle, rng: &mut StdRng) -> RgbaImage {
+    let mut img = RgbaImage::new(WIDTH, HEIGHT);
+
+    // 开放线条先画，身体盖在上面，看起来像是从身体里长出来的
+    for line in &doodle.lines {
+        stroke(&mut img, line, false, rng);
+    }
+    for (poly, color) in &doodle.shapes {
+        // 填色略微错位，像马克笔没涂准
+        let (ox, oy) = (rng.gen_range(-4.0..4.0), rng.gen_range(-4.0..4.0));
+        let shifted: Vec<Pt> = poly.iter().map(|(x, y)| (x + ox, y + oy)).collect();
+        fill(&mut img, &shifted, *color);
+        stroke(&mut img, poly, true, rng);
+    }
+    for line in &doodle.details {
+        stroke(&mut img, line, false, rng);
+    }
+    for &(at, r, color) in &doodle.dots {
+        disc(&mut img, at, r, color);
+    }
+    img
+}
+
+/// 偶奇规则填充多边形
+fn fill(img: &mut RgbaImage, poly: &[Pt], color: Rgba<u8>) {
+    if poly.len() < 3 {
+        return;
+    }
+    let (mut min_y, mut max_y) = (f32::MAX, f32::MIN);
+    for &(_, y) in poly {
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    let y0 = min_y.floor().max(0.0) as u32;
+    let y1 = (max_y.ceil() as u32).min(HEIGHT - 1);
+
+    for y in y0..=y1 {
+        let fy = y as f32 + 0.5;
+        let mut xs: Vec<f32> = Vec::new();
+        for i in 0..poly.len() {
+            let (a, b) = (poly[i], poly[(i + 1) % poly.len()]);
+            if (a.1 <= fy) != (b.1 <= fy) {
+                xs.push(a.0 + (fy - a.1) / (b.1 - a.1) * (b.0 - a.0));
+            }
+        }
+        xs.sort_by(f32::total_cmp);
+        for pair in xs.chunks(2) {
+            if let [from, to] = pair {
+                let x0 = from.round().max(0.0) as u32;
+                let x1 = (to.round().max(0.0) as u32).min(WIDTH);
+                for x in x0..x1 {
+                    img.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+fn disc(img: &mut RgbaImage, c: Pt, r: f32, color: Rgba<u8>) {
+    let x0 = (c.0 - r).floor().max(0.0) as u32;
+    let y0 = (c.1 - r).floor().max(0.0) as u32;
+    let x1 = ((c.0 + r).ceil().max(0.0) as u32).min(WIDTH - 1);
+    let y1 = ((c.1 + r).ceil().max(0.0) as u32).min(HEIGHT - 1);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let (dx, dy) = (x as f32 - c.0, y as f32 - c.1);
+            if dx * dx + dy * dy <= r * r {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+/// 手抖描边：沿路径逐像素盖圆点，偏移量做随机游走，笔触粗细也随之变化
+fn stroke(img: &mut RgbaImage, path: &[Pt], closed: bool, rng: &mut StdRng) {
+    if path.len() < 2 {
+        return;
+    }
+    let mut jitter = (0.0f32, 0.0f32);
+    let mut width: f32 = rng.gen_range(2.5..3.5);
+
+    let segments = if closed { path.len() } else { path.len() - 1 };
+    for i in 0..segments {
+        let (a, b) = (path[i], path[(i + 1) % path.len()]);
+        let steps = ((b.0 - a.0).hypot(b.1 - a.1).ceil() as usize).max(1);
+        for s in 0..steps {
+            let t = s as f32 / steps as f32;
+            jitter.0 = (jitter.0 + rng.gen_range(-0.4..0.4)).clamp(-2.5, 2.5);
+            jitter.1 = (jitter.1 + rng.gen_range(-0.4..0.4)).clamp(-2.5, 2.5);
+            width = (width + rng.gen_range(-0.15..0.15)).clamp(2.0, 4.0);
+            let p = (
+                a.0 + (b.0 - a.0) * t + jitter.0,
+                a.1 + (b.1 - a.1) * t + jitter.1,
+            );
+            disc(img, p, width, INK);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> (Vec<String>, Vec<String>) {
+        (
+            vec!["#FF6B6B".to_string(), "#4ECDC4".to_string()],
+            ["fish", "whale", "shark", "octopus", "jellyfish", "crab"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn same_seed_same_fish() {
+        let (palette, keywords) = theme();
+        let a = render(&palette, &keywords, 42).unwrap();
+        let b = render(&palette, &keywords, 42).unwrap();
+        assert_eq!(a.png, b.png);
+        assert_eq!(a.name, b.name);
+        assert_ne!(a.png, render(&palette, &keywords, 43).unwrap().png);
+
+        let img = image::load_from_memory(&a.png).unwrap();
+        assert_eq!((img.width(), img.height()), (WIDTH, HEIGHT));
+    }
+
+    #[test]
+    fn every_species_renders_with_bounded_text() {
+        let (palette, keywords) = theme();
+        for keyword in &keywords {
+            let fish = render(&palette, std::slice::from_ref(keyword), 7).unwrap();
+            let species = Species::from_keyword(keyword);
+            assert!(fish.name.contains(species.noun()));
+            assert!(fish.name.chars().count() <= NAME_MAX_CHARS);
+            assert!(fish.description.chars().count() <= DESCRIPTION_MAX_CHARS);
+        }
+    }
+
+    #[test]
+    fn colors_are_named_by_hue() {
+        assert_eq!(parse_hex_color("#4ECDC4").map(color_name), Some("青"));
+        assert_eq!(parse_hex_color("#FF6B6B").map(color_name), Some("红"));
+        assert_eq!(parse_hex_color("#FFEAA7").map(color_name), Some("黄"));
+        assert_eq!(parse_hex_color("red"), None);
+    }
+}
@@ -0,0 +1,435 @@
+//! 限流：Redis 令牌桶
+//!
+//! 每个「动作 + 主体」一个桶，存在 Redis 哈希 `ratelimit:{action}:{subject}` 里（`tokens`、`ts`），
+//! 由 Lua 脚本原子地补充和扣减，多实例共享同一份额度。主体优先用登录用户 ID，否则用客户端 IP；
+//! REST 请求的 token → 用户对应关系在 Redis 里缓存 `TOKEN_SUBJECT_CACHE_SECONDS` 秒，限流不必每次查库。
+//!
+//! REST 接口通过 `RateLimitLayer`（tower 中间件）挂在具体路由上，超限返回 429 和 `Retry-After`；
+//! Socket.IO 的投票、评论在处理函数里调用 `check`，超限回 `vote:error` / `comment:error`。
+//! 各动作的额度见 `Config` 的 `RATE_LIMIT_*`；Redis 不可用时放行。
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use deadpool_redis::redis::AsyncCommands;
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::redis_script::LuaScript;
+use crate::services::{auth, ApiError, AppState};
+
+/// token → 用户的缓存时长；吊销的 token 最多再按用户计数这么久
+const TOKEN_SUBJECT_CACHE_SECONDS: u64 = 60;
+
+/// 参数：容量、每毫秒补充的令牌数、当前毫秒时间戳；返回 `{是否放行, 需等待的毫秒数}`
+static TOKEN_BUCKET_SCRIPT: LuaScript = LuaScript::new(
+    r#"
+        local capacity = tonumber(ARGV[1])
+        local rate = tonumber(ARGV[2])
+        local now = tonumber(ARGV[3])
+        local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+        local tokens = tonumber(bucket[1]) or capacity
+        local ts = tonumber(bucket[2]) or now
+        if now > ts then
+            tokens = math.min(capacity, tokens + (now - ts) * rate)
+            ts = now
+        end
+        local allowed = 0
+        local retry = 0
+        if tokens >= 1 then
+            tokens = tokens - 1
+            allowed = 1
+        else
+            retry = math.ceil((1 - tokens) / rate)
+        end
+        redis.call('HSET', KEYS[1], 'tokens', tokens, 'ts', ts)
+        redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate) + 1000)
+        return {allowed, retry}
+"#,
+);
+
+/// 单个动作的额度：最多攒 `burst` 个令牌，每分钟补充 `per_minute` 个
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    /// `burst` 为 0 表示不限流
+    pub fn is_disabled(&self) -> bool {
+        self.burst == 0
+    }
+
+    fn tokens_per_ms(&self) -> f64 {
+        self.per_minute as f64 / 60_000.0
+    }
+}
+
+/// 格式 `<burst>/<per_minute>`，如 `10/60`；`0/0` 关闭
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (burst, per_minute) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("expected <burst>/<per_minute>, got {:?}", s))?;
+        let limit = RateLimit {
+            burst: burst.trim().parse().map_err(|e| format!("burst: {}", e))?,
+            per_minute: per_minute
+                .trim()
+                .parse()
+                .map_err(|e| format!("per_minute: {}", e))?,
+        };
+        if limit.burst > 0 && limit.per_minute == 0 {
+            return Err("per_minute must be positive when burst is set".to_string());
+        }
+        Ok(limit)
+    }
+}
+
+/// 受限的动作，同一动作的 REST 与 Socket.IO 入口共用一个桶
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateAction {
+    /// `vote:cast` / `vote:retract` / `POST /drawings/:id/vote`
+    Vote,
+    /// `comment:add`
+    Comment,
+    /// `POST /rooms/:room_code/drawings`
+    DrawingSubmit,
+    /// `POST /auth/guest/login`
+    GuestLogin,
+    /// `POST /game/start` 及每日挑战、无尽模式的开局
+    GameStart,
+}
+
+impl RateAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateAction::Vote => "vote",
+            RateAction::Comment => "comment",
+            RateAction::DrawingSubmit => "drawing",
+            RateAction::GuestLogin => "guest_login",
+            RateAction::GameStart => "game_start",
+        }
+    }
+
+    fn limit(self, config: &Config) -> RateLimit {
+        match self {
+            RateAction::Vote => config.rate_limit_vote,
+            RateAction::Comment => config.rate_limit_comment,
+            RateAction::DrawingSubmit => config.rate_limit_drawing,
+            RateAction::GuestLogin => config.rate_limit_guest_login,
+            RateAction::GameStart => config.rate_limit_game_start,
+        }
+    }
+}
+
+/// 计数的主体
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    User(Uuid),
+    Ip(String),
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subject::User(id) => write!(f, "user:{}", id),
+            Subject::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+fn bucket_key(action: RateAction, subject: &Subject) -> String {
+    format!("ratelimit:{}:{}", action.as_str(), subject)
+}
+
+/// 超限，`retry_after_ms` 后才会有新令牌
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    pub retry_after_ms: u64,
+}
+
+impl RateLimited {
+    /// `Retry-After` 头用的秒数，至少 1
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_ms.div_ceil(1000).max(1)
+    }
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        let mut response =
+            ApiError::TooManyRequests("Too many requests".to_string()).into_response();
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(self.retry_after_secs()),
+        );
+        response
+    }
+}
+
+/// 扣一个令牌；超限返回 `RateLimited`
+pub async fn check(
+    state: &AppState,
+    action: RateAction,
+    subject: &Subject,
+) -> Result<(), RateLimited> {
+    let limit = action.limit(&state.config);
+    if !state.config.rate_limit_enabled || limit.is_disabled() {
+        return Ok(());
+    }
+
+    let mut conn = match state.redis.get().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            tracing::warn!(
+                "[RateLimit] Redis unavailable, allowing {}: {}",
+                action.as_str(),
+                e
+            );
+            return Ok(());
+        }
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let result: Result<(i64, i64), _> = TOKEN_BUCKET_SCRIPT
+        .prepare()
+        .key(bucket_key(action, subject))
+        .arg(limit.burst)
+        .arg(limit.tokens_per_ms())
+        .arg(now_ms)
+        .invoke(&mut conn)
+        .await;
+
+    match result {
+        Ok((1, _)) => Ok(()),
+        Ok((_, retry_after_ms)) => {
+            tracing::debug!("[RateLimit] {} limited for {}", action.as_str(), subject);
+            Err(RateLimited {
+                retry_after_ms: retry_after_ms.max(0) as u64,
+            })
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[RateLimit] script failed, allowing {}: {}",
+                action.as_str(),
+                e
+            );
+            Ok(())
+        }
+    }
+}
+
+/// 客户端 IP：信任反向代理时取 `X-Real-IP`，没有时取 `X-Forwarded-For` 的最后一跳，否则用连接地址
+///
+/// nginx 把 `X-Real-IP` 设为它看到的对端地址，并把对端追加到 `X-Forwarded-For` 末尾；
+/// 前面的地址都是客户端自己带来的，可以随意伪造，不能用来计数。
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trust_proxy: bool) -> String {
+    if trust_proxy {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let forwarded = header("x-real-ip")
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .or_else(|| {
+                header("x-forwarded-for")
+                    .and_then(|v| v.rsplit(',').next())
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            });
+        if let Some(ip) = forwarded {
+            return ip.to_string();
+        }
+    } else if headers.contains_key("x-real-ip") || headers.contains_key("x-forwarded-for") {
+        // 带代理头却没开信任：多半是部署在 nginx 后面忘了配置，所有人会共用代理的 IP 计数
+        static WARN_ONCE: std::sync::Once = std::sync::Once::new();
+        WARN_ONCE.call_once(|| {
+            tracing::warn!(
+                "[RateLimit] requests carry proxy headers but RATE_LIMIT_TRUST_PROXY is off; \
+                 every client behind the proxy shares one IP bucket"
+            );
+        });
+    }
+    peer.map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// REST 请求的主体：带有效 Bearer token 时按用户，否则按 IP
+async fn http_subject(state: &AppState, headers: &HeaderMap, peer: Option<SocketAddr>) -> Subject {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    if let Some(token) = token {
+        if let Some(user_id) = token_user(state, token).await {
+            return Subject::User(user_id);
+        }
+    }
+
+    Subject::Ip(client_ip(
+        headers,
+        peer,
+        state.config.rate_limit_trust_proxy,
+    ))
+}
+
+/// token 对应的用户，先查 Redis 缓存（无效 token 也缓存为空串）；key 里只放 token 的摘要
+async fn token_user(state: &AppState, token: &str) -> Option<Uuid> {
+    let key = format!(
+        "ratelimit:token:{}",
+        hex::encode(Sha256::digest(token.as_bytes()))
+    );
+    let mut conn = state.redis.get().await.ok();
+    if let Some(conn) = conn.as_mut() {
+        if let Ok(Some(cached)) = conn.get::<_, Option<String>>(&key).await {
+            return Uuid::parse_str(&cached).ok();
+        }
+    }
+
+    let user_id = auth::user_id_from_token(&state.db, token).await.ok();
+    if let Some(conn) = conn.as_mut() {
+        let value = user_id.map(|id| id.to_string()).unwrap_or_default();
+        let _: Result<(), _> = conn.set_ex(&key, value, TOKEN_SUBJECT_CACHE_SECONDS).await;
+    }
+    user_id
+}
+
+/// 给单个路由限流的 tower 中间件
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    state: Arc<AppState>,
+    action: RateAction,
+}
+
+impl RateLimitLayer {
+    pub fn new(state: &Arc<AppState>, action: RateAction) -> Self {
+        Self {
+            state: state.clone(),
+            action,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            state: self.state.clone(),
+            action: self.action,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    state: Arc<AppState>,
+    action: RateAction,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // 用已 ready 的那个实例处理本次请求
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        let action = self.action;
+        let headers = req.headers().clone();
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+
+        Box::pin(async move {
+            let subject = http_subject(&state, &headers, peer).await;
+            if let Err(limited) = check(&state, action, &subject).await {
+                return Ok(limited.into_response());
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!(
+            " 10 / 60 ".parse::<RateLimit>(),
+            Ok(RateLimit {
+                burst: 10,
+                per_minute: 60
+            })
+        );
+        assert!("0/0".parse::<RateLimit>().unwrap().is_disabled());
+        assert!("5/0".parse::<RateLimit>().is_err());
+        assert!("10".parse::<RateLimit>().is_err());
+        assert!("a/1".parse::<RateLimit>().is_err());
+    }
+
+    #[test]
+    fn client_ip_takes_last_proxy_hop_when_trusted() {
+        let peer: Option<SocketAddr> = Some("10.0.0.2:5000".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        // 客户端伪造的第一段不可信，取 nginx 追加的最后一跳
+        headers.insert("x-forwarded-for", "1.1.1.1, 203.0.113.7".parse().unwrap());
+
+        assert_eq!(client_ip(&headers, peer, true), "203.0.113.7");
+        assert_eq!(client_ip(&headers, peer, false), "10.0.0.2");
+        headers.insert("x-real-ip", "198.51.100.4".parse().unwrap());
+        assert_eq!(client_ip(&headers, peer, true), "198.51.100.4");
+        assert_eq!(client_ip(&HeaderMap::new(), None, true), "unknown");
+    }
+
+    #[test]
+    fn bucket_keys_and_retry_after() {
+        let user = Subject::User(Uuid::nil());
+        assert_eq!(
+            bucket_key(RateAction::Vote, &user),
+            "ratelimit:vote:user:00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(
+            bucket_key(RateAction::GuestLogin, &Subject::Ip("1.2.3.4".to_string())),
+            "ratelimit:guest_login:ip:1.2.3.4"
+        );
+        assert_eq!(RateLimited { retry_after_ms: 1 }.retry_after_secs(), 1);
+        assert_eq!(
+            RateLimited {
+                retry_after_ms: 2500
+            }
+            .retry_after_secs(),
+            3
+        );
+        assert_eq!(RateLimited { retry_after_ms: 0 }.retry_after_secs(), 1);
+    }
+}
@@ -0,0 +1,91 @@
+//! Redis Lua 脚本
+//!
+//! deadpool-redis 依赖的 redis 没有开启 `script` feature，这里补一个最小实现：按脚本的 SHA1 走 `EVALSHA`，
+//! 服务端还没缓存该脚本（`NOSCRIPT`，如 Redis 刚重启）时退回 `EVAL`，顺带把脚本载入缓存。
+
+use deadpool_redis::redis::{self, aio::ConnectionLike, ErrorKind, FromRedisValue, ToRedisArgs};
+use sha1::{Digest, Sha1};
+use std::sync::OnceLock;
+
+pub struct LuaScript {
+    code: &'static str,
+    hash: OnceLock<String>,
+}
+
+impl LuaScript {
+    pub const fn new(code: &'static str) -> Self {
+        Self {
+            code,
+            hash: OnceLock::new(),
+        }
+    }
+
+    fn hash(&self) -> &str {
+        self.hash
+            .get_or_init(|| hex::encode(Sha1::digest(self.code.as_bytes())))
+    }
+
+    pub fn prepare(&self) -> ScriptCall<'_> {
+        ScriptCall {
+            script: self,
+            keys: Vec::new(),
+            args: Vec::new(),
+        }
+    }
+}
+
+/// 一次脚本调用的 `KEYS` 与 `ARGV`
+pub struct ScriptCall<'a> {
+    script: &'a LuaScript,
+    keys: Vec<Vec<u8>>,
+    args: Vec<Vec<u8>>,
+}
+
+impl ScriptCall<'_> {
+    pub fn key<T: ToRedisArgs>(mut self, key: T) -> Self {
+        self.keys.extend(key.to_redis_args());
+        self
+    }
+
+    pub fn arg<T: ToRedisArgs>(mut self, arg: T) -> Self {
+        self.args.extend(arg.to_redis_args());
+        self
+    }
+
+    pub async fn invoke<T: FromRedisValue, C: ConnectionLike>(
+        &self,
+        conn: &mut C,
+    ) -> redis::RedisResult<T> {
+        match self
+            .cmd("EVALSHA", self.script.hash())
+            .query_async(conn)
+            .await
+        {
+            Err(e) if e.kind() == ErrorKind::NoScriptError => {
+                self.cmd("EVAL", self.script.code).query_async(conn).await
+            }
+            result => result,
+        }
+    }
+
+    fn cmd(&self, name: &str, script: &str) -> redis::Cmd {
+        let mut cmd = redis::cmd(name);
+        cmd.arg(script)
+            .arg(self.keys.len())
+            .arg(&self.keys)
+            .arg(&self.args);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_matches_redis_script_sha() {
+        // redis-cli SCRIPT LOAD "return 1"
+        let script = LuaScript::new("return 1");
+        assert_eq!(script.hash(), "e0e1f9fabfc9d4800c877a703b823ac0578ff8db");
+    }
+}
@@ -0,0 +1,288 @@
+//! 单人对局收尾
+//!
+//! 对局原本只在 `catch_fish` / `submit_game` 被调用时才惰性过期，放弃的对局会一直停在
+//! `active`。后台任务定期把超时的对局结算为最终状态（不写排行榜，排行榜只收提交过的对局）；
+//! 开局前按用户/客户端 IP 限制同时进行中的对局数，防止刷 `start_game` 枚举鱼库。
+
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::SinglePlayerRun;
+use crate::services::leaderboard::compute_score;
+use crate::services::{ApiError, AppState};
+
+/// 每轮最多结算的对局数
+const SWEEP_BATCH: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: &'static str,
+    pub finished_at: DateTime<Utc>,
+    pub score: i32,
+}
+
+/// 对局的最终结果；仍在进行中返回 `None`
+pub fn outcome(run: &SinglePlayerRun, now: DateTime<Utc>) -> Option<Outcome> {
+    let endless = run.mode == "endless";
+    let status = if !endless && run.targets_found >= run.target_total {
+        "victory"
+    } else if run.mistakes >= run.max_mistakes {
+        "defeat"
+    } else if now >= run.ends_at {
+        "expired"
+    } else {
+        match run.status.as_str() {
+            "active" => return None,
+            "victory" => "victory",
+            "defeat" => "defeat",
+            _ => "expired",
+        }
+    };
+
+    let finished_at = run.finished_at.unwrap_or_else(|| now.min(run.ends_at));
+    // 无尽模式按结束时所在波次的难度计分
+    let level = if endless {
+        run.difficulty_level
+    } else {
+        run.level
+    };
+    let score = run.score.unwrap_or_else(|| {
+        compute_score(
+            level,
+            status,
+            run.targets_found,
+            run.mistakes,
+            (run.ends_at - finished_at).num_seconds(),
+        )
+    });
+
+    Some(Outcome {
+        status,
+        finished_at,
+        score,
+    })
+}
+
+/// 后台任务：按 `SINGLE_PLAYER_SWEEP_INTERVAL_SECONDS` 周期结算超时对局，0 表示关闭
+pub async fn start_run_sweeper(state: Arc<AppState>) {
+    let interval_secs = state.config.single_player_sweep_interval_seconds;
+    if interval_secs == 0 {
+        tracing::info!("[RunSweeper] disabled");
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    loop {
+        ticker.tick().await;
+        match sweep_expired_runs(&state).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("[RunSweeper] expired {} abandoned runs", n),
+            Err(err) => tracing::warn!("[RunSweeper] sweep failed: {:?}", err),
+        }
+    }
+}
+
+/// 结算一批超时仍为 `active` 的对局，返回处理数量
+pub async fn sweep_expired_runs(state: &AppState) -> Result<usize, ApiError> {
+    let mut tx = state.db.begin().await?;
+
+    let runs: Vec<SinglePlayerRun> = sqlx::query_as(
+        r#"
+        SELECT * FROM single_player_runs
+        WHERE status = 'active' AND ends_at <= NOW()
+        ORDER BY ends_at ASC
+        LIMIT $1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(SWEEP_BATCH)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let now = Utc::now();
+    let mut swept = 0;
+    for run in &runs {
+        let Some(result) = outcome(run, now) else {
+            continue;
+        };
+        sqlx::query(
+            r#"
+            UPDATE single_player_runs
+            SET status = $2, finished_at = COALESCE(finished_at, $3), score = COALESCE(score, $4)
+            WHERE id = $1
+            "#,
+        )
+        .bind(run.id)
+        .bind(result.status)
+        .bind(result.finished_at)
+        .bind(result.score)
+        .execute(&mut *tx)
+        .await?;
+        swept += 1;
+    }
+
+    tx.commit().await?;
+    Ok(swept)
+}
+
+/// 对局归属：登录用户按用户 ID，匿名玩家按服务端签发的 owner token（随开局响应下发，
+/// 下次开局带回）。客户端自报的 `session_id` 和代理后面共用的 IP 都不能区分玩家，不用来计数；
+/// 丢掉 token 重新领一个的刷局行为由开局接口的频率限制兜底
+#[derive(Debug, Clone)]
+pub struct RunOwner {
+    pub user_id: Option<Uuid>,
+    /// 仅匿名对局有
+    pub owner_token: Option<String>,
+}
+
+impl RunOwner {
+    /// 匿名玩家带回的 token 格式不对（或没带）时签发新的
+    pub fn new(user_id: Option<Uuid>, requested_token: Option<&str>) -> Self {
+        let owner_token = match (user_id, requested_token) {
+            (Some(_), _) => None,
+            (None, Some(token)) if is_owner_token(token) => Some(token.to_string()),
+            (None, _) => Some(Uuid::new_v4().simple().to_string()),
+        };
+        Self {
+            user_id,
+            owner_token,
+        }
+    }
+
+    fn lock_key(&self) -> String {
+        match (self.user_id, &self.owner_token) {
+            (Some(id), _) => format!("sp_runs:user:{}", id),
+            (None, token) => format!("sp_runs:owner:{}", token.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+fn is_owner_token(token: &str) -> bool {
+    token.len() == 32
+        && token
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// 开局前检查同一用户/匿名玩家进行中的对局数（同一事务内加锁，避免并发开局绕过上限）
+pub async fn ensure_run_capacity(
+    conn: &mut PgConnection,
+    max_active_runs: i64,
+    owner: &RunOwner,
+) -> Result<(), ApiError> {
+    if max_active_runs <= 0 {
+        return Ok(());
+    }
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(owner.lock_key())
+        .execute(&mut *conn)
+        .await?;
+
+    let active: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM single_player_runs
+        WHERE status = 'active' AND ends_at > NOW()
+          AND CASE WHEN $1::uuid IS NOT NULL THEN user_id = $1
+                   ELSE user_id IS NULL AND owner_token = $2 END
+        "#,
+    )
+    .bind(owner.user_id)
+    .bind(&owner.owner_token)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if active >= max_active_runs {
+        return Err(ApiError::BadRequest("Too many active runs".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn run(mode: &str, status: &str, ends_in_secs: i64) -> SinglePlayerRun {
+        let now = Utc::now();
+        SinglePlayerRun {
+            id: Uuid::new_v4(),
+            session_id: "s".to_string(),
+            theme_id: None,
+            level: 1,
+            status: status.to_string(),
+            max_mistakes: 3,
+            mistakes: 0,
+            target_total: 3,
+            targets_found: 0,
+            started_at: now - Duration::seconds(60),
+            ends_at: now + Duration::seconds(ends_in_secs),
+            submitted_at: None,
+            level_id: None,
+            time_bonus_seconds: 0,
+            mistake_penalty_seconds: 0,
+            user_id: None,
+            finished_at: None,
+            score: None,
+            challenge_date: None,
+            suspicious_catches: 0,
+            flagged: false,
+            mode: mode.to_string(),
+            wave: 1,
+            difficulty_level: 1,
+            owner_token: None,
+        }
+    }
+
+    #[test]
+    fn overdue_active_run_expires() {
+        let r = run("classic", "active", -10);
+        let out = outcome(&r, Utc::now()).unwrap();
+        assert_eq!(out.status, "expired");
+        assert_eq!(out.finished_at, r.ends_at);
+        assert_eq!(out.score, 0);
+    }
+
+    #[test]
+    fn running_game_has_no_outcome() {
+        assert!(outcome(&run("classic", "active", 30), Utc::now()).is_none());
+    }
+
+    #[test]
+    fn endless_never_wins_on_targets() {
+        let mut r = run("endless", "active", 30);
+        r.targets_found = 3;
+        assert!(outcome(&r, Utc::now()).is_none());
+        r.mistakes = 3;
+        assert_eq!(outcome(&r, Utc::now()).unwrap().status, "defeat");
+    }
+
+    #[test]
+    fn anonymous_owner_keeps_an_issued_token() {
+        let issued = RunOwner::new(None, None).owner_token.unwrap();
+        assert!(is_owner_token(&issued));
+
+        let again = RunOwner::new(None, Some(&issued));
+        assert_eq!(again.owner_token.as_deref(), Some(issued.as_str()));
+        assert_ne!(
+            RunOwner::new(None, Some("not-a-token")).owner_token,
+            Some("not-a-token".to_string())
+        );
+
+        let user = RunOwner::new(Some(Uuid::new_v4()), Some(&issued));
+        assert!(user.owner_token.is_none());
+        assert_ne!(user.lock_key(), again.lock_key());
+    }
+
+    #[test]
+    fn idle_endless_run_is_reclaimed_at_wave_difficulty() {
+        let mut r = run("endless", "active", -10);
+        r.targets_found = 3;
+        r.difficulty_level = 3;
+        let out = outcome(&r, Utc::now()).unwrap();
+        assert_eq!(out.status, "expired");
+        assert_eq!(out.score, compute_score(3, "expired", 3, 0, 0));
+    }
+}
@@ -0,0 +1,222 @@
+//! AI 鱼出场策略
+//!
+//! 每个主题在 `themes.spawn_policy`（JSONB，`{"type": ...}`）里选一种策略，缺省或无法解析时为固定比例：
+//! - `fixed_ratio`：每 `spawn_rate` 条人类画作出 1 条
+//! - `time_drip`：房间里有人类画作后，每隔 `intervalSeconds` 出 1 条
+//! - `target_fraction`：存活鱼中 AI 占比低于 `fraction` 时补 1 条
+//! - `adaptive`：按本房间 AI 被淘汰前的平均存活时长调整比例，淘汰得越快出得越密
+//!
+//! 不论哪种策略，本房间累计出场的 AI 数都不超过 `max_imposters`（0 表示不限）。
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::Theme;
+use crate::services::ApiError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum SpawnPolicy {
+    FixedRatio,
+    TimeDrip {
+        interval_seconds: i64,
+    },
+    TargetFraction {
+        fraction: f64,
+    },
+    Adaptive {
+        /// AI 平均存活时长达到该值时按 `spawn_rate` 出场
+        target_lifetime_seconds: f64,
+    },
+}
+
+impl SpawnPolicy {
+    pub fn from_theme(theme: &Theme) -> Self {
+        serde_json::from_value(theme.spawn_policy.clone()).unwrap_or(SpawnPolicy::FixedRatio)
+    }
+
+    /// 是否需要后台定时检查（其余策略只在人类提交画作时检查）
+    pub fn needs_tick(&self) -> bool {
+        matches!(
+            self,
+            SpawnPolicy::TimeDrip { .. } | SpawnPolicy::TargetFraction { .. }
+        )
+    }
+}
+
+/// 触发检查的时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnTrigger {
+    HumanDrawing,
+    Tick,
+}
+
+/// 决策所需的房间统计
+#[derive(Debug, Clone, Copy, Default, sqlx::FromRow)]
+pub struct RoomSpawnStats {
+    pub humans: i64,
+    pub human_alive: i64,
+    pub ai_spawned: i64,
+    pub ai_alive: i64,
+    /// 距上一条 AI 出场（没有则距第一条人类画作）的秒数
+    pub seconds_since_last_ai: Option<f64>,
+    /// 已淘汰 AI 的平均存活秒数
+    pub avg_ai_lifetime_seconds: Option<f64>,
+}
+
+/// 自适应策略下的实际比例：平均存活越短比例越小（出得越密），限制在 `[1, 2 * spawn_rate]`
+pub fn adaptive_rate(spawn_rate: i64, avg_lifetime: Option<f64>, target_lifetime: f64) -> i64 {
+    let Some(avg) = avg_lifetime.filter(|_| target_lifetime > 0.0) else {
+        return spawn_rate;
+    };
+    let scaled = (spawn_rate as f64 * avg / target_lifetime).round() as i64;
+    scaled.clamp(1, spawn_rate * 2)
+}
+
+/// 本次是否出一条 AI 鱼
+pub fn should_spawn(
+    policy: &SpawnPolicy,
+    spawn_rate: i32,
+    max_imposters: i32,
+    trigger: SpawnTrigger,
+    stats: &RoomSpawnStats,
+) -> bool {
+    if max_imposters > 0 && stats.ai_spawned >= max_imposters as i64 {
+        return false;
+    }
+    if stats.humans == 0 {
+        return false;
+    }
+    let spawn_rate = spawn_rate.max(1) as i64;
+
+    match policy {
+        SpawnPolicy::FixedRatio => {
+            trigger == SpawnTrigger::HumanDrawing && stats.ai_spawned < stats.humans / spawn_rate
+        }
+        SpawnPolicy::TimeDrip { interval_seconds } => stats
+            .seconds_since_last_ai
+            .is_some_and(|s| s >= (*interval_seconds).max(1) as f64),
+        SpawnPolicy::TargetFraction { fraction } => {
+            let alive = stats.ai_alive + stats.human_alive;
+            (stats.ai_alive as f64) < fraction * alive as f64
+        }
+        SpawnPolicy::Adaptive {
+            target_lifetime_seconds,
+        } => {
+            let rate = adaptive_rate(
+                spawn_rate,
+                stats.avg_ai_lifetime_seconds,
+                *target_lifetime_seconds,
+            );
+            trigger == SpawnTrigger::HumanDrawing && stats.ai_spawned < stats.humans / rate
+        }
+    }
+}
+
+pub async fn load_stats(
+    conn: &mut sqlx::PgConnection,
+    room_id: Uuid,
+) -> Result<RoomSpawnStats, ApiError> {
+    let stats: RoomSpawnStats = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE is_ai = FALSE) AS humans,
+            COUNT(*) FILTER (WHERE is_ai = FALSE AND is_eliminated = FALSE AND is_hidden = FALSE)
+                AS human_alive,
+            COUNT(*) FILTER (WHERE is_ai = TRUE) AS ai_spawned,
+            COUNT(*) FILTER (WHERE is_ai = TRUE AND is_eliminated = FALSE AND is_hidden = FALSE)
+                AS ai_alive,
+            EXTRACT(EPOCH FROM NOW() - COALESCE(
+                MAX(created_at) FILTER (WHERE is_ai = TRUE),
+                MIN(created_at) FILTER (WHERE is_ai = FALSE)
+            ))::float8 AS seconds_since_last_ai,
+            AVG(EXTRACT(EPOCH FROM eliminated_at - created_at))
+                FILTER (WHERE is_ai = TRUE AND eliminated_at IS NOT NULL)::float8
+                AS avg_ai_lifetime_seconds
+        FROM drawings
+        WHERE room_id = $1
+        "#,
+    )
+    .bind(room_id)
+    .fetch_one(conn)
+    .await?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(humans: i64, ai_spawned: i64) -> RoomSpawnStats {
+        RoomSpawnStats {
+            humans,
+            human_alive: humans,
+            ai_spawned,
+            ai_alive: ai_spawned,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fixed_ratio_and_cap() {
+        let p = SpawnPolicy::FixedRatio;
+        let t = SpawnTrigger::HumanDrawing;
+        assert!(!should_spawn(&p, 5, 5, t, &stats(4, 0)));
+        assert!(should_spawn(&p, 5, 5, t, &stats(5, 0)));
+        assert!(!should_spawn(&p, 5, 5, t, &stats(6, 1)));
+        assert!(!should_spawn(&p, 5, 5, SpawnTrigger::Tick, &stats(5, 0)));
+        assert!(!should_spawn(&p, 1, 3, t, &stats(10, 3)));
+        assert!(should_spawn(&p, 0, 0, t, &stats(10, 3)));
+    }
+
+    #[test]
+    fn drip_and_fraction() {
+        let drip = SpawnPolicy::TimeDrip {
+            interval_seconds: 30,
+        };
+        let mut s = stats(2, 0);
+        assert!(!should_spawn(&drip, 5, 5, SpawnTrigger::Tick, &s));
+        s.seconds_since_last_ai = Some(31.0);
+        assert!(should_spawn(&drip, 5, 5, SpawnTrigger::Tick, &s));
+        assert!(!should_spawn(&drip, 5, 5, SpawnTrigger::Tick, &stats(0, 0)));
+
+        let frac = SpawnPolicy::TargetFraction { fraction: 0.25 };
+        assert!(should_spawn(&frac, 5, 5, SpawnTrigger::Tick, &stats(4, 0)));
+        assert!(!should_spawn(&frac, 5, 5, SpawnTrigger::Tick, &stats(3, 1)));
+    }
+
+    #[test]
+    fn adaptive_spawns_faster_when_ais_die_fast() {
+        assert_eq!(adaptive_rate(4, None, 60.0), 4);
+        assert_eq!(adaptive_rate(4, Some(15.0), 60.0), 1);
+        assert_eq!(adaptive_rate(4, Some(600.0), 60.0), 8);
+
+        let p = SpawnPolicy::Adaptive {
+            target_lifetime_seconds: 60.0,
+        };
+        let mut s = stats(2, 1);
+        s.avg_ai_lifetime_seconds = Some(10.0);
+        assert!(should_spawn(&p, 4, 5, SpawnTrigger::HumanDrawing, &s));
+        s.avg_ai_lifetime_seconds = None;
+        assert!(!should_spawn(&p, 4, 5, SpawnTrigger::HumanDrawing, &s));
+    }
+
+    #[test]
+    fn policy_json_roundtrip() {
+        let p: SpawnPolicy =
+            serde_json::from_str(r#"{"type":"time_drip","intervalSeconds":20}"#).unwrap();
+        assert_eq!(
+            p,
+            SpawnPolicy::TimeDrip {
+                interval_seconds: 20
+            }
+        );
+        assert!(p.needs_tick());
+        let p: SpawnPolicy = serde_json::from_str(r#"{"type":"fixed_ratio"}"#).unwrap();
+        assert_eq!(p, SpawnPolicy::FixedRatio);
+    }
+}
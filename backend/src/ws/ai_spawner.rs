@@ -0,0 +1,221 @@
+//! 按主题出场策略投放 AI 鱼并广播 `item:add`
+//!
+//! 人类提交画作时检查一次；需要定时检查的策略（见 `SpawnPolicy::needs_tick`）由后台任务周期检查。
+
+use deadpool_redis::redis;
+use socketioxide::SocketIo;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::models::{Drawing, Room, Theme};
+use crate::services::redis_script::LuaScript;
+use crate::services::spawn_policy::{self, SpawnPolicy, SpawnTrigger};
+use crate::services::{ai_fish_buffer, ApiError, AppState};
+use crate::ws::{event_log, GameItemData};
+
+/// 出鱼锁的过期时间：持锁进程崩溃时最多卡住这么久
+const SPAWN_LOCK_TTL_MS: u64 = 10_000;
+
+/// 只删除自己持有的锁，避免过期后误删别人刚拿到的锁
+static RELEASE_LOCK_SCRIPT: LuaScript = LuaScript::new(
+    r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#,
+);
+
+fn spawn_lock_key(room_id: Uuid) -> String {
+    format!("ai_spawn:lock:{}", room_id)
+}
+
+/// `SET NX PX` 取房间出鱼锁，成功返回锁令牌；已被占用时返回 `None`
+async fn acquire_spawn_lock(state: &AppState, room_id: Uuid) -> Result<Option<String>, ApiError> {
+    let mut conn = state
+        .redis
+        .get()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    let token = Uuid::new_v4().to_string();
+    let acquired: Option<String> = redis::cmd("SET")
+        .arg(spawn_lock_key(room_id))
+        .arg(&token)
+        .arg("NX")
+        .arg("PX")
+        .arg(SPAWN_LOCK_TTL_MS)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Redis error: {}", e)))?;
+    Ok(acquired.map(|_| token))
+}
+
+async fn release_spawn_lock(state: &AppState, room_id: Uuid, token: &str) {
+    let result: Result<i64, String> = async {
+        let mut conn = state.redis.get().await.map_err(|e| e.to_string())?;
+        RELEASE_LOCK_SCRIPT
+            .prepare()
+            .key(spawn_lock_key(room_id))
+            .arg(token)
+            .invoke(&mut conn)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(
+            "[AiSpawn] failed to release lock for room {}: {}",
+            room_id,
+            e
+        );
+    }
+}
+
+enum SpawnAttempt {
+    /// 策略判定这次不出鱼
+    NotDue,
+    /// 尝试出鱼（两个池都取不到时为 `None`）
+    Attempted(Option<Box<Drawing>>),
+}
+
+/// 持锁期间的判定与出鱼
+async fn spawn_locked(
+    state: &AppState,
+    room: &Room,
+    theme: &Theme,
+    policy: &SpawnPolicy,
+    trigger: SpawnTrigger,
+) -> Result<SpawnAttempt, ApiError> {
+    let stats = {
+        let mut conn = state.db.acquire().await?;
+        spawn_policy::load_stats(&mut conn, room.id).await?
+    };
+    if !spawn_policy::should_spawn(
+        policy,
+        theme.spawn_rate,
+        theme.max_imposters,
+        trigger,
+        &stats,
+    ) {
+        return Ok(SpawnAttempt::NotDue);
+    }
+
+    tracing::info!(
+        "AI fish trigger ({:?}, {:?}): humans={}, ai_spawned={}",
+        policy,
+        trigger,
+        stats.humans,
+        stats.ai_spawned
+    );
+
+    let ai_drawing = match state.try_spawn_from_n8n_queue(room.id, theme.id).await {
+        Some(d) => Some(d),
+        None => state.spawn_preset_ai_fish(room.id).await,
+    };
+    Ok(SpawnAttempt::Attempted(ai_drawing.map(Box::new)))
+}
+
+/// 按策略判断是否出鱼；出鱼时优先取主题共享池，池空时退回预置池
+pub async fn maybe_spawn_ai(
+    io: &SocketIo,
+    state: &Arc<AppState>,
+    room: &Room,
+    theme: &Theme,
+    trigger: SpawnTrigger,
+) -> Result<Option<Drawing>, ApiError> {
+    let policy = SpawnPolicy::from_theme(theme);
+
+    // 同一房间的判定与出鱼串行化，避免并发提交时越过 max_imposters；
+    // 用 Redis 锁而不是数据库事务锁，出鱼时不必额外占着一条数据库连接
+    let Some(lock) = acquire_spawn_lock(state, room.id).await? else {
+        return Ok(None);
+    };
+    let attempt = spawn_locked(state, room, theme, &policy, trigger).await;
+    release_spawn_lock(state, room.id, &lock).await;
+    let SpawnAttempt::Attempted(ai_drawing) = attempt? else {
+        return Ok(None);
+    };
+    let ai_drawing = ai_drawing.map(|d| *d);
+
+    if let Some(ref ai_drawing) = ai_drawing {
+        tracing::info!("AI fish spawned: {} ({})", ai_drawing.name, ai_drawing.id);
+
+        // 广播 item:add 事件通知前端
+        let item_data: GameItemData = ai_drawing.clone().into();
+        event_log::broadcast(
+            state,
+            io.within(room.room_code.clone()),
+            &room.room_code,
+            &item_data,
+        )
+        .await;
+    } else {
+        tracing::warn!("Failed to spawn AI fish for room {}", room.room_code);
+    }
+
+    // 后台给主题池补货
+    let state = state.clone();
+    let theme = theme.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ai_fish_buffer::ensure_theme_buffer(&state, &theme).await {
+            tracing::warn!("AI fish buffer refill failed: {:?}", e);
+        }
+    });
+
+    Ok(ai_drawing)
+}
+
+/// 后台任务：按 `AI_SPAWN_TICK_SECONDS` 周期检查定时类策略的活跃房间，0 表示关闭
+pub async fn start_ai_spawn_ticker(io: SocketIo, state: Arc<AppState>) {
+    let interval_secs = state.config.ai_spawn_tick_seconds;
+    if interval_secs == 0 {
+        tracing::info!("[AiSpawn] ticker disabled");
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    loop {
+        ticker.tick().await;
+        if let Err(err) = tick_ai_spawn(&io, &state).await {
+            tracing::warn!("[AiSpawn] tick failed: {:?}", err);
+        }
+    }
+}
+
+async fn tick_ai_spawn(io: &SocketIo, state: &Arc<AppState>) -> Result<(), ApiError> {
+    let themes: HashMap<Uuid, Theme> = sqlx::query_as::<_, Theme>("SELECT * FROM themes")
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .filter(|t| SpawnPolicy::from_theme(t).needs_tick())
+        .map(|t| (t.id, t))
+        .collect();
+    if themes.is_empty() {
+        return Ok(());
+    }
+
+    let theme_ids: Vec<Uuid> = themes.keys().copied().collect();
+    let rooms: Vec<Room> = sqlx::query_as(
+        r#"
+        SELECT * FROM rooms
+        WHERE status = 'active'
+          AND theme_id = ANY($1)
+          AND (voting_ends_at IS NULL OR voting_ends_at <= NOW())
+          AND updated_at > NOW() - INTERVAL '1 hour'
+        "#,
+    )
+    .bind(&theme_ids)
+    .fetch_all(&state.db)
+    .await?;
+
+    for room in &rooms {
+        let Some(theme) = themes.get(&room.theme_id) else {
+            continue;
+        };
+        if let Err(err) = maybe_spawn_ai(io, state, room, theme, SpawnTrigger::Tick).await {
+            tracing::warn!("[AiSpawn] room {} failed: {:?}", room.room_code, err);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! 房间事件日志：断线重连后按顺序补发错过的广播
+//!
+//! 房间广播（实现 `RoomEvent` 的事件：`item:add`、`vote:update`、`vote:received`、`fish:eliminate`、
+//! `phase:update`、`game:*`、`comment:add`）都经 `broadcast` 发出：先在 Redis 里分配房间内递增的 `eventSeq` 写进 payload，
+//! 再把 `{seq, event, data}` 追加到 `room:{code}:events`，只保留最近 `ROOM_EVENT_LOG_MAX` 条，
+//! `ROOM_EVENT_LOG_TTL_SECONDS` 内没有新事件则日志过期。事件号计数器 `room:{code}:event_seq` 不过期：
+//! 它若随日志一起过期会从 1 重新计数，客户端手里更大的 `lastSeq` 会把新事件误判成已收到。
+//! `tank:snapshot` 只是位置快照，不进日志。
+//!
+//! 客户端重连后发 `room:resume { roomId, lastSeq }`，服务端按顺序补发 `lastSeq` 之后的事件；
+//! 缺口里的事件已被截断或日志已过期时改发完整的 `sync:state`（其中 `eventSeq` 作为新的起点）。
+
+use deadpool_redis::redis::{self, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use socketioxide::operators::BroadcastOperators;
+
+use crate::services::{ApiError, AppState};
+use crate::ws::messages::RoomEvent;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub seq: i64,
+    pub event: String,
+    pub data: serde_json::Value,
+}
+
+/// `room:resume` 的处理方式
+#[derive(Debug, PartialEq)]
+pub enum Replay {
+    /// 按顺序补发（可能为空，表示没有错过任何事件）
+    Events(Vec<LoggedEvent>),
+    /// 缺口过大或日志已失效，改发完整状态
+    FullSync,
+}
+
+fn seq_key(room_code: &str) -> String {
+    format!("room:{}:event_seq", room_code)
+}
+
+fn log_key(room_code: &str) -> String {
+    format!("room:{}:events", room_code)
+}
+
+fn redis_error(e: impl std::fmt::Display) -> ApiError {
+    ApiError::Internal(format!("Redis error: {}", e))
+}
+
+/// 决定如何补发：`entries` 为日志中现存的事件，`current_seq` 为房间最新事件号
+pub fn plan_replay(mut entries: Vec<LoggedEvent>, last_seq: i64, current_seq: i64) -> Replay {
+    if last_seq > current_seq {
+        return Replay::FullSync;
+    }
+    if last_seq == current_seq {
+        return Replay::Events(Vec::new());
+    }
+
+    entries.retain(|e| e.seq > last_seq);
+    entries.sort_by_key(|e| e.seq);
+    entries.dedup_by_key(|e| e.seq);
+    let contiguous = entries.first().is_some_and(|e| e.seq == last_seq + 1)
+        && entries.windows(2).all(|w| w[1].seq == w[0].seq + 1);
+    if contiguous {
+        Replay::Events(entries)
+    } else {
+        Replay::FullSync
+    }
+}
+
+/// 房间最新事件号，没有日志时为 0
+pub async fn current_seq(state: &AppState, room_code: &str) -> Result<i64, ApiError> {
+    let mut conn = state.redis.get().await.map_err(redis_error)?;
+    let seq: Option<i64> = conn.get(seq_key(room_code)).await.map_err(redis_error)?;
+    Ok(seq.unwrap_or(0))
+}
+
+/// 一批房间的最新事件号（一次 `MGET`），顺序与 `room_codes` 一致，没有日志时为 0
+pub async fn current_seqs(state: &AppState, room_codes: &[String]) -> Result<Vec<i64>, ApiError> {
+    if room_codes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut conn = state.redis.get().await.map_err(redis_error)?;
+    let keys: Vec<String> = room_codes.iter().map(|code| seq_key(code)).collect();
+    let seqs: Vec<Option<i64>> = redis::cmd("MGET")
+        .arg(keys)
+        .query_async(&mut conn)
+        .await
+        .map_err(redis_error)?;
+    Ok(seqs.into_iter().map(|seq| seq.unwrap_or(0)).collect())
+}
+
+/// 分配事件号、写入 `data.eventSeq` 并追加到日志
+async fn append(
+    state: &AppState,
+    room_code: &str,
+    event: &str,
+    data: &mut serde_json::Value,
+) -> Result<i64, ApiError> {
+    let mut conn = state.redis.get().await.map_err(redis_error)?;
+    let seq: i64 = conn
+        .incr(seq_key(room_code), 1)
+        .await
+        .map_err(redis_error)?;
+    if let Some(obj) = data.as_object_mut() {
+        obj.insert("eventSeq".to_string(), seq.into());
+    }
+
+    let entry = serde_json::to_string(&LoggedEvent {
+        seq,
+        event: event.to_string(),
+        data: data.clone(),
+    })
+    .map_err(|e| ApiError::Internal(format!("Event encode failed: {}", e)))?;
+
+    let max = state.config.room_event_log_max.max(1);
+    let ttl = state.config.room_event_log_ttl_seconds;
+    let mut pipe = redis::pipe();
+    pipe.rpush(log_key(room_code), entry)
+        .ignore()
+        .ltrim(log_key(room_code), -max, -1)
+        .ignore()
+        .expire(log_key(room_code), ttl)
+        .ignore();
+    pipe.query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(redis_error)?;
+    Ok(seq)
+}
+
+/// 记入房间日志后广播；日志写入失败时照常广播（不带 `eventSeq`）
+pub async fn broadcast<T: RoomEvent>(
+    state: &AppState,
+    ops: BroadcastOperators,
+    room_code: &str,
+    payload: &T,
+) {
+    let event = T::NAME;
+    let mut data = match serde_json::to_value(payload) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!("[EventLog] encode {} failed: {}", event, e);
+            return;
+        }
+    };
+    if let Err(e) = append(state, room_code, event, &mut data).await {
+        tracing::warn!(
+            "[EventLog] append {} to {} failed: {:?}",
+            event,
+            room_code,
+            e
+        );
+    }
+    if let Err(e) = ops.emit(event, &data) {
+        tracing::error!("Failed to emit {} to room {}: {}", event, room_code, e);
+    }
+}
+
+/// 读出 `last_seq` 之后的事件
+pub async fn load_since(
+    state: &AppState,
+    room_code: &str,
+    last_seq: i64,
+) -> Result<Replay, ApiError> {
+    let mut conn = state.redis.get().await.map_err(redis_error)?;
+    let (current, raw): (Option<i64>, Vec<String>) = redis::pipe()
+        .get(seq_key(room_code))
+        .lrange(log_key(room_code), 0, -1)
+        .query_async(&mut conn)
+        .await
+        .map_err(redis_error)?;
+
+    let entries = raw
+        .iter()
+        .filter_map(|s| serde_json::from_str::<LoggedEvent>(s).ok())
+        .collect();
+    Ok(plan_replay(entries, last_seq, current.unwrap_or(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ev(seq: i64) -> LoggedEvent {
+        LoggedEvent {
+            seq,
+            event: "vote:update".to_string(),
+            data: serde_json::json!({ "eventSeq": seq }),
+        }
+    }
+
+    #[test]
+    fn replays_contiguous_events_in_order() {
+        let entries = vec![ev(5), ev(3), ev(4), ev(2)];
+        assert_eq!(
+            plan_replay(entries, 2, 5),
+            Replay::Events(vec![ev(3), ev(4), ev(5)])
+        );
+        assert_eq!(plan_replay(vec![ev(5)], 5, 5), Replay::Events(vec![]));
+    }
+
+    #[test]
+    fn falls_back_to_full_sync_on_gap_or_reset() {
+        // 3 已被截断
+        assert_eq!(plan_replay(vec![ev(4), ev(5)], 2, 5), Replay::FullSync);
+        // 日志过期、事件号归零
+        assert_eq!(plan_replay(vec![], 7, 0), Replay::FullSync);
+        // 写日志失败留下的空洞
+        assert_eq!(plan_replay(vec![ev(3), ev(5)], 2, 5), Replay::FullSync);
+    }
+}
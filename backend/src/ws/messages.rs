@@ -1,161 +1,510 @@
+//! Socket.IO 协议定义
+//!
+//! 服务端发出的每个事件都对应一个实现 `ServerEvent` 的结构体，事件名由 `ServerEvent::NAME` 给出；
+//! 房间广播另实现 `RoomEvent`，经 `event_log::broadcast` 发出并带上 `eventSeq`。
+//! 客户端连接时在 auth 里带 `protocol`，服务端协商出版本后先发 `protocol:hello`；不带视为版本 1。
+//! 版本之间只增字段、不改含义。
+//!
+//! 完整的 JSON Schema 见 `protocol_schema()`，也可通过 `GET /api/ws/schema` 获取，
+//! 仓库里的 `ws-protocol.schema.json` 由测试保持同步（`UPDATE_WS_SCHEMA=1 cargo test` 重新生成）。
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-/// WebSocket 消息类型
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "type", content = "data")]
-#[serde(rename_all = "snake_case")]
-pub enum WsMessage {
-    /// 初始房间状态
-    RoomState(RoomStateData),
-    /// 新作品加入
-    DrawingAdded(DrawingAddedData),
-    /// 投票数更新
-    VoteUpdated(VoteUpdatedData),
-    /// AI 卧底出现
-    AiSpawned(AiSpawnedData),
-    /// 作品被淘汰
-    DrawingEliminated(DrawingEliminatedData),
-    /// 在线人数更新
-    OnlineCount(OnlineCountData),
-    /// 浑浊度变化
-    TurbidityChanged(TurbidityChangedData),
-    /// 游戏结束
-    GameOver(GameOverData),
-    /// 心跳响应
-    Pong,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RoomStateData {
-    pub room: serde_json::Value,
-    pub drawings: Vec<serde_json::Value>,
-    pub theme: serde_json::Value,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DrawingAddedData {
-    pub id: Uuid,
-    pub name: String,
-    pub position: Position,
+use crate::models::{drawing_image_url, Drawing, DrawingItemRow, ThemeResponse};
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 2;
+/// 仍然支持的最低版本（1 为未声明版本的旧客户端）
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// 按客户端声明的版本协商，返回双方都支持的最高版本
+pub fn negotiate_version(requested: Option<u32>) -> Result<u32, String> {
+    match requested {
+        None => Ok(MIN_PROTOCOL_VERSION),
+        Some(v) if v < MIN_PROTOCOL_VERSION => Err(format!(
+            "unsupported protocol version {} (min {})",
+            v, MIN_PROTOCOL_VERSION
+        )),
+        Some(v) => Ok(v.min(PROTOCOL_VERSION)),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Position {
-    pub x: f64,
-    pub y: f64,
+/// 服务端发往客户端的事件
+pub trait ServerEvent: Serialize + JsonSchema {
+    const NAME: &'static str;
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct VoteUpdatedData {
-    pub drawing_id: Uuid,
-    pub vote_count: i32,
+/// 向整个房间广播并记入事件日志的事件
+pub trait RoomEvent: ServerEvent {}
+
+macro_rules! server_event {
+    ($ty:ty, $name:literal) => {
+        impl ServerEvent for $ty {
+            const NAME: &'static str = $name;
+        }
+    };
+    ($ty:ty, $name:literal, room) => {
+        server_event!($ty, $name);
+        impl RoomEvent for $ty {}
+    };
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AiSpawnedData {
-    pub ai_count: i32,
+// ==================== 客户端 → 服务端 ====================
+
+/// 连接时的 auth 数据
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectAuthData {
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub authorization: Option<String>,
+    /// 客户端支持的最高协议版本
+    #[serde(default)]
+    pub protocol: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DrawingEliminatedData {
-    pub drawing_id: Uuid,
-    pub was_ai: bool,
-    pub animation: String,
+/// `room:join`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomJoinInput {
+    pub room_id: String,
+    /// 重连时客户端已知的最新版本号，见 `room_sync`
+    #[serde(default)]
+    pub since_seq: Option<i64>,
+    /// `msgpack` 时同步数据走二进制附件
+    #[serde(default)]
+    pub encoding: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OnlineCountData {
-    pub count: i32,
+/// `room:resume`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomResumeInput {
+    pub room_id: String,
+    /// 客户端收到的最后一个 `eventSeq`
+    #[serde(default)]
+    pub last_seq: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TurbidityChangedData {
-    pub level: f64,
+/// `room:leave`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomLeaveInput {
+    pub room_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GameOverData {
-    pub reason: String,
-    pub final_ai_count: i32,
-    pub animation: String,
+/// `vote:cast` / `vote:retract` / `vote:chase`，投票者取自连接鉴权
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteInput {
+    pub fish_id: String,
 }
 
-// ==================== 战斗系统事件 ====================
+/// `comment:add`（客户端发送与服务端转发同一结构）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAddData {
+    pub item_id: String,
+    pub comment: CommentData,
+}
 
-/// 投票/开火事件 (前端 → 后端)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct VoteCastInput {
-    pub fish_id: String,
-    pub voter_id: String,
+pub struct CommentData {
+    pub author: String,
+    pub content: String,
 }
 
-/// 撤票事件 (前端 → 后端)
-#[derive(Debug, Serialize, Deserialize)]
+server_event!(CommentAddData, "comment:add", room);
+
+// ==================== 服务端 → 客户端 ====================
+
+/// `protocol:hello`：连接建立后发送协商结果
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct VoteRetractInput {
-    pub fish_id: String,
-    pub voter_id: String,
+pub struct ProtocolHello {
+    pub version: u32,
+    pub server_version: u32,
+    pub min_version: u32,
 }
 
-/// 追击事件 (前端 → 后端)
-#[derive(Debug, Serialize, Deserialize)]
+server_event!(ProtocolHello, "protocol:hello");
+
+/// 前端 GameItem 格式（`item:add` 及同步数据中的鱼）
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct VoteChaseInput {
-    pub fish_id: String,
-    pub voter_id: String,
+pub struct GameItemData {
+    pub id: String,
+    #[serde(rename = "imageUrl")]
+    pub image_url: String,
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    #[serde(rename = "isAI")]
+    pub is_ai: bool,
+    pub created_at: i64,
+    pub position: PositionData,
+    pub velocity: VelocityData,
+    pub rotation: f64,
+    pub scale: f64,
+    pub flip_x: bool,
+    pub comments: Vec<CommentData>, // 暂时为空数组
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PositionData {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VelocityData {
+    pub vx: f64,
+    pub vy: f64,
+}
+
+server_event!(GameItemData, "item:add", room);
+
+impl From<Drawing> for GameItemData {
+    fn from(d: Drawing) -> Self {
+        Self {
+            id: d.id.to_string(),
+            image_url: drawing_image_url(d.id),
+            name: d.name,
+            description: d.description.unwrap_or_default(),
+            author: d.author_name, // 前端用 author
+            is_ai: d.is_ai,
+            created_at: d.created_at.timestamp_millis(),
+            position: PositionData {
+                x: d.position_x,
+                y: d.position_y,
+            },
+            velocity: VelocityData {
+                vx: d.velocity_x,
+                vy: d.velocity_y,
+            },
+            rotation: d.rotation,
+            scale: d.scale,
+            flip_x: d.flip_x,
+            comments: vec![],
+        }
+    }
+}
+
+impl From<DrawingItemRow> for GameItemData {
+    fn from(d: DrawingItemRow) -> Self {
+        Self {
+            id: d.id.to_string(),
+            image_url: drawing_image_url(d.id),
+            name: d.name,
+            description: d.description.unwrap_or_default(),
+            author: d.author_name,
+            is_ai: d.is_ai,
+            created_at: d.created_at.timestamp_millis(),
+            position: PositionData {
+                x: d.position_x,
+                y: d.position_y,
+            },
+            velocity: VelocityData {
+                vx: d.velocity_x,
+                vy: d.velocity_y,
+            },
+            rotation: d.rotation,
+            scale: d.scale,
+            flip_x: d.flip_x,
+            comments: vec![],
+        }
+    }
+}
+
+/// `sync:state`：进入房间时的全量状态
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStateData {
+    pub phase: String,
+    pub room_id: String,
+    pub total_items: i32,
+    pub ai_count: i32,
+    pub turbidity: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_ends_at: Option<i64>,
+    pub server_time: i64,
+    pub seq: i64,
+    /// 房间事件日志的最新事件号，`room:resume` 从这里继续
+    pub event_seq: i64,
+    pub theme: ThemeResponse,
+    pub items: Vec<GameItemData>,
+}
+
+server_event!(SyncStateData, "sync:state");
+
+/// `sync:delta`：`sinceSeq` 之后的变化
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDeltaData {
+    pub room_id: String,
+    pub seq: i64,
+    pub since_seq: i64,
+    pub phase: String,
+    pub total_items: i32,
+    pub ai_count: i32,
+    pub turbidity: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_ends_at: Option<i64>,
+    pub server_time: i64,
+    /// 房间事件日志的最新事件号，见 `event_log`
+    pub event_seq: i64,
+    /// 新出现的鱼（含回看窗口内客户端可能已有的，按 id 覆盖）
+    pub added: Vec<GameItemData>,
+    /// 有变化且仍在场的鱼的票数 `[id, count]`
+    pub votes: Vec<(String, i32)>,
+    /// 被淘汰或隐藏的鱼（可能有客户端没见过的 id）
+    pub removed: Vec<String>,
+}
+
+server_event!(SyncDeltaData, "sync:delta");
+
+/// `phase:update`：房间阶段切换
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseUpdateData {
+    pub phase: String,
+    pub room_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voting_ends_at: Option<i64>,
+    pub server_time: i64,
+    pub seq: i64,
 }
 
-/// 票数更新 (后端 → 前端)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+server_event!(PhaseUpdateData, "phase:update", room);
+
+/// `vote:update`：票数更新
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct VoteUpdateOutput {
     pub fish_id: String,
     pub count: i32,
     pub voters: Vec<String>,
+    pub seq: i64,
 }
 
-/// 被投票通知 (后端 → 前端)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+server_event!(VoteUpdateOutput, "vote:update", room);
+
+/// `vote:received`：被投票通知
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct VoteReceivedOutput {
     pub fish_id: String,
     pub voter_id: String,
 }
 
-/// 鱼被淘汰 (后端 → 前端)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+server_event!(VoteReceivedOutput, "vote:received", room);
+
+/// `vote:error`：只发给操作者
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteErrorOutput {
+    /// `unauthorized` / `not_voting` / `chase_disabled` / `rate_limited`
+    pub reason: String,
+    pub fish_id: String,
+    /// `rate_limited` 时多久后可以重试（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+server_event!(VoteErrorOutput, "vote:error");
+
+/// `comment:error`：评论被拒绝，只发给操作者
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentErrorOutput {
+    /// `rate_limited` / `blocked_content`
+    pub reason: String,
+    pub item_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+server_event!(CommentErrorOutput, "comment:error");
+
+/// `fish:eliminate`：鱼被淘汰
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FishEliminateOutput {
     pub fish_id: String,
     pub fish_name: String,
+    #[serde(rename = "isAI")]
     pub is_ai: bool,
     pub fish_owner_id: String,
     pub killer_names: Vec<String>,
+    pub seq: Option<i64>,
 }
 
-/// 游戏胜利 (后端 → 前端)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+server_event!(FishEliminateOutput, "fish:eliminate", room);
+
+/// `game:victory`
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GameVictoryOutput {
     pub mvp_id: String,
     pub mvp_name: String,
-    pub ai_remaining: i32,
-    pub human_remaining: i32,
+    pub ai_remaining: i64,
+    pub human_remaining: i64,
+    pub human_total: i64,
 }
 
-/// 游戏失败 (后端 → 前端)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+server_event!(GameVictoryOutput, "game:victory", room);
+
+/// `game:defeat`
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GameDefeatOutput {
-    pub ai_remaining: i32,
-    pub human_remaining: i32,
+    /// `too_many_human_killed` / `ai_overrun`
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub human_killed: Option<i64>,
+    pub ai_remaining: i64,
+    pub human_remaining: i64,
+    pub human_total: i64,
 }
 
-/// 战斗系统常量
-pub mod battle_constants {
-    pub const ELIMINATION_THRESHOLD: i32 = 4;
-    pub const DEFEAT_AI_COUNT: i32 = 5;
-    pub const VICTORY_MIN_HUMAN: i32 = 5;
+server_event!(GameDefeatOutput, "game:defeat", room);
+
+/// 快照里的一条鱼，序列化为数组 `[id, x, y, vx, vy, flipX]`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SnapshotItem(pub Uuid, pub f32, pub f32, pub f32, pub f32, pub bool);
+
+/// `tank:snapshot`：服务端鱼缸模拟的位置快照，见 `tank_sim`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TankSnapshot {
+    pub t: i64,
+    pub items: Vec<SnapshotItem>,
+}
+
+server_event!(TankSnapshot, "tank:snapshot");
+
+// ==================== Schema 导出 ====================
+
+/// 整个协议的 JSON Schema：`clientToServer` / `serverToClient` 按事件名给出 payload 的引用，
+/// 房间广播的 payload 另带 `eventSeq`
+pub fn protocol_schema() -> serde_json::Value {
+    let mut gen = SchemaGenerator::new(SchemaSettings::draft07());
+
+    let mut client = serde_json::Map::new();
+    let mut input = |name: &str, schema: schemars::schema::Schema| {
+        client.insert(name.to_string(), serde_json::to_value(schema).unwrap());
+    };
+    input("auth", gen.subschema_for::<ConnectAuthData>());
+    input("room:join", gen.subschema_for::<RoomJoinInput>());
+    input("room:resume", gen.subschema_for::<RoomResumeInput>());
+    input("room:leave", gen.subschema_for::<RoomLeaveInput>());
+    input("vote:cast", gen.subschema_for::<VoteInput>());
+    input("vote:retract", gen.subschema_for::<VoteInput>());
+    input("vote:chase", gen.subschema_for::<VoteInput>());
+    input("comment:add", gen.subschema_for::<CommentAddData>());
+
+    let mut server = serde_json::Map::new();
+    fn unicast<T: ServerEvent>(
+        gen: &mut SchemaGenerator,
+        out: &mut serde_json::Map<String, serde_json::Value>,
+    ) {
+        let schema = gen.subschema_for::<T>();
+        out.insert(T::NAME.to_string(), serde_json::to_value(schema).unwrap());
+    }
+    fn room<T: RoomEvent>(
+        gen: &mut SchemaGenerator,
+        out: &mut serde_json::Map<String, serde_json::Value>,
+    ) {
+        let schema = serde_json::to_value(gen.subschema_for::<T>()).unwrap();
+        out.insert(
+            T::NAME.to_string(),
+            serde_json::json!({
+                "allOf": [
+                    schema,
+                    { "properties": { "eventSeq": { "type": "integer", "format": "int64" } } }
+                ]
+            }),
+        );
+    }
+    unicast::<ProtocolHello>(&mut gen, &mut server);
+    unicast::<SyncStateData>(&mut gen, &mut server);
+    unicast::<SyncDeltaData>(&mut gen, &mut server);
+    unicast::<VoteErrorOutput>(&mut gen, &mut server);
+    unicast::<CommentErrorOutput>(&mut gen, &mut server);
+    unicast::<TankSnapshot>(&mut gen, &mut server);
+    room::<GameItemData>(&mut gen, &mut server);
+    room::<PhaseUpdateData>(&mut gen, &mut server);
+    room::<VoteUpdateOutput>(&mut gen, &mut server);
+    room::<VoteReceivedOutput>(&mut gen, &mut server);
+    room::<FishEliminateOutput>(&mut gen, &mut server);
+    room::<GameVictoryOutput>(&mut gen, &mut server);
+    room::<GameDefeatOutput>(&mut gen, &mut server);
+    room::<CommentAddData>(&mut gen, &mut server);
+
+    serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Mimic Socket.IO protocol",
+        "version": PROTOCOL_VERSION,
+        "minVersion": MIN_PROTOCOL_VERSION,
+        "clientToServer": client,
+        "serverToClient": server,
+        "definitions": gen.definitions(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_highest_common_version() {
+        assert_eq!(negotiate_version(None), Ok(1));
+        assert_eq!(negotiate_version(Some(1)), Ok(1));
+        assert_eq!(negotiate_version(Some(99)), Ok(PROTOCOL_VERSION));
+        assert!(negotiate_version(Some(0)).is_err());
+    }
+
+    #[test]
+    fn payloads_keep_wire_names() {
+        let out = FishEliminateOutput {
+            fish_id: "f".to_string(),
+            fish_name: "n".to_string(),
+            is_ai: true,
+            fish_owner_id: String::new(),
+            killer_names: vec![],
+            seq: Some(3),
+        };
+        let json = serde_json::to_value(&out).unwrap();
+        assert_eq!(json["isAI"], true);
+        assert_eq!(json["killerNames"], serde_json::json!([]));
+
+        let schema = protocol_schema();
+        assert!(schema["serverToClient"]["fish:eliminate"]["allOf"].is_array());
+        assert!(schema["definitions"]["SyncStateData"].is_object());
+    }
+
+    /// 仓库里的 schema 文件与代码保持一致；`UPDATE_WS_SCHEMA=1` 时重新生成
+    #[test]
+    fn schema_file_is_up_to_date() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/ws-protocol.schema.json");
+        let generated = serde_json::to_string_pretty(&protocol_schema()).unwrap() + "\n";
+        if std::env::var("UPDATE_WS_SCHEMA").is_ok() {
+            std::fs::write(path, &generated).unwrap();
+        }
+        let committed = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "ws-protocol.schema.json is stale, run `UPDATE_WS_SCHEMA=1 cargo test`"
+        );
+    }
 }
@@ -1,4 +1,9 @@
+pub mod ai_spawner;
+pub mod event_log;
 pub mod game_rules;
+pub mod messages;
+pub mod room_sync;
 pub mod socketio_handler;
+pub mod tank_sim;
 
-pub use socketio_handler::GameItemData;
+pub use messages::GameItemData;
@@ -0,0 +1,208 @@
+//! 房间状态版本号与增量同步
+//!
+//! 鱼的新增、票数、淘汰/隐藏以及房间相位、计数变化时，数据库触发器从全局序列 `room_state_seq` 取号（见 schema.sql）：
+//! 变化的鱼记到 `drawings.state_seq`（新增时另记 `created_seq`），房间自身的变化记到 `rooms.state_seq`。
+//! 鱼的变化不回写房间行，投票之间不争房间行锁；房间当前版本号取两者的最大值（`current_seq`）。
+//! 序列号在提交前分配，提交顺序可能与号的大小不一致：客户端拿到 N+1 之后 N 才提交，
+//! 若这时已经断线，带着 `sinceSeq=N+1` 重连就会永远错过 N。因此增量从 `sinceSeq - SEQ_LOOKBACK`
+//! 开始取，窗口内的鱼按完整数据重发，客户端按 id 覆盖即可。
+//! `sync:state`、`vote:update`、`fish:eliminate`、`phase:update` 都带 `seq`，客户端记住收到的最大值；
+//! 重连时在 `room:join` 带上 `sinceSeq`，服务端回 `sync:delta`（新增的鱼、票数变化、移除的鱼与房间信息），
+//! `sinceSeq` 比当前版本还新（如数据库重置）时回退为完整的 `sync:state`。
+//!
+//! `room:join` 带 `encoding: "msgpack"` 时，`sync:state` / `sync:delta` 的内容改为 MessagePack 二进制附件，
+//! 事件数据只剩 `{"encoding": "msgpack", "seq": ...}`；房间广播仍为 JSON。
+
+use chrono::Utc;
+use socketioxide::extract::SocketRef;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{DrawingItemRow, Room};
+use crate::services::AppState;
+use crate::ws::event_log;
+use crate::ws::messages::{GameItemData, ServerEvent, SyncDeltaData};
+
+/// `sync:state` / `sync:delta` 的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MsgPack,
+}
+
+impl Encoding {
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()) {
+            Some(v) if v == "msgpack" => Encoding::MsgPack,
+            _ => Encoding::Json,
+        }
+    }
+}
+
+/// 按编码向单个客户端发送
+pub fn emit_encoded<T: ServerEvent>(socket: &SocketRef, seq: i64, payload: &T, encoding: Encoding) {
+    let event = T::NAME;
+    match encoding {
+        Encoding::Json => {
+            let _ = socket.emit(event, payload);
+        }
+        Encoding::MsgPack => match rmp_serde::to_vec_named(payload) {
+            Ok(bytes) => {
+                let header = serde_json::json!({ "encoding": "msgpack", "seq": seq });
+                let _ = socket.bin(vec![bytes]).emit(event, &header);
+            }
+            Err(e) => tracing::error!("[RoomSync] msgpack encode failed for {}: {}", event, e),
+        },
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeltaRow {
+    #[sqlx(flatten)]
+    item: DrawingItemRow,
+    created_seq: i64,
+}
+
+/// 增量回看的序列号个数：序列全局共享，取号到提交之间其它房间也在取号，
+/// 窗口要盖住这段时间内全站的取号量
+pub const SEQ_LOOKBACK: i64 = 1000;
+
+/// 一条变化的鱼在增量里的归类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// 完整数据，客户端可能已经有了（回看窗口内），按 id 覆盖
+    Added,
+    Updated,
+    /// 客户端可能从没见过，忽略即可
+    Removed,
+}
+
+/// `from` 为实际取增量的起点（已减去回看窗口）
+pub fn classify(created_seq: i64, gone: bool, from: i64) -> Change {
+    if gone {
+        Change::Removed
+    } else if created_seq > from {
+        Change::Added
+    } else {
+        Change::Updated
+    }
+}
+
+/// 房间当前版本号：房间行与其鱼的版本号取最大
+pub async fn current_seq(db: &PgPool, room_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT GREATEST(
+            state_seq,
+            COALESCE((SELECT MAX(state_seq) FROM drawings WHERE room_id = $1), 0)
+        )
+        FROM rooms WHERE id = $1
+        "#,
+    )
+    .bind(room_id)
+    .fetch_one(db)
+    .await
+}
+
+/// 载入 `since` 之后的增量；`since` 超出当前版本时返回 `None`，由调用方改发完整状态
+pub async fn load_delta(
+    state: &AppState,
+    room_code: &str,
+    since: i64,
+) -> Result<Option<SyncDeltaData>, sqlx::Error> {
+    let Some(room) = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE room_code = $1")
+        .bind(room_code)
+        .fetch_optional(&state.db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    // 先读版本号再读变化，之后的变化下次增量还能取到；号比它小却晚提交的由回看窗口补上
+    let seq = current_seq(&state.db, room.id).await?;
+    if since > seq {
+        return Ok(None);
+    }
+    let from = (since - SEQ_LOOKBACK).max(0);
+
+    let rows: Vec<DeltaRow> = sqlx::query_as(
+        r#"
+        SELECT
+            id, room_id, is_ai, name, description, author_name,
+            position_x, position_y, velocity_x, velocity_y, rotation, scale, flip_x,
+            vote_count, is_eliminated, is_hidden, session_id, created_at, created_seq
+        FROM drawings
+        WHERE room_id = $1 AND state_seq > $2
+        ORDER BY created_at
+        "#,
+    )
+    .bind(room.id)
+    .bind(from)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut added = Vec::new();
+    let mut votes = Vec::new();
+    let mut removed = Vec::new();
+    for row in rows {
+        let id: Uuid = row.item.id;
+        let gone = row.item.is_eliminated || row.item.is_hidden;
+        match classify(row.created_seq, gone, from) {
+            Change::Added => {
+                votes.push((id.to_string(), row.item.vote_count));
+                added.push(GameItemData::from(row.item));
+            }
+            Change::Updated => votes.push((id.to_string(), row.item.vote_count)),
+            Change::Removed => removed.push(id.to_string()),
+        }
+    }
+
+    Ok(Some(SyncDeltaData {
+        room_id: room.room_code,
+        seq,
+        since_seq: since,
+        phase: room.status,
+        total_items: room.total_items,
+        ai_count: room.ai_count,
+        turbidity: room.turbidity,
+        voting_started_at: room.voting_started_at.map(|t| t.timestamp_millis()),
+        voting_ends_at: room.voting_ends_at.map(|t| t.timestamp_millis()),
+        server_time: Utc::now().timestamp_millis(),
+        event_seq: event_log::current_seq(state, room_code).await.unwrap_or(0),
+        added,
+        votes,
+        removed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_changes_since_seq() {
+        assert_eq!(classify(12, false, 10), Change::Added);
+        assert_eq!(classify(12, true, 10), Change::Removed);
+        assert_eq!(classify(3, true, 10), Change::Removed);
+        assert_eq!(classify(10, false, 10), Change::Updated);
+    }
+
+    #[test]
+    fn late_commit_before_since_is_resent_in_full() {
+        // 客户端带 sinceSeq=N+1 重连，N 号的新鱼在它断线后才提交
+        let since = 501;
+        let from = (since - SEQ_LOOKBACK).max(0);
+        assert_eq!(classify(500, false, from), Change::Added);
+    }
+
+    #[test]
+    fn encoding_and_msgpack_votes() {
+        assert_eq!(Encoding::parse(Some(" MsgPack ")), Encoding::MsgPack);
+        assert_eq!(Encoding::parse(Some("json")), Encoding::Json);
+        assert_eq!(Encoding::parse(None), Encoding::Json);
+
+        let votes = vec![("a".to_string(), 3)];
+        let bytes = rmp_serde::to_vec_named(&votes).unwrap();
+        let decoded: Vec<(String, i32)> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, votes);
+    }
+}
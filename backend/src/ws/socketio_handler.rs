@@ -8,9 +8,19 @@ use std::sync::Arc;
 use tracing::{debug, info};
 use uuid::Uuid;
 
-use crate::models::{drawing_image_url, Drawing, DrawingItemRow, Room, Theme, ThemeResponse};
+use crate::models::{Drawing, DrawingItemRow, Room, Theme, ThemeResponse};
+use crate::services::content_filter::Field;
+use crate::services::rate_limit::{self, RateAction, Subject};
 use crate::services::{auth, AppState};
+use crate::ws::event_log::{self, Replay};
 use crate::ws::game_rules;
+use crate::ws::messages::{
+    negotiate_version, CommentAddData, CommentErrorOutput, ConnectAuthData, FishEliminateOutput,
+    GameDefeatOutput, GameItemData, GameVictoryOutput, PhaseUpdateData, ProtocolHello,
+    RoomJoinInput, RoomLeaveInput, RoomResumeInput, ServerEvent, SyncStateData, VoteErrorOutput,
+    VoteInput, VoteReceivedOutput, VoteUpdateOutput, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use crate::ws::room_sync::{self, Encoding};
 
 /// 存储在 socket extensions 中的会话信息
 #[derive(Clone)]
@@ -24,6 +34,12 @@ pub struct AuthSession {
     pub user_id: Uuid,
 }
 
+/// 存储在 socket extensions 中的协商后协议版本
+#[derive(Clone)]
+pub struct ProtocolSession {
+    pub version: u32,
+}
+
 #[derive(Debug)]
 pub struct WsAuthError(String);
 
@@ -40,6 +56,7 @@ pub async fn auth_middleware(
     state: SioState<Arc<AppState>>,
     Data(auth_data): Data<ConnectAuthData>,
 ) -> Result<(), WsAuthError> {
+    let version = negotiate_version(auth_data.protocol).map_err(WsAuthError)?;
     let token = extract_auth_token(&auth_data)
         .ok_or_else(|| WsAuthError("missing auth token".to_string()))?;
 
@@ -48,16 +65,34 @@ pub async fn auth_middleware(
         .map_err(|_| WsAuthError("invalid auth token".to_string()))?;
 
     socket.extensions.insert(AuthSession { user_id });
+    socket.extensions.insert(ProtocolSession { version });
     Ok(())
 }
 
 /// Socket.IO 连接处理
 pub fn on_connect(socket: SocketRef, _state: SioState<Arc<AppState>>) {
     let session_id = socket.id.to_string();
-    info!("[Socket.IO] New connection: {}", session_id);
+    let version = socket
+        .extensions
+        .get::<ProtocolSession>()
+        .map(|p| p.version)
+        .unwrap_or(MIN_PROTOCOL_VERSION);
+    info!(
+        "[Socket.IO] New connection: {} (protocol v{})",
+        session_id, version
+    );
+    let _ = socket.emit(
+        ProtocolHello::NAME,
+        &ProtocolHello {
+            version,
+            server_version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+        },
+    );
 
     // 注册事件处理器
     socket.on("room:join", on_room_join);
+    socket.on("room:resume", on_room_resume);
     socket.on("room:leave", on_room_leave);
     socket.on("vote:cast", on_vote_cast);
     socket.on("vote:retract", on_vote_retract);
@@ -113,7 +148,7 @@ async fn tick_phase_guard(io: &SocketIo, state: &AppState) -> Result<(), sqlx::E
             }
         }
 
-        emit_phase_update_with_io(io, &final_room);
+        emit_phase_update_with_io(io, state, &final_room).await;
     }
 
     // 2) active 提交期兜底：无人事件也能按提交超时进入 voting
@@ -152,7 +187,7 @@ async fn tick_phase_guard(io: &SocketIo, state: &AppState) -> Result<(), sqlx::E
 
         if should_start {
             if let Some(updated) = start_voting(state, room.id).await {
-                emit_phase_update_with_io(io, &updated);
+                emit_phase_update_with_io(io, state, &updated).await;
             }
         }
     }
@@ -163,36 +198,82 @@ async fn tick_phase_guard(io: &SocketIo, state: &AppState) -> Result<(), sqlx::E
 /// 加入房间
 async fn on_room_join(
     socket: SocketRef,
-    Data(data): Data<RoomJoinData>,
+    Data(data): Data<RoomJoinInput>,
     state: SioState<Arc<AppState>>,
 ) {
     let room_id = &data.room_id;
     info!("[Socket.IO] {} joining room {}", socket.id, room_id);
+    enter_room(&socket, &state, room_id).await;
 
-    // 加入 Socket.IO 房间
+    let encoding = Encoding::parse(data.encoding.as_deref());
+
+    // 重连：只发 sinceSeq 之后的变化
+    if let Some(since) = data.since_seq.filter(|s| *s > 0) {
+        match room_sync::load_delta(&state, room_id, since).await {
+            Ok(Some(delta)) => {
+                room_sync::emit_encoded(&socket, delta.seq, &delta, encoding);
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("[Socket.IO] delta for {} failed: {}", room_id, e),
+        }
+    }
+
+    // 发送房间初始状态
+    if let Ok(room_state) = get_room_state(&state, room_id).await {
+        room_sync::emit_encoded(&socket, room_state.seq, &room_state, encoding);
+    }
+}
+
+/// 断线重连：补发 `lastSeq` 之后错过的房间事件，补不上时发完整 `sync:state`
+async fn on_room_resume(
+    socket: SocketRef,
+    Data(data): Data<RoomResumeInput>,
+    state: SioState<Arc<AppState>>,
+) {
+    let room_id = &data.room_id;
+    info!(
+        "[Socket.IO] {} resuming room {} from event {}",
+        socket.id, room_id, data.last_seq
+    );
+    enter_room(&socket, &state, room_id).await;
+
+    match event_log::load_since(&state, room_id, data.last_seq).await {
+        Ok(Replay::Events(events)) => {
+            for logged in events {
+                let _ = socket.emit(logged.event, &logged.data);
+            }
+            return;
+        }
+        Ok(Replay::FullSync) => {}
+        Err(e) => tracing::warn!("[Socket.IO] resume for {} failed: {:?}", room_id, e),
+    }
+
+    if let Ok(room_state) = get_room_state(&state, room_id).await {
+        let _ = socket.emit(SyncStateData::NAME, &room_state);
+    }
+}
+
+/// 加入 Socket.IO 房间、记录会话并推进相位
+async fn enter_room(socket: &SocketRef, state: &AppState, room_id: &str) {
     let _ = socket.leave_all();
-    let _ = socket.join(room_id.clone());
+    let _ = socket.join(room_id.to_string());
 
     // 保存 session 信息到 extensions
     socket.extensions.insert(RoomSession {
-        room_code: room_id.clone(),
+        room_code: room_id.to_string(),
     });
 
     // 更新在线人数
-    update_online_count(&state, room_id, 1).await;
-
-    let _ = phase_tick_by_room_code(&socket, &state, room_id).await;
+    update_online_count(state, room_id, 1).await;
 
-    // 发送房间初始状态
-    if let Ok(room_state) = get_room_state(&state, room_id).await {
-        let _ = socket.emit("sync:state", &room_state);
-    }
+    let _ = phase_tick_by_room_code(socket, state, room_id).await;
 }
 
 /// 离开房间
 async fn on_room_leave(
     socket: SocketRef,
-    Data(data): Data<RoomLeaveData>,
+    Data(data): Data<RoomLeaveInput>,
     state: SioState<Arc<AppState>>,
 ) {
     info!("[Socket.IO] {} leaving room {}", socket.id, data.room_id);
@@ -218,18 +299,17 @@ fn on_disconnect(socket: SocketRef, state: SioState<Arc<AppState>>) {
 /// 投票/开火 (战斗系统)
 async fn on_vote_cast(
     socket: SocketRef,
-    Data(data): Data<BattleVoteCastData>,
+    Data(data): Data<VoteInput>,
     state: SioState<Arc<AppState>>,
 ) {
     info!("[Socket.IO] Vote cast: {:?}", data);
     let Some(voter_id) = authenticated_voter_id(&socket) else {
-        let payload = serde_json::json!({
-            "reason": "unauthorized",
-            "fishId": data.fish_id
-        });
-        let _ = socket.emit("vote:error", &payload);
+        emit_vote_error(&socket, "unauthorized", &data.fish_id);
         return;
     };
+    if !vote_allowed(&socket, &state, &data.fish_id).await {
+        return;
+    }
 
     let fish_id = match uuid::Uuid::parse_str(&data.fish_id) {
         Ok(id) => id,
@@ -260,11 +340,7 @@ async fn on_vote_cast(
         None => return,
     };
     if room.status != "voting" {
-        let payload = serde_json::json!({
-            "reason": "not_voting",
-            "fishId": data.fish_id
-        });
-        let _ = socket.emit("vote:error", &payload);
+        emit_vote_error(&socket, "not_voting", &data.fish_id);
         return;
     }
 
@@ -285,8 +361,8 @@ async fn on_vote_cast(
     }
 
     // 更新票数
-    let new_count: i32 = match sqlx::query_scalar(
-        "UPDATE drawings SET vote_count = vote_count + 1, updated_at = NOW() WHERE id = $1 RETURNING vote_count",
+    let (new_count, seq): (i32, i64) = match sqlx::query_as(
+        "UPDATE drawings SET vote_count = vote_count + 1, votes_received = votes_received + 1, updated_at = NOW() WHERE id = $1 RETURNING vote_count, state_seq",
     )
     .bind(fish_id)
     .fetch_one(&state.db)
@@ -305,48 +381,64 @@ async fn on_vote_cast(
             .unwrap_or_default();
 
     // 广播 vote:update
-    let vote_update = serde_json::json!({
-        "fishId": data.fish_id,
-        "count": new_count,
-        "voters": voters
-    });
-    let _ = socket
-        .within(room.room_code.clone())
-        .emit("vote:update", &vote_update);
+    let vote_update = VoteUpdateOutput {
+        fish_id: data.fish_id.clone(),
+        count: new_count,
+        voters: voters.clone(),
+        seq,
+    };
+    event_log::broadcast(
+        &state,
+        socket.within(room.room_code.clone()),
+        &room.room_code,
+        &vote_update,
+    )
+    .await;
 
     // 通知被投票者 vote:received
-    let vote_received = serde_json::json!({
-        "fishId": data.fish_id,
-        "voterId": voter_id
-    });
-    let _ = socket
-        .within(room.room_code.clone())
-        .emit("vote:received", &vote_received);
+    let vote_received = VoteReceivedOutput {
+        fish_id: data.fish_id.clone(),
+        voter_id,
+    };
+    event_log::broadcast(
+        &state,
+        socket.within(room.room_code.clone()),
+        &room.room_code,
+        &vote_received,
+    )
+    .await;
 
     let elimination_threshold = room.vote_threshold(&state.config);
     if new_count >= elimination_threshold {
         // 标记为淘汰
-        let _ = sqlx::query(
-            "UPDATE drawings SET is_eliminated = TRUE, eliminated_at = NOW() WHERE id = $1",
+        let eliminated_seq: Option<i64> = sqlx::query_scalar(
+            "UPDATE drawings SET is_eliminated = TRUE, eliminated_at = NOW() WHERE id = $1 RETURNING state_seq",
         )
         .bind(fish_id)
-        .execute(&state.db)
-        .await;
+        .fetch_optional(&state.db)
+        .await
+        .ok()
+        .flatten();
 
         // 获取投票者名字
         let killer_names: Vec<String> = voters.clone();
 
         // 广播 fish:eliminate
-        let eliminate_data = serde_json::json!({
-            "fishId": data.fish_id,
-            "fishName": drawing.name,
-            "isAI": drawing.is_ai,
-            "fishOwnerId": drawing.session_id.unwrap_or_default(),
-            "killerNames": killer_names
-        });
-        let _ = socket
-            .within(room.room_code.clone())
-            .emit("fish:eliminate", &eliminate_data);
+        let eliminate_data = FishEliminateOutput {
+            fish_id: data.fish_id.clone(),
+            fish_name: drawing.name.clone(),
+            is_ai: drawing.is_ai,
+            fish_owner_id: drawing.session_id.clone().unwrap_or_default(),
+            killer_names,
+            seq: eliminated_seq,
+        };
+        event_log::broadcast(
+            &state,
+            socket.within(room.room_code.clone()),
+            &room.room_code,
+            &eliminate_data,
+        )
+        .await;
 
         // 更新 AI 计数
         if drawing.is_ai {
@@ -364,18 +456,17 @@ async fn on_vote_cast(
 /// 撤票 (换目标时)
 async fn on_vote_retract(
     socket: SocketRef,
-    Data(data): Data<BattleVoteCastData>,
+    Data(data): Data<VoteInput>,
     state: SioState<Arc<AppState>>,
 ) {
     info!("[Socket.IO] Vote retract: {:?}", data);
     let Some(voter_id) = authenticated_voter_id(&socket) else {
-        let payload = serde_json::json!({
-            "reason": "unauthorized",
-            "fishId": data.fish_id
-        });
-        let _ = socket.emit("vote:error", &payload);
+        emit_vote_error(&socket, "unauthorized", &data.fish_id);
         return;
     };
+    if !vote_allowed(&socket, &state, &data.fish_id).await {
+        return;
+    }
 
     let fish_id = match uuid::Uuid::parse_str(&data.fish_id) {
         Ok(id) => id,
@@ -405,11 +496,7 @@ async fn on_vote_retract(
         None => return,
     };
     if room.status != "voting" {
-        let payload = serde_json::json!({
-            "reason": "not_voting",
-            "fishId": data.fish_id
-        });
-        let _ = socket.emit("vote:error", &payload);
+        emit_vote_error(&socket, "not_voting", &data.fish_id);
         return;
     }
 
@@ -429,8 +516,8 @@ async fn on_vote_retract(
     }
 
     // 减少票数
-    let new_count: i32 = match sqlx::query_scalar(
-        "UPDATE drawings SET vote_count = GREATEST(vote_count - 1, 0), updated_at = NOW() WHERE id = $1 RETURNING vote_count",
+    let (new_count, seq): (i32, i64) = match sqlx::query_as(
+        "UPDATE drawings SET vote_count = GREATEST(vote_count - 1, 0), votes_received = GREATEST(votes_received - 1, 0), updated_at = NOW() WHERE id = $1 RETURNING vote_count, state_seq",
     )
     .bind(fish_id)
     .fetch_one(&state.db)
@@ -447,25 +534,26 @@ async fn on_vote_retract(
             .await
             .unwrap_or_default();
 
-    let vote_update = serde_json::json!({
-        "fishId": data.fish_id,
-        "count": new_count,
-        "voters": voters
-    });
-    let _ = socket
-        .within(room.room_code)
-        .emit("vote:update", &vote_update);
+    let vote_update = VoteUpdateOutput {
+        fish_id: data.fish_id.clone(),
+        count: new_count,
+        voters: voters.clone(),
+        seq,
+    };
+    event_log::broadcast(
+        &state,
+        socket.within(room.room_code.clone()),
+        &room.room_code,
+        &vote_update,
+    )
+    .await;
 }
 
 /// 追击能力当前关闭：保留事件名以兼容旧前端，统一返回 `chase_disabled`。
-async fn on_vote_chase(socket: SocketRef, Data(data): Data<BattleVoteCastData>) {
+async fn on_vote_chase(socket: SocketRef, Data(data): Data<VoteInput>) {
     info!("[Socket.IO] Vote chase: {:?}", data);
 
-    let payload = serde_json::json!({
-        "reason": "chase_disabled",
-        "fishId": data.fish_id
-    });
-    let _ = socket.emit("vote:error", &payload);
+    emit_vote_error(&socket, "chase_disabled", &data.fish_id);
 }
 
 /// 检查游戏结束条件
@@ -529,17 +617,21 @@ async fn check_game_end(
         game_rules::human_eliminated_limit(human_total, state.config.human_eliminated_ratio);
 
     if stats.human_eliminated >= max_human_eliminated {
-        let defeat_data = serde_json::json!({
-            "reason": "too_many_human_killed",
-            "humanKilled": stats.human_eliminated,
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = socket
-            .within(room.room_code.clone())
-            .emit("game:defeat", &defeat_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let defeat_data = GameDefeatOutput {
+            reason: "too_many_human_killed".to_string(),
+            human_killed: Some(stats.human_eliminated),
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            socket.within(room.room_code.clone()),
+            &room.room_code,
+            &defeat_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -549,7 +641,7 @@ async fn check_game_end(
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update(socket, &updated_room);
+                emit_phase_update(socket, state, &updated_room).await;
             }
         }
         tracing::info!(
@@ -563,17 +655,21 @@ async fn check_game_end(
     let min_human_survive =
         game_rules::min_human_survive(human_total, state.config.victory_human_survive_ratio);
     if stats.ai_alive == 0 && stats.human_alive >= min_human_survive {
-        let victory_data = serde_json::json!({
-            "mvpId": "",
-            "mvpName": "Unknown",
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = socket
-            .within(room.room_code.clone())
-            .emit("game:victory", &victory_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let victory_data = GameVictoryOutput {
+            mvp_id: String::new(),
+            mvp_name: "Unknown".to_string(),
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            socket.within(room.room_code.clone()),
+            &room.room_code,
+            &victory_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -583,7 +679,7 @@ async fn check_game_end(
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update(socket, &updated_room);
+                emit_phase_update(socket, state, &updated_room).await;
             }
         }
         tracing::info!("[Game] Victory in room {}", room.room_code);
@@ -594,16 +690,21 @@ async fn check_game_end(
         stats.human_alive,
         state.config.ai_overflow_delta,
     ) {
-        let defeat_data = serde_json::json!({
-            "reason": "ai_overrun",
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = socket
-            .within(room.room_code.clone())
-            .emit("game:defeat", &defeat_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let defeat_data = GameDefeatOutput {
+            reason: "ai_overrun".to_string(),
+            human_killed: None,
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            socket.within(room.room_code.clone()),
+            &room.room_code,
+            &defeat_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -613,14 +714,19 @@ async fn check_game_end(
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update(socket, &updated_room);
+                emit_phase_update(socket, state, &updated_room).await;
             }
         }
         tracing::info!("[Game] Defeat: AI overrun in room {}", room.room_code);
     }
 }
 
-async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, broadcast_phase_update: bool) {
+async fn check_game_end_with_io(
+    io: &SocketIo,
+    state: &AppState,
+    room: &Room,
+    broadcast_phase_update: bool,
+) {
     let room = match sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
         .bind(room.id)
         .fetch_one(&state.db)
@@ -666,15 +772,21 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
         game_rules::human_eliminated_limit(human_total, state.config.human_eliminated_ratio);
 
     if stats.human_eliminated >= max_human_eliminated {
-        let defeat_data = serde_json::json!({
-            "reason": "too_many_human_killed",
-            "humanKilled": stats.human_eliminated,
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = io.within(room.room_code.clone()).emit("game:defeat", &defeat_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let defeat_data = GameDefeatOutput {
+            reason: "too_many_human_killed".to_string(),
+            human_killed: Some(stats.human_eliminated),
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            io.within(room.room_code.clone()),
+            &room.room_code,
+            &defeat_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -684,7 +796,7 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update_with_io(io, &updated_room);
+                emit_phase_update_with_io(io, state, &updated_room).await;
             }
         }
         tracing::info!(
@@ -698,15 +810,21 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
     let min_human_survive =
         game_rules::min_human_survive(human_total, state.config.victory_human_survive_ratio);
     if stats.ai_alive == 0 && stats.human_alive >= min_human_survive {
-        let victory_data = serde_json::json!({
-            "mvpId": "",
-            "mvpName": "Unknown",
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = io.within(room.room_code.clone()).emit("game:victory", &victory_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let victory_data = GameVictoryOutput {
+            mvp_id: String::new(),
+            mvp_name: "Unknown".to_string(),
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            io.within(room.room_code.clone()),
+            &room.room_code,
+            &victory_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -716,7 +834,7 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update_with_io(io, &updated_room);
+                emit_phase_update_with_io(io, state, &updated_room).await;
             }
         }
         tracing::info!("[Game] Victory in room {}", room.room_code);
@@ -725,14 +843,21 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
         stats.human_alive,
         state.config.ai_overflow_delta,
     ) {
-        let defeat_data = serde_json::json!({
-            "reason": "ai_overrun",
-            "aiRemaining": stats.ai_alive,
-            "humanRemaining": stats.human_alive,
-            "humanTotal": human_total
-        });
-        let _ = io.within(room.room_code.clone()).emit("game:defeat", &defeat_data);
-        let _ = sqlx::query("UPDATE rooms SET status = 'gameover' WHERE id = $1")
+        let defeat_data = GameDefeatOutput {
+            reason: "ai_overrun".to_string(),
+            human_killed: None,
+            ai_remaining: stats.ai_alive,
+            human_remaining: stats.human_alive,
+            human_total,
+        };
+        event_log::broadcast(
+            state,
+            io.within(room.room_code.clone()),
+            &room.room_code,
+            &defeat_data,
+        )
+        .await;
+        let _ = sqlx::query("UPDATE rooms SET status = 'gameover', ended_at = NOW() WHERE id = $1")
             .bind(room.id)
             .execute(&state.db)
             .await;
@@ -742,7 +867,7 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
             .await
         {
             if broadcast_phase_update {
-                emit_phase_update_with_io(io, &updated_room);
+                emit_phase_update_with_io(io, state, &updated_room).await;
             }
         }
         tracing::info!("[Game] Defeat: AI overrun in room {}", room.room_code);
@@ -750,61 +875,61 @@ async fn check_game_end_with_io(io: &SocketIo, state: &AppState, room: &Room, br
 }
 
 /// 添加评论
-async fn on_comment_add(socket: SocketRef, Data(data): Data<CommentAddData>) {
+async fn on_comment_add(
+    socket: SocketRef,
+    Data(mut data): Data<CommentAddData>,
+    state: SioState<Arc<AppState>>,
+) {
     debug!("[Socket.IO] Comment added to item {}", data.item_id);
 
+    if let Some(auth) = socket.extensions.get::<AuthSession>() {
+        let subject = Subject::User(auth.user_id);
+        if let Err(limited) = rate_limit::check(&state, RateAction::Comment, &subject).await {
+            let payload = CommentErrorOutput {
+                reason: "rate_limited".to_string(),
+                item_id: data.item_id.clone(),
+                retry_after_ms: Some(limited.retry_after_ms),
+            };
+            let _ = socket.emit(CommentErrorOutput::NAME, &payload);
+            return;
+        }
+    }
+
+    // 文本审核
+    let filter = &state.content_filter;
+    let author = filter.moderate(Field::CommentAuthor, &data.comment.author);
+    let content = filter.moderate(Field::CommentContent, &data.comment.content);
+    match (author, content) {
+        (Ok(_), Ok(content)) if content.is_empty() => return,
+        (Ok(author), Ok(content)) => {
+            data.comment.author = author;
+            data.comment.content = content;
+        }
+        _ => {
+            let payload = CommentErrorOutput {
+                reason: "blocked_content".to_string(),
+                item_id: data.item_id.clone(),
+                retry_after_ms: None,
+            };
+            let _ = socket.emit(CommentErrorOutput::NAME, &payload);
+            return;
+        }
+    }
+
     if let Some(session) = socket.extensions.get::<RoomSession>() {
         // 广播评论到房间内其他人
-        let _ = socket
-            .within(session.room_code.clone())
-            .except(socket.id)
-            .emit("comment:add", &data);
+        event_log::broadcast(
+            &state,
+            socket.within(session.room_code.clone()).except(socket.id),
+            &session.room_code,
+            &data,
+        )
+        .await;
     }
 }
 
 // === Helper Types ===
 
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub(crate) struct ConnectAuthData {
-    #[serde(default)]
-    token: Option<String>,
-    #[serde(default)]
-    authorization: Option<String>,
-}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RoomJoinData {
-    room_id: String,
-}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RoomLeaveData {
-    room_id: String,
-}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct BattleVoteCastData {
-    fish_id: String,
-}
-
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct CommentAddData {
-    item_id: String,
-    comment: CommentData,
-}
-
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct CommentData {
-    author: String,
-    content: String,
-}
-
 fn extract_auth_token(auth: &ConnectAuthData) -> Option<String> {
     if let Some(token) = auth
         .token
@@ -833,6 +958,34 @@ fn extract_auth_token(auth: &ConnectAuthData) -> Option<String> {
     }
 }
 
+fn emit_vote_error(socket: &SocketRef, reason: &str, fish_id: &str) {
+    let payload = VoteErrorOutput {
+        reason: reason.to_string(),
+        fish_id: fish_id.to_string(),
+        retry_after_ms: None,
+    };
+    let _ = socket.emit(VoteErrorOutput::NAME, &payload);
+}
+
+/// 投票限流（cast / retract 共用一个桶），超限时回 `vote:error`
+async fn vote_allowed(socket: &SocketRef, state: &AppState, fish_id: &str) -> bool {
+    let Some(auth) = socket.extensions.get::<AuthSession>() else {
+        return true;
+    };
+    match rate_limit::check(state, RateAction::Vote, &Subject::User(auth.user_id)).await {
+        Ok(()) => true,
+        Err(limited) => {
+            let payload = VoteErrorOutput {
+                reason: "rate_limited".to_string(),
+                fish_id: fish_id.to_string(),
+                retry_after_ms: Some(limited.retry_after_ms),
+            };
+            let _ = socket.emit(VoteErrorOutput::NAME, &payload);
+            false
+        }
+    }
+}
+
 fn authenticated_voter_id(socket: &SocketRef) -> Option<String> {
     socket
         .extensions
@@ -840,41 +993,6 @@ fn authenticated_voter_id(socket: &SocketRef) -> Option<String> {
         .map(|session| session.user_id.to_string())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{extract_auth_token, ConnectAuthData};
-
-    #[test]
-    fn extract_auth_token_prefers_token_field() {
-        let payload = ConnectAuthData {
-            token: Some("token-123".to_string()),
-            authorization: Some("Bearer ignored".to_string()),
-        };
-
-        assert_eq!(extract_auth_token(&payload), Some("token-123".to_string()));
-    }
-
-    #[test]
-    fn extract_auth_token_supports_bearer_authorization() {
-        let payload = ConnectAuthData {
-            token: None,
-            authorization: Some("Bearer token-xyz".to_string()),
-        };
-
-        assert_eq!(extract_auth_token(&payload), Some("token-xyz".to_string()));
-    }
-
-    #[test]
-    fn extract_auth_token_returns_none_when_missing() {
-        let payload = ConnectAuthData {
-            token: None,
-            authorization: None,
-        };
-
-        assert_eq!(extract_auth_token(&payload), None);
-    }
-}
-
 // === Helper Functions ===
 
 /// 获取房间初始状态 (sync:state)
@@ -895,6 +1013,11 @@ async fn get_room_state(state: &AppState, room_code: &str) -> Result<SyncStateDa
         .map_err(|_| ())?
         .ok_or(())?;
 
+    // 先读版本号再读鱼，之后的变化客户端重连时还能按增量取到
+    let seq = room_sync::current_seq(&state.db, room.id)
+        .await
+        .map_err(|_| ())?;
+
     let drawings: Vec<DrawingItemRow> = sqlx::query_as(
         r#"
         SELECT
@@ -939,45 +1062,47 @@ async fn get_room_state(state: &AppState, room_code: &str) -> Result<SyncStateDa
         voting_started_at: room.voting_started_at.map(|t| t.timestamp_millis()),
         voting_ends_at: room.voting_ends_at.map(|t| t.timestamp_millis()),
         server_time: Utc::now().timestamp_millis(),
+        seq,
+        event_seq: event_log::current_seq(state, room_code).await.unwrap_or(0),
         theme: theme_response,
         items,
     })
 }
 
-#[derive(Debug, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct PhaseUpdateData {
-    phase: String,
-    room_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    voting_started_at: Option<i64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    voting_ends_at: Option<i64>,
-    server_time: i64,
-}
-
-fn emit_phase_update(socket: &SocketRef, room: &Room) {
+async fn emit_phase_update(socket: &SocketRef, state: &AppState, room: &Room) {
     let payload = PhaseUpdateData {
         phase: room.status.clone(),
         room_id: room.room_code.clone(),
         voting_started_at: room.voting_started_at.map(|t| t.timestamp_millis()),
         voting_ends_at: room.voting_ends_at.map(|t| t.timestamp_millis()),
         server_time: Utc::now().timestamp_millis(),
+        seq: room.state_seq,
     };
-    let _ = socket
-        .within(room.room_code.clone())
-        .emit("phase:update", &payload);
+    event_log::broadcast(
+        state,
+        socket.within(room.room_code.clone()),
+        &room.room_code,
+        &payload,
+    )
+    .await;
 }
 
-fn emit_phase_update_with_io(io: &SocketIo, room: &Room) {
+async fn emit_phase_update_with_io(io: &SocketIo, state: &AppState, room: &Room) {
     let payload = PhaseUpdateData {
         phase: room.status.clone(),
         room_id: room.room_code.clone(),
         voting_started_at: room.voting_started_at.map(|t| t.timestamp_millis()),
         voting_ends_at: room.voting_ends_at.map(|t| t.timestamp_millis()),
         server_time: Utc::now().timestamp_millis(),
+        seq: room.state_seq,
     };
-    let _ = io.within(room.room_code.clone()).emit("phase:update", &payload);
+    event_log::broadcast(
+        state,
+        io.within(room.room_code.clone()),
+        &room.room_code,
+        &payload,
+    )
+    .await;
 }
 
 async fn phase_tick_by_room_code(
@@ -1066,7 +1191,7 @@ async fn phase_tick(socket: &SocketRef, state: &AppState, mut room: Room) -> Opt
     }
 
     if before_phase != room.status {
-        emit_phase_update(socket, &room);
+        emit_phase_update(socket, state, &room).await;
     }
 
     Some(room)
@@ -1144,106 +1269,40 @@ async fn update_online_count(state: &AppState, room_code: &str, delta: i32) {
     }
 }
 
-// === Response Types (camelCase) ===
-
-#[derive(Debug, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct SyncStateData {
-    phase: String,
-    room_id: String,
-    total_items: i32,
-    ai_count: i32,
-    turbidity: f64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    voting_started_at: Option<i64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    voting_ends_at: Option<i64>,
-    server_time: i64,
-    theme: ThemeResponse,
-    items: Vec<GameItemData>,
-}
+#[cfg(test)]
+mod tests {
+    use super::{extract_auth_token, ConnectAuthData};
 
-/// 前端 GameItem 格式
-#[derive(Debug, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GameItemData {
-    pub id: String,
-    #[serde(rename = "imageUrl")]
-    pub image_url: String,
-    pub name: String,
-    pub description: String,
-    pub author: String,
-    #[serde(rename = "isAI")]
-    pub is_ai: bool,
-    pub created_at: i64,
-    pub position: PositionData,
-    pub velocity: VelocityData,
-    pub rotation: f64,
-    pub scale: f64,
-    pub flip_x: bool,
-    pub comments: Vec<()>, // 暂时为空数组
-}
+    #[test]
+    fn extract_auth_token_prefers_token_field() {
+        let payload = ConnectAuthData {
+            token: Some("token-123".to_string()),
+            authorization: Some("Bearer ignored".to_string()),
+            protocol: None,
+        };
 
-#[derive(Debug, serde::Serialize)]
-pub struct PositionData {
-    pub x: f64,
-    pub y: f64,
-}
+        assert_eq!(extract_auth_token(&payload), Some("token-123".to_string()));
+    }
 
-#[derive(Debug, serde::Serialize)]
-pub struct VelocityData {
-    pub vx: f64,
-    pub vy: f64,
-}
+    #[test]
+    fn extract_auth_token_supports_bearer_authorization() {
+        let payload = ConnectAuthData {
+            token: None,
+            authorization: Some("Bearer token-xyz".to_string()),
+            protocol: None,
+        };
 
-impl From<Drawing> for GameItemData {
-    fn from(d: Drawing) -> Self {
-        Self {
-            id: d.id.to_string(),
-            image_url: drawing_image_url(d.id),
-            name: d.name,
-            description: d.description.unwrap_or_default(),
-            author: d.author_name, // 前端用 author
-            is_ai: d.is_ai,
-            created_at: d.created_at.timestamp_millis(),
-            position: PositionData {
-                x: d.position_x,
-                y: d.position_y,
-            },
-            velocity: VelocityData {
-                vx: d.velocity_x,
-                vy: d.velocity_y,
-            },
-            rotation: d.rotation,
-            scale: d.scale,
-            flip_x: d.flip_x,
-            comments: vec![],
-        }
+        assert_eq!(extract_auth_token(&payload), Some("token-xyz".to_string()));
     }
-}
 
-impl From<DrawingItemRow> for GameItemData {
-    fn from(d: DrawingItemRow) -> Self {
-        Self {
-            id: d.id.to_string(),
-            image_url: drawing_image_url(d.id),
-            name: d.name,
-            description: d.description.unwrap_or_default(),
-            author: d.author_name,
-            is_ai: d.is_ai,
-            created_at: d.created_at.timestamp_millis(),
-            position: PositionData {
-                x: d.position_x,
-                y: d.position_y,
-            },
-            velocity: VelocityData {
-                vx: d.velocity_x,
-                vy: d.velocity_y,
-            },
-            rotation: d.rotation,
-            scale: d.scale,
-            flip_x: d.flip_x,
-            comments: vec![],
-        }
+    #[test]
+    fn extract_auth_token_returns_none_when_missing() {
+        let payload = ConnectAuthData {
+            token: None,
+            authorization: None,
+            protocol: None,
+        };
+
+        assert_eq!(extract_auth_token(&payload), None);
     }
 }
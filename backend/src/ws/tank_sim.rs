@@ -0,0 +1,340 @@
+//! 服务端鱼缸模拟
+//!
+//! 有人在线的房间由服务端统一推进鱼的位置：坐标归一化到 `[0, 1]`，速度沿用客户端的单位，
+//! 即按 60 帧/秒计每帧移动的缸宽比例（`drawings.velocity_*` 入库时就是这个单位），
+//! 碰到左右边界反弹并更新 `flip_x`（向左游为 true），碰到上下边界反弹。
+//! 每 `TANK_SNAPSHOT_INTERVAL_MS` 推进一步并向房间广播 `tank:snapshot`，每隔若干步把位置写回
+//! `drawings.position_*` / `velocity_*` / `flip_x`，新加入的玩家和重启后都从最新布局开始。
+//!
+//! 鱼缸只在房间有变化时从数据库重新加载：房间的事件号（见 `event_log`）变了，说明有鱼加入、
+//! 淘汰或阶段切换；另外每次写回时全量对齐一次，兜住没有广播的改动（如管理员隐藏画作）。
+//!
+//! 模拟状态只在本进程内存里，假设后端单实例部署；多实例时每个实例都会推进并广播各自的快照，
+//! 需要改成只在一个实例上开启（其余实例设 `TANK_SNAPSHOT_INTERVAL_MS=0`）。
+//!
+//! 快照格式：`{"t": 服务器毫秒时间戳, "items": [[id, x, y, vx, vy, flipX], ...]}`
+
+use socketioxide::SocketIo;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::services::{ApiError, AppState};
+use crate::ws::event_log;
+use crate::ws::messages::{ServerEvent, SnapshotItem, TankSnapshot};
+
+/// 鱼可活动的范围（留出边距，避免贴边）
+const MIN_POS: f64 = 0.05;
+const MAX_POS: f64 = 0.95;
+
+/// 每隔多少步写回一次数据库（同时全量对齐鱼缸）
+const PERSIST_EVERY_TICKS: u64 = 20;
+
+/// 速度按客户端的帧率换算成每秒
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FishState {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub flip_x: bool,
+}
+
+/// 推进 `dt` 秒，越界时反弹
+pub fn step(fish: &mut FishState, dt: f64) {
+    let frames = dt * FRAMES_PER_SECOND;
+    fish.x += fish.vx * frames;
+    fish.y += fish.vy * frames;
+
+    if fish.x <= MIN_POS {
+        fish.x = MIN_POS + (MIN_POS - fish.x);
+        fish.vx = fish.vx.abs();
+    } else if fish.x >= MAX_POS {
+        fish.x = MAX_POS - (fish.x - MAX_POS);
+        fish.vx = -fish.vx.abs();
+    }
+    if fish.y <= MIN_POS {
+        fish.y = MIN_POS + (MIN_POS - fish.y);
+        fish.vy = fish.vy.abs();
+    } else if fish.y >= MAX_POS {
+        fish.y = MAX_POS - (fish.y - MAX_POS);
+        fish.vy = -fish.vy.abs();
+    }
+    fish.x = fish.x.clamp(MIN_POS, MAX_POS);
+    fish.y = fish.y.clamp(MIN_POS, MAX_POS);
+
+    if fish.vx != 0.0 {
+        fish.flip_x = fish.vx < 0.0;
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FishRow {
+    id: Uuid,
+    room_code: String,
+    position_x: f64,
+    position_y: f64,
+    velocity_x: f64,
+    velocity_y: f64,
+    flip_x: bool,
+}
+
+/// 一个房间的鱼缸；`seq` 是加载时房间的事件号
+#[derive(Default)]
+struct Tank {
+    seq: i64,
+    fish: HashMap<Uuid, FishState>,
+}
+
+fn round4(v: f64) -> f32 {
+    ((v * 10_000.0).round() / 10_000.0) as f32
+}
+
+/// 后台任务：按 `TANK_SNAPSHOT_INTERVAL_MS` 推进有人在线的房间并广播快照，0 表示关闭
+pub async fn start_tank_simulation(io: SocketIo, state: Arc<AppState>) {
+    let interval_ms = state.config.tank_snapshot_interval_ms;
+    if interval_ms == 0 {
+        tracing::info!("[TankSim] disabled");
+        return;
+    }
+
+    // 房间号 -> 鱼缸
+    let mut tanks: HashMap<String, Tank> = HashMap::new();
+    let mut ticker = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+    let mut last_tick = Instant::now();
+    let mut ticks: u64 = 0;
+    loop {
+        ticker.tick().await;
+        let dt = last_tick.elapsed().as_secs_f64();
+        last_tick = Instant::now();
+        ticks += 1;
+        let resync = ticks.is_multiple_of(PERSIST_EVERY_TICKS);
+
+        if let Err(err) = tick(&io, &state, &mut tanks, dt, resync).await {
+            tracing::warn!("[TankSim] tick failed: {:?}", err);
+            continue;
+        }
+        if resync {
+            if let Err(err) = persist(&state, &tanks).await {
+                tracing::warn!("[TankSim] persist failed: {}", err);
+            }
+        }
+    }
+}
+
+async fn tick(
+    io: &SocketIo,
+    state: &AppState,
+    tanks: &mut HashMap<String, Tank>,
+    dt: f64,
+    resync: bool,
+) -> Result<(), ApiError> {
+    let room_codes: Vec<String> = io
+        .rooms()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| r.to_string())
+        .collect();
+    tanks.retain(|code, _| room_codes.contains(code));
+    if room_codes.is_empty() {
+        return Ok(());
+    }
+
+    // 新房间、事件号变了的房间（或全量对齐时的所有房间）才重新加载
+    let seqs = event_log::current_seqs(state, &room_codes).await?;
+    let stale: Vec<(String, i64)> = room_codes
+        .into_iter()
+        .zip(seqs)
+        .filter(|(code, seq)| resync || tanks.get(code).is_none_or(|tank| tank.seq != *seq))
+        .collect();
+    if !stale.is_empty() {
+        reload(state, tanks, stale).await?;
+    }
+
+    let t = chrono::Utc::now().timestamp_millis();
+    for (room_code, tank) in tanks.iter_mut() {
+        if tank.fish.is_empty() {
+            continue;
+        }
+        let items = tank
+            .fish
+            .iter_mut()
+            .map(|(id, fish)| {
+                step(fish, dt);
+                SnapshotItem(
+                    *id,
+                    round4(fish.x),
+                    round4(fish.y),
+                    round4(fish.vx),
+                    round4(fish.vy),
+                    fish.flip_x,
+                )
+            })
+            .collect();
+        let _ = io
+            .within(room_code.clone())
+            .emit(TankSnapshot::NAME, &TankSnapshot { t, items });
+    }
+    Ok(())
+}
+
+/// 按数据库对齐这些房间的鱼：新鱼取库里的状态，已有的沿用内存状态，已淘汰/隐藏的移除
+async fn reload(
+    state: &AppState,
+    tanks: &mut HashMap<String, Tank>,
+    stale: Vec<(String, i64)>,
+) -> Result<(), sqlx::Error> {
+    let codes: Vec<&str> = stale.iter().map(|(code, _)| code.as_str()).collect();
+    let rows: Vec<FishRow> = sqlx::query_as(
+        r#"
+        SELECT d.id, r.room_code, d.position_x, d.position_y, d.velocity_x, d.velocity_y, d.flip_x
+        FROM drawings d
+        JOIN rooms r ON r.id = d.room_id
+        WHERE r.room_code = ANY($1)
+          AND r.status IN ('active', 'voting')
+          AND d.is_eliminated = FALSE
+          AND d.is_hidden = FALSE
+        "#,
+    )
+    .bind(&codes)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut next: HashMap<String, Tank> = stale
+        .into_iter()
+        .map(|(code, seq)| {
+            (
+                code,
+                Tank {
+                    seq,
+                    fish: HashMap::new(),
+                },
+            )
+        })
+        .collect();
+    for row in rows {
+        let fish = tanks
+            .get(&row.room_code)
+            .and_then(|tank| tank.fish.get(&row.id))
+            .copied()
+            .unwrap_or(FishState {
+                x: row.position_x.clamp(MIN_POS, MAX_POS),
+                y: row.position_y.clamp(MIN_POS, MAX_POS),
+                vx: row.velocity_x,
+                vy: row.velocity_y,
+                flip_x: row.flip_x,
+            });
+        if let Some(tank) = next.get_mut(&row.room_code) {
+            tank.fish.insert(row.id, fish);
+        }
+    }
+    tanks.extend(next);
+    Ok(())
+}
+
+async fn persist(state: &AppState, tanks: &HashMap<String, Tank>) -> Result<(), sqlx::Error> {
+    let fish: Vec<(&Uuid, &FishState)> = tanks.values().flat_map(|t| t.fish.iter()).collect();
+    if fish.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = fish.iter().map(|(id, _)| **id).collect();
+    let xs: Vec<f64> = fish.iter().map(|(_, f)| f.x).collect();
+    let ys: Vec<f64> = fish.iter().map(|(_, f)| f.y).collect();
+    let vxs: Vec<f64> = fish.iter().map(|(_, f)| f.vx).collect();
+    let vys: Vec<f64> = fish.iter().map(|(_, f)| f.vy).collect();
+    let flips: Vec<bool> = fish.iter().map(|(_, f)| f.flip_x).collect();
+
+    sqlx::query(
+        r#"
+        UPDATE drawings d
+        SET position_x = u.x, position_y = u.y, velocity_x = u.vx, velocity_y = u.vy, flip_x = u.flip
+        FROM UNNEST($1::uuid[], $2::float8[], $3::float8[], $4::float8[], $5::float8[], $6::bool[])
+            AS u(id, x, y, vx, vy, flip)
+        WHERE d.id = u.id
+        "#,
+    )
+    .bind(&ids)
+    .bind(&xs)
+    .bind(&ys)
+    .bind(&vxs)
+    .bind(&vys)
+    .bind(&flips)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f64 = 1.0 / FRAMES_PER_SECOND;
+
+    #[test]
+    fn velocity_is_per_client_frame() {
+        let mut fish = FishState {
+            x: 0.2,
+            y: 0.5,
+            vx: 0.005,
+            vy: 0.0,
+            flip_x: false,
+        };
+        step(&mut fish, 1.0);
+        assert!((fish.x - 0.5).abs() < 1e-9);
+        assert!(!fish.flip_x);
+    }
+
+    #[test]
+    fn bounces_off_walls_and_flips() {
+        let mut fish = FishState {
+            x: 0.9,
+            y: 0.5,
+            vx: 0.1,
+            vy: 0.0,
+            flip_x: false,
+        };
+        step(&mut fish, FRAME);
+        assert!((fish.x - 0.9).abs() < 1e-9);
+        assert!(fish.vx < 0.0);
+        assert!(fish.flip_x);
+
+        let mut fish = FishState {
+            x: 0.5,
+            y: 0.06,
+            vx: -0.01,
+            vy: -0.05,
+            flip_x: false,
+        };
+        step(&mut fish, FRAME);
+        assert!(fish.y >= MIN_POS && fish.vy > 0.0);
+        assert!(fish.flip_x);
+
+        // 一步跨越整个鱼缸也不会越界
+        let mut fish = FishState {
+            x: 0.5,
+            y: 0.5,
+            vx: 5.0,
+            vy: 5.0,
+            flip_x: false,
+        };
+        step(&mut fish, 1.0);
+        assert!((MIN_POS..=MAX_POS).contains(&fish.x));
+        assert!((MIN_POS..=MAX_POS).contains(&fish.y));
+    }
+
+    #[test]
+    fn snapshot_items_are_compact_arrays() {
+        let snapshot = TankSnapshot {
+            t: 1,
+            items: vec![SnapshotItem(Uuid::nil(), 0.5, 0.25, -0.02, 0.0, true)],
+        };
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            r#"{"t":1,"items":[["00000000-0000-0000-0000-000000000000",0.5,0.25,-0.02,0.0,true]]}"#
+        );
+    }
+}
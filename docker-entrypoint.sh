@@ -33,6 +33,14 @@ if [ ! -f "$PGDATA_DIR/PG_VERSION" ]; then
 else
     echo "[PostgreSQL] Using existing database at $PGDATA_DIR"
     chown -R postgres:postgres "$PGDATA_DIR"
+
+    # schema.sql 可重复执行，用来给旧库补列
+    echo "[PostgreSQL] Applying schema upgrades..."
+    su - postgres -c "/usr/lib/postgresql/15/bin/pg_ctl -D $PGDATA_DIR -l /tmp/pg_init.log start"
+    sleep 3
+    su - postgres -c "psql -v ON_ERROR_STOP=1 -d mimic -f /app/backend/schema.sql"
+    su - postgres -c "/usr/lib/postgresql/15/bin/pg_ctl -D $PGDATA_DIR stop"
+    sleep 2
 fi
 
 chown -R redis:redis "$REDIS_DIR" 2>/dev/null || true